@@ -21,15 +21,32 @@ typedef struct ExternError {
     char* message;
 } ExternError;
 
+/* The error codes returned by functions and set in ExternError.code.
+ * Since 3.0 a token or proof that fails to verify returns OBERON_ERROR_VERIFICATION_FAILED instead of 1 */
+#define OBERON_ERROR_INVALID_ENCODING 1
+#define OBERON_ERROR_INVALID_LENGTH 2
+#define OBERON_ERROR_INVALID_PUBLIC_KEY 3
+#define OBERON_ERROR_IDENTITY_POINT 4
+#define OBERON_ERROR_ZERO_HASH 5
+#define OBERON_ERROR_VERIFICATION_FAILED 6
+#define OBERON_ERROR_INVALID_PARAMETERS 7
+#define OBERON_ERROR_REVOKED 8
+#define OBERON_ERROR_EXPIRED 9
+#define OBERON_ERROR_REPLAYED 10
+#define OBERON_ERROR_UNKNOWN_KEY 11
+
 void oberon_string_free(char *s);
 void oberon_byte_buffer_free(struct ByteBuffer v);
 void oberon_create_proof_free(uint64_t handle, struct ExternError *err);
+void oberon_rebind_free(uint64_t handle, struct ExternError *err);
 
 int32_t oberon_secret_key_size(void);
 int32_t oberon_public_key_size(void);
 int32_t oberon_token_size(void);
 int32_t oberon_blinding_size(void);
 int32_t oberon_proof_size(void);
+int32_t oberon_challenge_key_size(void);
+int32_t oberon_challenge_size(void);
 
 int32_t oberon_new_secret_key(struct ByteBuffer secret_key);
 int32_t oberon_get_public_key(struct ByteArray secret_key, struct ByteBuffer *public_key, struct ExternError *err);
@@ -39,6 +56,10 @@ int32_t oberon_verify_token(struct ByteArray token, struct ByteArray public_key,
 int32_t oberon_create_blinding(struct ByteArray data, struct ByteBuffer *blinding);
 int32_t oberon_add_blinding(struct ByteArray old_token, struct ByteArray data, struct ByteBuffer *new_token, struct ExternError *err);
 int32_t oberon_remove_blinding(struct ByteArray old_token, struct ByteArray data, struct ByteBuffer *new_token, struct ExternError *err);
+uint64_t oberon_rebind_init(struct ExternError *err);
+int32_t oberon_rebind_add_old_blinding(uint64_t handle, struct ByteArray blinding, struct ExternError *err);
+int32_t oberon_rebind_add_new_blinding(uint64_t handle, struct ByteArray blinding, struct ExternError *err);
+int32_t oberon_rebind_finish(uint64_t handle, struct ByteArray old_token, struct ByteArray pk, struct ByteArray id, struct ByteBuffer *new_token, struct ExternError *err);
 uint64_t oberon_create_proof_init(struct ExternError *err);
 int32_t oberon_create_proof_set_token(uint64_t handle, struct ByteArray token, struct ExternError *err);
 int32_t oberon_create_proof_set_id(uint64_t handle, struct ByteArray id, struct ExternError *err);
@@ -46,5 +67,8 @@ int32_t oberon_create_proof_set_nonce(uint64_t handle, struct ByteArray nonce, s
 int32_t oberon_create_proof_add_blinding(uint64_t handle, struct ByteArray blinding, struct ExternError *err);
 int32_t oberon_create_proof_finish(uint64_t handle, struct ByteBuffer *proof, struct ExternError *err);
 int32_t oberon_verify_proof(struct ByteArray proof, struct ByteArray public_key, struct ByteArray id, struct ByteArray nonce, struct ExternError *err);
+int32_t oberon_new_challenge_key(struct ByteBuffer *key);
+int32_t oberon_new_challenge(struct ByteArray key, uint64_t now, struct ByteBuffer *challenge, struct ExternError *err);
+int32_t oberon_verify_challenge(struct ByteArray challenge, struct ByteArray key, uint64_t now, uint64_t ttl, struct ExternError *err);
 
 #endif
\ No newline at end of file
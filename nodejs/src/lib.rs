@@ -188,6 +188,57 @@ fn remove_blinding(mut cx: FunctionContext) -> JsResult<JsArrayBuffer> {
 }
 
 
+/// @param ArrayBuffer `token` - The token
+/// @param Array<ArrayBuffer> `old_blindings` - The blinding factors on the token
+/// @param Array<ArrayBuffer> `new_blindings` - The blinding factors to apply instead
+/// @param ArrayBuffer `public_key` - The verification key
+/// @param ArrayBuffer `id` - The identifier
+/// @returns ArrayBuffer `new_token` - The token with the new blinding factors,
+/// throws if the old blinding factors are wrong
+fn rebind(mut cx: FunctionContext) -> JsResult<JsArrayBuffer> {
+    let ja = JsArrayBuffer::new(&mut cx, 0)?;
+
+    let tk_buffer: Handle<JsArrayBuffer> = cx.argument(0)?;
+    let old_buffer: Vec<Handle<JsValue>> = obj_field_to_vec!(cx, 1);
+    let new_buffer: Vec<Handle<JsValue>> = obj_field_to_vec!(cx, 2);
+    let public_key_buffer: Handle<JsArrayBuffer> = cx.argument(3)?;
+    let id_buffer: Handle<JsArrayBuffer> = cx.argument(4)?;
+
+    let tk_bytes = tk_buffer.as_slice(&cx);
+    let pk_bytes = public_key_buffer.as_slice(&cx);
+    let id = id_buffer.as_slice(&cx).to_vec();
+
+    if tk_bytes.len() != Token::BYTES || pk_bytes.len() != PublicKey::BYTES {
+        return cx.throw(ja);
+    }
+    let tk_size = <[u8; Token::BYTES]>::try_from(tk_bytes).unwrap();
+    let pk_size = <[u8; PublicKey::BYTES]>::try_from(pk_bytes).unwrap();
+
+    let ct_tk = Token::from_bytes(&tk_size);
+    let ct_pk = PublicKey::from_bytes(&pk_size);
+
+    if (ct_tk.is_none() | ct_pk.is_none()).unwrap_u8() == 1u8 {
+        return cx.throw(ja);
+    }
+    let mut old = Vec::new();
+    for b in &old_buffer {
+        let a: Handle<JsArrayBuffer> = b.downcast_or_throw(&mut cx)?;
+        old.push(Blinding::new(a.as_slice(&cx)));
+    }
+    let mut new = Vec::new();
+    for b in &new_buffer {
+        let a: Handle<JsArrayBuffer> = b.downcast_or_throw(&mut cx)?;
+        new.push(Blinding::new(a.as_slice(&cx)));
+    }
+
+    let tk = ct_tk.unwrap();
+    match tk.rebind(&old, &new, ct_pk.unwrap(), &id) {
+        Err(_) => cx.throw(ja),
+        Ok(new_tk) => Ok(slice_to_js_array_buffer!(&new_tk.to_bytes(), cx))
+    }
+}
+
+
 /// @param ArrayBuffer `token` - The token
 /// @param ArrayBuffer `id` - The identifier
 /// @param Array<ArrayBuffer> `blindings` - The blinding factors
@@ -285,6 +336,7 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("verifyToken", verify_token)?;
     cx.export_function("addBlinding", add_blinding)?;
     cx.export_function("removeBlinding", remove_blinding)?;
+    cx.export_function("rebind", rebind)?;
     cx.export_function("createProof", create_proof)?;
     cx.export_function("verifyProof", verify_proof)?;
     Ok(())
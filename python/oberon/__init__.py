@@ -1,11 +1,15 @@
 """Oberon Python wrapper library"""
 
-from .classes import SecretKey, PublicKey, Token, Proof
+from .classes import SecretKey, PublicKey, Token, Proof, ChallengeKey
+from .error import OberonError, OberonErrorCode
 
 __all__ = [
     "SecretKey",
     "PublicKey",
     "Token",
-    "Proof"
+    "Proof",
+    "ChallengeKey",
+    "OberonError",
+    "OberonErrorCode"
 ]
 
@@ -19,6 +19,8 @@ from ctypes import (
 from ctypes.util import find_library
 from typing import Optional, Union
 
+from .error import OberonError
+
 LIB: CDLL = None
 
 class FfiByteBuffer(Structure):
@@ -151,6 +153,14 @@ def proof_size() -> int:
     return _get_size("oberon_proof_size")
 
 
+def challenge_key_size() -> int:
+    return _get_size("oberon_challenge_key_size")
+
+
+def challenge_size() -> int:
+    return _get_size("oberon_challenge_size")
+
+
 def _free_buffer(buffer: FfiByteBuffer):
     lib_fn = _get_func("oberon_byte_buffer_free")
     lib_fn(byref(buffer))
@@ -166,6 +176,11 @@ def _free_handle(handle: c_int64, err: FfiError):
     lib_fn(handle, byref(err))
 
 
+def _free_rebind_handle(handle: c_int64, err: FfiError):
+    lib_fn = _get_func("oberon_rebind_free")
+    lib_fn(handle, byref(err))
+
+
 def new_secret_key() -> bytes:
     buffer = FfiByteBuffer()
     lib_fn = _get_func("oberon_new_secret_key")
@@ -191,9 +206,9 @@ def get_public_key(secret_key: bytes) -> bytes:
         #_free_buffer(public_key)
         return out
     else:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
 
 def secret_key_from_seed(seed: bytes) -> bytes:
@@ -222,9 +237,9 @@ def new_token(secret_key: bytes, identifier: bytes) -> bytes:
         #_free_buffer(token)
         return out
     else:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
 
 def verify_token(token: bytes, public_key: bytes, identifier: bytes) -> bool:
@@ -238,9 +253,9 @@ def verify_token(token: bytes, public_key: bytes, identifier: bytes) -> bool:
     if result == 0:
         return True
     elif err.code != 0:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
     else:
         return False
 
@@ -259,9 +274,9 @@ def add_blinding(old_token: bytes, blinding: bytes) -> bytes:
         #_free_buffer(new)
         return out
     else:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
 
 def remove_blinding(old_token: bytes, blinding: bytes) -> bytes:
@@ -278,9 +293,52 @@ def remove_blinding(old_token: bytes, blinding: bytes) -> bytes:
         #_free_buffer(new)
         return out
     else:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
+        #_free_string(err)
+        raise OberonError(code, message)
+
+
+def rebind(old_token: bytes, old_blindings: list[bytes], new_blindings: list[bytes], public_key: bytes, identifier: bytes) -> bytes:
+    """Replace the blinding factors on the token after checking the old ones are correct."""
+    tk = _encode_bytes(old_token)
+    pk = _encode_bytes(public_key)
+    id = _encode_bytes(identifier)
+    err = FfiError()
+    lib_fn = _get_func("oberon_rebind_init")
+    lib_fn.restype = c_uint64
+
+    handle = lib_fn(byref(err))
+    if handle == 0:
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
+
+    handle = c_uint64(handle)
+
+    for fn_name, blinders in (("oberon_rebind_add_old_blinding", old_blindings),
+                              ("oberon_rebind_add_new_blinding", new_blindings)):
+        lib_fn = _get_func(fn_name)
+        for blinder in blinders:
+            b = _encode_bytes(blinder)
+            result = lib_fn(handle, b, byref(err))
+            if result != 0:
+                code, message = err.code, string_at(err.message)
+                #_free_string(err)
+                _free_rebind_handle(handle, err)
+                raise OberonError(code, message)
+
+    lib_fn = _get_func("oberon_rebind_finish")
+    new = FfiByteBuffer()
+    result = lib_fn(handle, tk, pk, id, byref(new), byref(err))
+    if result == 0:
+        out = _decode_bytes(new)
+        #_free_buffer(new)
+        return out
+    else:
+        code, message = err.code, string_at(err.message)
+        _free_rebind_handle(handle, err)
+        #_free_string(err)
+        raise OberonError(code, message)
 
 
 def create_proof(token: bytes, identifier: bytes, blindings: list[bytes], nonce: bytes) -> bytes:
@@ -293,45 +351,45 @@ def create_proof(token: bytes, identifier: bytes, blindings: list[bytes], nonce:
 
     handle = lib_fn(byref(err))
     if handle == 0:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
     handle = c_uint64(handle)
 
     lib_fn = _get_func("oberon_create_proof_set_token")
     result = lib_fn(handle, tk, byref(err))
     if result != 0:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
         _free_handle(handle, err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
     lib_fn = _get_func("oberon_create_proof_set_id")
     result = lib_fn(handle, id, byref(err))
     if result != 0:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
         _free_handle(handle, err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
     lib_fn = _get_func("oberon_create_proof_set_nonce")
     result = lib_fn(handle, n, byref(err))
     if result != 0:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
         _free_handle(handle, err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
     lib_fn = _get_func("oberon_create_proof_add_blinding")
     for blinder in blindings:
         b = _encode_bytes(blinder)
         result = lib_fn(handle, b, byref(err))
         if result != 0:
-            message = string_at(err.message)
+            code, message = err.code, string_at(err.message)
             #_free_string(err)
             _free_handle(handle, err)
-            raise Exception(message)
+            raise OberonError(code, message)
 
     lib_fn = _get_func("oberon_create_proof_finish")
     proof = FfiByteBuffer()
@@ -342,10 +400,10 @@ def create_proof(token: bytes, identifier: bytes, blindings: list[bytes], nonce:
         #_free_buffer(new)
         return out
     else:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
         _free_handle(handle, err)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
 
 
 def verify_proof(proof: bytes, public_key: bytes, identifier: bytes, nonce: bytes) -> bool:
@@ -360,9 +418,55 @@ def verify_proof(proof: bytes, public_key: bytes, identifier: bytes, nonce: byte
     if result == 0:
         return True
     elif err.code != 0:
-        message = string_at(err.message)
+        code, message = err.code, string_at(err.message)
+        #_free_string(err)
+        raise OberonError(code, message)
+    else:
+        return False
+
+
+def new_challenge_key() -> bytes:
+    buffer = FfiByteBuffer()
+    lib_fn = _get_func("oberon_new_challenge_key")
+    lib_fn(byref(buffer))
+
+    result = _decode_bytes(buffer)
+    #_free_buffer(buffer)
+    return result
+
+
+def new_challenge(key: bytes, now: int) -> bytes:
+    """Mint a challenge issued at `now` seconds since the unix epoch."""
+    k = _encode_bytes(key)
+    challenge = FfiByteBuffer()
+    err = FfiError()
+
+    lib_fn = _get_func("oberon_new_challenge")
+    result = lib_fn(k, c_uint64(now), byref(challenge), byref(err))
+    if result == 0:
+        out = _decode_bytes(challenge)
+        #_free_buffer(challenge)
+        return out
+    else:
+        code, message = err.code, string_at(err.message)
+        #_free_string(err)
+        raise OberonError(code, message)
+
+
+def verify_challenge(challenge: bytes, key: bytes, now: int, ttl: int) -> bool:
+    """Check the challenge was issued by this key within `ttl` seconds before `now`."""
+    c = _encode_bytes(challenge)
+    k = _encode_bytes(key)
+    err = FfiError()
+
+    lib_fn = _get_func("oberon_verify_challenge")
+    result = lib_fn(c, k, c_uint64(now), c_uint64(ttl), byref(err))
+    if result == 0:
+        return True
+    elif err.code != 0:
+        code, message = err.code, string_at(err.message)
         #_free_string(err)
-        raise Exception(message)
+        raise OberonError(code, message)
     else:
         return False
 
@@ -1,8 +1,9 @@
 from .bindings import secret_key_size, public_key_size, token_size, \
     proof_size, new_secret_key, \
     get_public_key, secret_key_from_seed, new_token, \
-    verify_token, add_blinding, remove_blinding, \
-    create_proof, verify_proof
+    verify_token, add_blinding, remove_blinding, rebind, \
+    create_proof, verify_proof, challenge_key_size, \
+    new_challenge_key, new_challenge, verify_challenge
 
 from typing import Optional, Union
 
@@ -69,6 +70,9 @@ class Token:
     def remove_blinding(self, blinder: bytes):
         return Token(remove_blinding(self.value, blinder))
 
+    def rebind(self, old_blinders: list[bytes], new_blinders: list[bytes], public_key: PublicKey, identifier: bytes):
+        return Token(rebind(self.value, old_blinders, new_blinders, public_key.value, identifier))
+
     def create_proof(self, identifier: bytes, blindings: list[bytes], nonce: bytes):
         return Proof(create_proof(self.value, identifier, blindings, nonce))
 
@@ -90,3 +94,24 @@ class Proof:
 
     def verify(self, identifier: bytes, public_key: PublicKey, nonce: bytes):
         return verify_proof(self.value, public_key.value, identifier, nonce)
+
+
+class ChallengeKey:
+    """A verifier key for minting proof nonces"""
+
+    def __init__(self, value: Optional[bytes] = None):
+        if value is None:
+            self.value = new_challenge_key()
+        elif len(value) == challenge_key_size():
+            self.value = value
+        else:
+            raise Exception("invalid challenge key size")
+
+    def __bytes__(self):
+        return self.value
+
+    def new_challenge(self, now: int) -> bytes:
+        return new_challenge(self.value, now)
+
+    def verify(self, challenge: bytes, now: int, ttl: int) -> bool:
+        return verify_challenge(challenge, self.value, now, ttl)
@@ -4,8 +4,17 @@ from enum import IntEnum
 
 class OberonErrorCode(IntEnum):
     SUCCESS = 0
-    INPUT = 1
-    SIGNING = 2
+    INVALID_ENCODING = 1
+    INVALID_LENGTH = 2
+    INVALID_PUBLIC_KEY = 3
+    IDENTITY_POINT = 4
+    ZERO_HASH = 5
+    VERIFICATION_FAILED = 6
+    INVALID_PARAMETERS = 7
+    REVOKED = 8
+    EXPIRED = 9
+    REPLAYED = 10
+    UNKNOWN_KEY = 11
     WRAPPER = 99
 
 class OberonError(Exception):
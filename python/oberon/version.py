@@ -1,3 +1,3 @@
 """Oberon library wrapper version."""
 
-__version__ = "1.2.1"
+__version__ = "2.0.0"
@@ -107,6 +107,40 @@ fn proof_verify(c: &mut Criterion) {
     });
 }
 
+fn prepared_token_verify(c: &mut Criterion) {
+    let mut id = [0u8; 16];
+    let mut sk_seed = [0u8; 16];
+    thread_rng().fill_bytes(&mut sk_seed);
+    thread_rng().fill_bytes(&mut id);
+    let sk = SecretKey::hash(&sk_seed);
+    let pk = PreparedPublicKey::new(PublicKey::from(&sk)).unwrap();
+    let token = sk.sign(&id).unwrap();
+    c.bench_function("prepared token verification", |b| {
+        b.iter(|| pk.verify_token(&token, id))
+    });
+}
+
+fn prepared_proof_verify(c: &mut Criterion) {
+    let (id, nonce, token, sk) = setup(&mut thread_rng());
+    let proof = Proof::new(&token, &[], id, &nonce, thread_rng()).unwrap();
+    let pk = PreparedPublicKey::new(PublicKey::from(&sk)).unwrap();
+    c.bench_function("prepared proof verification", |b| {
+        b.iter(|| pk.open(&proof, id, nonce))
+    });
+}
+
+fn id_digest_decode(c: &mut Criterion) {
+    let mut id = [0u8; 16];
+    thread_rng().fill_bytes(&mut id);
+    let bytes = IdDigest::new(id).unwrap().to_bytes();
+    c.bench_function("id digest from bytes", |b| {
+        b.iter(|| IdDigest::from_bytes(&bytes))
+    });
+    c.bench_function("id digest from bytes unchecked", |b| {
+        b.iter(|| IdDigest::from_bytes_unchecked(&bytes))
+    });
+}
+
 fn blinding_factor(c: &mut Criterion) {
     c.bench_function("Blinding factor length 1", |b| {
         b.iter(|| Blinding::new(&[1u8]))
@@ -129,7 +163,10 @@ criterion_group!(
     benches,
     signing,
     token_verify,
+    prepared_token_verify,
     proof_verify,
+    prepared_proof_verify,
+    id_digest_decode,
     xof_shift_rng,
     chacha_rng,
     os_rng,
@@ -0,0 +1,204 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Error, Proof, PublicKey, Token};
+use core::ops::Add;
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, CtOption};
+
+impl Add<&PublicKey> for &PublicKey {
+    type Output = PublicKey;
+
+    #[inline]
+    fn add(self, rhs: &PublicKey) -> PublicKey {
+        *self + *rhs
+    }
+}
+
+impl Add<&PublicKey> for PublicKey {
+    type Output = PublicKey;
+
+    #[inline]
+    fn add(self, rhs: &PublicKey) -> PublicKey {
+        self + *rhs
+    }
+}
+
+impl Add<PublicKey> for &PublicKey {
+    type Output = PublicKey;
+
+    #[inline]
+    fn add(self, rhs: PublicKey) -> PublicKey {
+        *self + rhs
+    }
+}
+
+impl Add<PublicKey> for PublicKey {
+    type Output = PublicKey;
+
+    #[inline]
+    fn add(self, rhs: PublicKey) -> PublicKey {
+        PublicKey {
+            w: self.w + rhs.w,
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl Add<&Token> for &Token {
+    type Output = Token;
+
+    #[inline]
+    fn add(self, rhs: &Token) -> Token {
+        Token(self.0 + rhs.0)
+    }
+}
+
+impl Add<&Token> for Token {
+    type Output = Token;
+
+    #[inline]
+    fn add(self, rhs: &Token) -> Token {
+        Token(self.0 + rhs.0)
+    }
+}
+
+impl Add<Token> for &Token {
+    type Output = Token;
+
+    #[inline]
+    fn add(self, rhs: Token) -> Token {
+        Token(self.0 + rhs.0)
+    }
+}
+
+impl Add<Token> for Token {
+    type Output = Token;
+
+    #[inline]
+    fn add(self, rhs: Token) -> Token {
+        Token(self.0 + rhs.0)
+    }
+}
+
+/// The sum of the public keys of issuers that all signed the same id.
+///
+/// Tokens issued for the same id by each issuer sum to a token
+/// valid under the aggregate key, so a single proof shows that
+/// every issuer enrolled the id. The component keys must come from
+/// trusted sources since anyone can choose a key that cancels another.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let hr = SecretKey::new(thread_rng());
+/// let security = SecretKey::new(thread_rng());
+/// let apk = AggregatePublicKey::new(&[PublicKey::from(&hr), PublicKey::from(&security)]).unwrap();
+///
+/// let id = b"test identity";
+/// let token = hr.sign(id).unwrap() + security.sign(id).unwrap();
+/// let proof = Proof::new(&token, &[], id, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open_aggregate(&apk, id, b"nonce").unwrap_u8(), 1u8);
+///
+/// // one issuer's token is not enough
+/// let token = hr.sign(id).unwrap();
+/// let proof = Proof::new(&token, &[], id, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open_aggregate(&apk, id, b"nonce").unwrap_u8(), 0u8);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AggregatePublicKey(pub(crate) PublicKey);
+
+impl From<AggregatePublicKey> for PublicKey {
+    fn from(apk: AggregatePublicKey) -> Self {
+        apk.0
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(AggregatePublicKey);
+
+try_from_bytes_impl!(AggregatePublicKey);
+
+impl AggregatePublicKey {
+    /// The number of bytes in an aggregate public key
+    pub const BYTES: usize = PublicKey::BYTES;
+
+    /// Aggregate the issuer public keys.
+    /// Fails if there are none, any is invalid or repeated,
+    /// or the sum is invalid
+    pub fn new(keys: &[PublicKey]) -> Option<Self> {
+        Self::try_new(keys).ok()
+    }
+
+    /// Aggregate the issuer public keys returning the reason on failure
+    pub fn try_new(keys: &[PublicKey]) -> Result<Self, Error> {
+        if keys.is_empty() {
+            return Err(Error::InvalidPublicKey);
+        }
+        for (i, pk) in keys.iter().enumerate() {
+            if pk.is_invalid().unwrap_u8() == 1 || keys[..i].contains(pk) {
+                return Err(Error::InvalidPublicKey);
+            }
+        }
+        let sum = keys[1..].iter().fold(keys[0], |acc, pk| acc + pk);
+        if sum.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        Ok(Self(sum))
+    }
+
+    /// The aggregated key as a regular public key
+    pub fn public_key(&self) -> PublicKey {
+        self.0
+    }
+
+    /// Is this aggregate public key invalid
+    pub fn is_invalid(&self) -> Choice {
+        self.0.is_invalid()
+    }
+
+    /// Verify that an aggregated token is valid
+    pub fn verify_token<B: AsRef<[u8]>>(&self, id: B, token: &Token) -> Choice {
+        token.verify(self.0, id)
+    }
+
+    /// Convert this aggregate public key into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_bytes()
+    }
+
+    /// Convert a byte sequence to an aggregate public key
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        match Option::<PublicKey>::from(PublicKey::from_bytes(data)) {
+            Some(pk) => CtOption::new(Self(pk), Choice::from(1u8)),
+            None => CtOption::new(Self::default(), Choice::from(0u8)),
+        }
+    }
+}
+
+impl Proof {
+    /// Check whether this proof is valid for a token aggregated
+    /// from every issuer in `apk`
+    pub fn open_aggregate<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        apk: &AggregatePublicKey,
+        id: B,
+        nonce: N,
+    ) -> Choice {
+        self.open(apk.0, id, nonce)
+    }
+
+    /// Check whether this proof is valid for a token aggregated
+    /// from every issuer in `apk` returning the reason on failure
+    pub fn try_open_aggregate<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        apk: &AggregatePublicKey,
+        id: B,
+        nonce: N,
+    ) -> Result<(), Error> {
+        self.try_open(apk.0, id, nonce)
+    }
+}
@@ -0,0 +1,211 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, IdDigest, Proof, PublicKey, Token};
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+use subtle::Choice;
+
+/// Verifies many proofs and tokens at once.
+///
+/// Each entry is a pairing equation e(L, R).e(Z, P) == 1.
+/// All entries are combined with random coefficients
+/// into a single multi-pairing with one final exponentiation.
+/// If the combined check fails, the entries are bisected
+/// to find the ones that are invalid.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let nonce = b"nonce";
+///
+/// let mut verifier = BatchVerifier::new();
+/// for id in [b"alice", b"bobby"] {
+///     let token = sk.sign(id).unwrap();
+///     let proof = Proof::new(&token, &[], id, nonce, thread_rng()).unwrap();
+///     verifier.add_proof(&proof, pk, id, nonce);
+/// }
+/// assert_eq!(verifier.verify(thread_rng()).unwrap_u8(), 1u8);
+/// assert!(verifier.failures(thread_rng()).is_empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BatchVerifier {
+    entries: Vec<Option<BatchEntry>>,
+}
+
+#[derive(Clone, Debug)]
+struct BatchEntry {
+    lhs: G1Projective,
+    rhs: G2Prepared,
+    z: G1Projective,
+}
+
+impl BatchVerifier {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of entries in the batch
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Is the batch empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add a proof to the batch with the same inputs as `Proof::open`.
+    /// Returns the index of the entry
+    pub fn add_proof<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &mut self,
+        proof: &Proof,
+        pk: PublicKey,
+        id: B,
+        nonce: N,
+    ) -> usize {
+        let entry = if (proof.u.is_identity() | proof.z.is_identity() | pk.is_invalid()).unwrap_u8()
+            == 1u8
+        {
+            None
+        } else {
+            IdDigest::new(id).map(|IdDigest { m, m_tick, a }| {
+                let t = Proof::challenge(proof.u, nonce.as_ref());
+                BatchEntry {
+                    lhs: a * t + proof.u,
+                    rhs: G2Prepared::from(pk.sum_of_products(m_tick, m).to_affine()),
+                    z: proof.z,
+                }
+            })
+        };
+        self.entries.push(entry);
+        self.entries.len() - 1
+    }
+
+    /// Add a token to the batch with the same inputs as `Token::verify`.
+    /// Returns the index of the entry
+    pub fn add_token<B: AsRef<[u8]>>(&mut self, token: &Token, pk: PublicKey, id: B) -> usize {
+        let entry = if pk.is_invalid().unwrap_u8() == 1u8 {
+            None
+        } else {
+            IdDigest::new(id).map(|IdDigest { m, m_tick, a }| BatchEntry {
+                lhs: a,
+                rhs: G2Prepared::from(pk.sum_of_products(m_tick, m).to_affine()),
+                z: -token.0,
+            })
+        };
+        self.entries.push(entry);
+        self.entries.len() - 1
+    }
+
+    /// Check whether every entry in the batch is valid
+    pub fn verify(&self, rng: impl RngCore + CryptoRng) -> Choice {
+        if self.entries.iter().any(|e| e.is_none()) {
+            return Choice::from(0u8);
+        }
+        let coefficients = self.coefficients(rng);
+        let indices: Vec<usize> = (0..self.entries.len()).collect();
+        Choice::from(self.check(&indices, &coefficients) as u8)
+    }
+
+    /// Return the indices of all invalid entries, empty if all are valid
+    pub fn failures(&self, rng: impl RngCore + CryptoRng) -> Vec<usize> {
+        let coefficients = self.coefficients(rng);
+        let mut failures = Vec::new();
+        let mut indices = Vec::with_capacity(self.entries.len());
+        for (i, entry) in self.entries.iter().enumerate() {
+            match entry {
+                None => failures.push(i),
+                Some(_) => indices.push(i),
+            }
+        }
+        self.bisect(&indices, &coefficients, &mut failures);
+        failures.sort_unstable();
+        failures
+    }
+
+    fn coefficients(&self, mut rng: impl RngCore + CryptoRng) -> Vec<Scalar> {
+        self.entries
+            .iter()
+            .map(|_| non_zero_scalar(&mut rng))
+            .collect()
+    }
+
+    fn bisect(&self, indices: &[usize], coefficients: &[Scalar], failures: &mut Vec<usize>) {
+        if indices.is_empty() || self.check(indices, coefficients) {
+            return;
+        }
+        if indices.len() == 1 {
+            failures.push(indices[0]);
+            return;
+        }
+        let (left, right) = indices.split_at(indices.len() / 2);
+        self.bisect(left, coefficients, failures);
+        self.bisect(right, coefficients, failures);
+    }
+
+    fn check(&self, indices: &[usize], coefficients: &[Scalar]) -> bool {
+        let mut z = G1Projective::IDENTITY;
+        let mut lhs = Vec::with_capacity(indices.len());
+        for i in indices {
+            // Only called with indices of valid entries
+            let entry = self.entries[*i].as_ref().unwrap();
+            lhs.push((entry.lhs * coefficients[*i]).to_affine());
+            z += entry.z * coefficients[*i];
+        }
+        let z = z.to_affine();
+        let generator = G2Prepared::from(G2Affine::generator());
+
+        let mut pairs = Vec::with_capacity(indices.len() + 1);
+        for (l, i) in lhs.iter().zip(indices) {
+            pairs.push((l, &self.entries[*i].as_ref().unwrap().rhs));
+        }
+        pairs.push((&z, &generator));
+
+        multi_miller_loop(&pairs)
+            .final_exponentiation()
+            .is_identity()
+            .unwrap_u8()
+            == 1
+    }
+}
+
+impl Proof {
+    /// Check many proofs at once returning the indices of the invalid ones.
+    /// An empty result means all proofs are valid.
+    ///
+    /// Each entry is (proof, public key, id, nonce) as used by `open`
+    pub fn batch_open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        entries: &[(Proof, PublicKey, B, N)],
+        rng: impl RngCore + CryptoRng,
+    ) -> Vec<usize> {
+        let mut verifier = BatchVerifier::new();
+        for (proof, pk, id, nonce) in entries {
+            verifier.add_proof(proof, *pk, id, nonce);
+        }
+        verifier.failures(rng)
+    }
+}
+
+impl Token {
+    /// Check many tokens at once returning the indices of the invalid ones.
+    /// An empty result means all tokens are valid.
+    ///
+    /// Each entry is (token, public key, id) as used by `verify`
+    pub fn batch_verify<B: AsRef<[u8]>>(
+        entries: &[(Token, PublicKey, B)],
+        rng: impl RngCore + CryptoRng,
+    ) -> Vec<usize> {
+        let mut verifier = BatchVerifier::new();
+        for (token, pk, id) in entries {
+            verifier.add_token(token, *pk, id);
+        }
+        verifier.failures(rng)
+    }
+}
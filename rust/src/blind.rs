@@ -0,0 +1,342 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Error, IdDigest, PublicKey, SecretKey, Token};
+use core::convert::TryFrom;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::ZeroizeOnDrop;
+
+const BLIND_REQUEST_DST: &[u8] = b"OBERON_BLIND_ISSUANCE_REQUEST_";
+
+/// A request for a token on an identity the issuer never sees.
+///
+/// The user sends m' = H_s(m), U = r.H_G(m') and M = m.U with a
+/// Schnorr proof of knowledge of r and m. The issuer hashes m' to the
+/// curve itself so it only signs multiples of H_G(m') and never a base
+/// chosen by the user. The issuer learns m' but not the identity, so
+/// an identity from a small set can still be found by hashing each one.
+///
+/// Nothing shows m' = H_s(m), so the issuer signs each m' at most once.
+/// Two signatures on one m' with different m would reveal y.H_G(m').
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+/// use std::collections::BTreeSet;
+///
+/// struct Log(BTreeSet<[u8; 32]>);
+///
+/// impl BlindIssuanceLog for Log {
+///     fn insert(&mut self, m_tick: &[u8; 32]) -> bool {
+///         self.0.insert(*m_tick)
+///     }
+/// }
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"private identity";
+/// let mut log = Log(BTreeSet::new());
+///
+/// let (request, secrets) = BlindTokenRequest::new(id, thread_rng()).unwrap();
+/// // sent to the issuer
+/// let response = sk.blind_sign(&request, &mut log).unwrap();
+/// // sent back to the user
+/// let token = response.unblind(&secrets, pk, id).unwrap();
+/// assert_eq!(token.verify(pk, id).unwrap_u8(), 1u8);
+///
+/// // the same id cannot be signed again
+/// let (request, _) = BlindTokenRequest::new(id, thread_rng()).unwrap();
+/// assert_eq!(sk.blind_sign(&request, &mut log).unwrap_err(), Error::Replayed);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BlindTokenRequest {
+    pub(crate) m_tick: Scalar,
+    pub(crate) u: G1Projective,
+    pub(crate) m_u: G1Projective,
+    pub(crate) challenge: Scalar,
+    pub(crate) s_r: Scalar,
+    pub(crate) s_m: Scalar,
+}
+
+/// The value the user keeps to unblind the issuer's response
+/// Display is not implemented to prevent accidental leak of the secrets
+#[derive(Clone, Debug, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct BlindTokenSecrets {
+    pub(crate) r: Scalar,
+}
+
+/// The issuer's signature on the blinded base
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BlindTokenResponse {
+    pub(crate) s: G1Projective,
+}
+
+try_from_bytes_impl!(BlindTokenRequest);
+
+impl BlindTokenRequest {
+    /// The number of bytes in a blind token request
+    pub const BYTES: usize = 224;
+
+    /// Create a request for a token on `id`
+    pub fn new<B: AsRef<[u8]>>(
+        id: B,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(Self, BlindTokenSecrets), Error> {
+        Ok(Self::new_with_digest(&IdDigest::try_new(id)?, rng))
+    }
+
+    /// Create a request from an id digest
+    pub(crate) fn new_with_digest(
+        digest: &IdDigest,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> (Self, BlindTokenSecrets) {
+        let r = non_zero_scalar(&mut rng);
+        let u = digest.a * r;
+        let m_u = u * digest.m;
+
+        let k_r = Scalar::random(&mut rng);
+        let k_m = Scalar::random(&mut rng);
+        let t_u = digest.a * k_r;
+        let t_m = u * k_m;
+        let challenge = request_challenge(digest.m_tick, u, m_u, t_u, t_m);
+
+        let request = Self {
+            m_tick: digest.m_tick,
+            u,
+            m_u,
+            challenge,
+            s_r: k_r + challenge * r,
+            s_m: k_m + challenge * digest.m,
+        };
+        (request, BlindTokenSecrets { r })
+    }
+
+    /// Check whether the commitments are well formed
+    pub fn verify(&self) -> Choice {
+        let a = hash_to_curve(&self.m_tick.to_le_bytes()[..]);
+        let t_u = a * self.s_r - self.u * self.challenge;
+        let t_m = self.u * self.s_m - self.m_u * self.challenge;
+        let challenge = request_challenge(self.m_tick, self.u, self.m_u, t_u, t_m);
+        !(self.m_tick.is_zero() | self.u.is_identity() | self.m_u.is_identity())
+            & challenge.ct_eq(&self.challenge)
+    }
+
+    /// Convert this request into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..32].copy_from_slice(&self.m_tick.to_le_bytes());
+        out[32..80].copy_from_slice(&self.u.to_affine().to_compressed());
+        out[80..128].copy_from_slice(&self.m_u.to_affine().to_compressed());
+        out[128..160].copy_from_slice(&self.challenge.to_le_bytes());
+        out[160..192].copy_from_slice(&self.s_r.to_le_bytes());
+        out[192..].copy_from_slice(&self.s_m.to_le_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into a request
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let point = |i: usize| {
+            G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[i..i + 48]).unwrap())
+                .map(G1Projective::from)
+        };
+        let scalar =
+            |i: usize| Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[i..i + 32]).unwrap());
+        let (m_tick, u, m_u) = (scalar(0), point(32), point(80));
+        let (challenge, s_r, s_m) = (scalar(128), scalar(160), scalar(192));
+        let valid = m_tick.is_some()
+            & u.is_some()
+            & m_u.is_some()
+            & challenge.is_some()
+            & s_r.is_some()
+            & s_m.is_some();
+        CtOption::new(
+            Self {
+                m_tick: m_tick.unwrap_or(Scalar::ZERO),
+                u: u.unwrap_or(G1Projective::IDENTITY),
+                m_u: m_u.unwrap_or(G1Projective::IDENTITY),
+                challenge: challenge.unwrap_or(Scalar::ZERO),
+                s_r: s_r.unwrap_or(Scalar::ZERO),
+                s_m: s_m.unwrap_or(Scalar::ZERO),
+            },
+            valid,
+        )
+    }
+}
+
+try_from_bytes_impl!(BlindTokenResponse);
+
+impl BlindTokenResponse {
+    /// The number of bytes in a blind token response
+    pub const BYTES: usize = 48;
+
+    /// Remove the blinding to get a token and check it is valid
+    /// for `pk` and `id`
+    pub fn unblind<B: AsRef<[u8]>>(
+        &self,
+        secrets: &BlindTokenSecrets,
+        pk: PublicKey,
+        id: B,
+    ) -> Result<Token, Error> {
+        let r_inv = Option::<Scalar>::from(secrets.r.invert()).ok_or(Error::InvalidParameters)?;
+        let sigma = self.s * r_inv;
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let token = Token(sigma);
+        token.try_verify(pk, id)?;
+        Ok(token)
+    }
+
+    /// Convert this response into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.s.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence into a response
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data).map(|p| Self {
+            s: G1Projective::from(p),
+        })
+    }
+}
+
+/// Records each m' an issuer has blind signed so it is signed only once.
+/// The log must be durable and shared by everything signing with the key.
+/// Ids signed with `SecretKey::sign` should be recorded too, e.g. with
+/// `IdDigest::hashed_id_scalar`
+pub trait BlindIssuanceLog {
+    /// Record the encoded m' of a request.
+    /// Returns false if it was already recorded
+    fn insert(&mut self, m_tick: &[u8; 32]) -> bool;
+}
+
+impl SecretKey {
+    /// Sign a blind token request without learning the identity.
+    /// Returns `Error::VerificationFailed` for a request whose commitments
+    /// are not well formed and `Error::Replayed` if `log` already has its m'
+    pub fn blind_sign<L: BlindIssuanceLog>(
+        &self,
+        request: &BlindTokenRequest,
+        log: &mut L,
+    ) -> Result<BlindTokenResponse, Error> {
+        if request.verify().unwrap_u8() == 0 {
+            return Err(Error::VerificationFailed);
+        }
+        if !log.insert(&request.m_tick.to_le_bytes()) {
+            return Err(Error::Replayed);
+        }
+        let s = request.u * (self.x + self.w * request.m_tick) + request.m_u * self.y;
+        if s.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(BlindTokenResponse { s })
+    }
+}
+
+fn request_challenge(
+    m_tick: Scalar,
+    u: G1Projective,
+    m_u: G1Projective,
+    t_u: G1Projective,
+    t_m: G1Projective,
+) -> Scalar {
+    hash_to_scalar(&[
+        BLIND_REQUEST_DST,
+        &m_tick.to_le_bytes(),
+        &u.to_affine().to_compressed(),
+        &m_u.to_affine().to_compressed(),
+        &t_u.to_affine().to_compressed(),
+        &t_m.to_affine().to_compressed(),
+    ])
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod log {
+    use super::BlindIssuanceLog;
+    use alloc::collections::BTreeSet;
+
+    /// An in-memory blind issuance log
+    #[derive(Clone, Debug, Default)]
+    pub struct MemoryBlindIssuanceLog {
+        entries: BTreeSet<[u8; 32]>,
+    }
+
+    impl MemoryBlindIssuanceLog {
+        /// Create an empty log
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// The number of m' recorded
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        /// Is the log empty
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+    }
+
+    impl BlindIssuanceLog for MemoryBlindIssuanceLog {
+        fn insert(&mut self, m_tick: &[u8; 32]) -> bool {
+            self.entries.insert(*m_tick)
+        }
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use log::*;
+
+#[test]
+fn mismatched_blind_token() {
+    use crate::Proof;
+
+    struct OnceLog(Option<[u8; 32]>);
+
+    impl BlindIssuanceLog for OnceLog {
+        fn insert(&mut self, m_tick: &[u8; 32]) -> bool {
+            self.0.replace(*m_tick).is_none()
+        }
+    }
+
+    let sk = SecretKey::new(rand::thread_rng());
+    let pk = PublicKey::from(&sk);
+    let own = IdDigest::new(b"own identity").unwrap();
+    let victim = IdDigest::new(b"victim identity").unwrap();
+    // m' from one id and m from another
+    let mixed = IdDigest {
+        m: victim.m,
+        m_tick: own.m_tick,
+        a: own.a,
+    };
+    let mut log = OnceLog(None);
+
+    let (request, secrets) = BlindTokenRequest::new_with_digest(&mixed, rand::thread_rng());
+    let response = sk.blind_sign(&request, &mut log).unwrap();
+    let token = Token(response.s * secrets.r.invert().unwrap());
+    assert_eq!(token.verify_with_digest(pk, &mixed).unwrap_u8(), 1u8);
+
+    // it is not a token for either id
+    assert_eq!(
+        response.unblind(&secrets, pk, b"victim identity"),
+        Err(Error::VerificationFailed)
+    );
+    for id in [&b"victim identity"[..], &b"own identity"[..]] {
+        assert_eq!(token.verify(pk, id).unwrap_u8(), 0u8);
+        let proof = Proof::new(&token, &[], id, b"nonce", rand::thread_rng()).unwrap();
+        assert_eq!(proof.open(pk, id, b"nonce").unwrap_u8(), 0u8);
+    }
+
+    // a second signature on m' would reveal y.A
+    let (request, _) = BlindTokenRequest::new_with_digest(&own, rand::thread_rng());
+    assert_eq!(
+        sk.blind_sign(&request, &mut log).unwrap_err(),
+        Error::Replayed
+    );
+}
@@ -31,6 +31,8 @@ impl Default for Blinding {
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(Blinding);
 
+try_from_bytes_impl!(Blinding);
+
 impl Blinding {
     /// The number of bytes in a blinding factor
     pub const BYTES: usize = 48;
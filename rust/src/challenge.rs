@@ -0,0 +1,304 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Error, Proof, PublicKey};
+use core::convert::TryFrom;
+use digest::{ExtendableOutput, Update, XofReader};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::Shake256;
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::ZeroizeOnDrop;
+
+const CHALLENGE_DST: &[u8] = b"OBERON_CHALLENGE_MAC_SHAKE-256_";
+
+/// The verifier's secret key for minting and checking challenges.
+/// Display is not implemented to prevent accidental leak of the key
+///
+/// Challenges are stateless: the verifier only needs the key
+/// and the current time to check one it issued earlier.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let token = sk.sign(b"test identity").unwrap();
+///
+/// let key = ChallengeKey::new(thread_rng());
+/// let challenge = key.issue(1_700_000_000, thread_rng());
+///
+/// // the prover uses the challenge as the nonce
+/// let proof = Proof::new(&token, &[], b"test identity", challenge.to_bytes(), thread_rng()).unwrap();
+///
+/// let result = proof.open_with_challenge(pk, b"test identity", &challenge, &key, 1_700_000_030, 60);
+/// assert!(result.is_ok());
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct ChallengeKey([u8; 32]);
+
+impl PartialEq for ChallengeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for ChallengeKey {}
+
+impl ConstantTimeEq for ChallengeKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(ChallengeKey);
+
+try_from_bytes_impl!(ChallengeKey);
+
+impl ChallengeKey {
+    /// The number of bytes in a challenge key
+    pub const BYTES: usize = 32;
+
+    /// Generate a new random key
+    pub fn new(mut rng: impl RngCore + CryptoRng) -> Self {
+        let mut key = [0u8; Self::BYTES];
+        rng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// Mint a new challenge issued at `now` in seconds since the unix epoch
+    pub fn issue(&self, now: u64, mut rng: impl RngCore + CryptoRng) -> Challenge {
+        let mut value = [0u8; 16];
+        rng.fill_bytes(&mut value);
+        Challenge {
+            issued_at: now,
+            value,
+            tag: self.tag(now, &value),
+        }
+    }
+
+    /// Check the challenge was issued by this key and `now`
+    /// is within `ttl` seconds after when it was issued.
+    /// Returns `Error::VerificationFailed` for a forged challenge
+    /// and `Error::Expired` for a stale one or one issued after `now`,
+    /// so verifiers that share a key need synchronized clocks
+    pub fn check(&self, challenge: &Challenge, now: u64, ttl: u64) -> Result<(), Error> {
+        let tag = self.tag(challenge.issued_at, &challenge.value);
+        if tag.ct_eq(&challenge.tag).unwrap_u8() == 0 {
+            return Err(Error::VerificationFailed);
+        }
+        if challenge.issued_at > now || now - challenge.issued_at > ttl {
+            return Err(Error::Expired);
+        }
+        Ok(())
+    }
+
+    /// Check the challenge like `check` and record it in `cache`.
+    /// Returns `Error::Replayed` if it was already used.
+    ///
+    /// Call this only after the proof made with the challenge verified,
+    /// otherwise anyone who sees the challenge can spend it with a bad proof.
+    /// `Proof::open_once` does both in the right order
+    pub fn check_once<R: ReplayCache>(
+        &self,
+        challenge: &Challenge,
+        now: u64,
+        ttl: u64,
+        cache: &mut R,
+    ) -> Result<(), Error> {
+        self.check(challenge, now, ttl)?;
+        record(challenge, now, ttl, cache)
+    }
+
+    /// Convert this key into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0
+    }
+
+    /// Convert a byte sequence into a key
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        CtOption::new(Self(*data), Choice::from(1u8))
+    }
+
+    fn tag(&self, issued_at: u64, value: &[u8; 16]) -> [u8; 32] {
+        let mut hasher = Shake256::default();
+        hasher.update(CHALLENGE_DST);
+        hasher.update(&self.0);
+        hasher.update(&issued_at.to_be_bytes());
+        hasher.update(value);
+        let mut reader = hasher.finalize_xof();
+        let mut tag = [0u8; 32];
+        reader.read(&mut tag);
+        tag
+    }
+}
+
+/// A nonce minted by a verifier that encodes when it was issued,
+/// a random value and a MAC over both
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Challenge {
+    issued_at: u64,
+    value: [u8; 16],
+    tag: [u8; 32],
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(Challenge);
+
+try_from_bytes_impl!(Challenge);
+
+impl Challenge {
+    /// The number of bytes in a challenge
+    pub const BYTES: usize = 56;
+
+    /// When the challenge was issued in seconds since the unix epoch
+    pub fn issued_at(&self) -> u64 {
+        self.issued_at
+    }
+
+    /// Convert this challenge into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..8].copy_from_slice(&self.issued_at.to_be_bytes());
+        out[8..24].copy_from_slice(&self.value);
+        out[24..].copy_from_slice(&self.tag);
+        out
+    }
+
+    /// Convert a byte sequence into a challenge.
+    /// The MAC is only checked by `ChallengeKey::check`
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        CtOption::new(
+            Self {
+                issued_at: u64::from_be_bytes(<[u8; 8]>::try_from(&data[..8]).unwrap()),
+                value: <[u8; 16]>::try_from(&data[8..24]).unwrap(),
+                tag: <[u8; 32]>::try_from(&data[24..]).unwrap(),
+            },
+            Choice::from(1u8),
+        )
+    }
+}
+
+/// Remembers challenges that have been used so they
+/// cannot be replayed before they expire
+pub trait ReplayCache {
+    /// Record a challenge that is valid until `expires_at`.
+    /// Returns false if it was already recorded
+    fn insert(&mut self, challenge: &Challenge, expires_at: u64) -> bool;
+
+    /// Forget challenges that expired before `now`
+    fn prune(&mut self, _now: u64) {}
+}
+
+impl Proof {
+    /// Check the challenge is authentic and fresh and this proof
+    /// is valid using the challenge as the nonce
+    pub fn open_with_challenge<B: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        challenge: &Challenge,
+        key: &ChallengeKey,
+        now: u64,
+        ttl: u64,
+    ) -> Result<(), Error> {
+        key.check(challenge, now, ttl)?;
+        self.try_open(pk, id, challenge.to_bytes())
+    }
+
+    /// Check the challenge and this proof like `open_with_challenge`
+    /// and then record the challenge in `cache`.
+    /// Returns `Error::Replayed` if it was already used.
+    /// The challenge is only recorded once the proof is valid
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_once<B: AsRef<[u8]>, R: ReplayCache>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        challenge: &Challenge,
+        key: &ChallengeKey,
+        now: u64,
+        ttl: u64,
+        cache: &mut R,
+    ) -> Result<(), Error> {
+        self.open_with_challenge(pk, id, challenge, key, now, ttl)?;
+        record(challenge, now, ttl, cache)
+    }
+}
+
+fn record<R: ReplayCache>(
+    challenge: &Challenge,
+    now: u64,
+    ttl: u64,
+    cache: &mut R,
+) -> Result<(), Error> {
+    cache.prune(now);
+    if cache.insert(challenge, challenge.issued_at.saturating_add(ttl)) {
+        Ok(())
+    } else {
+        Err(Error::Replayed)
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod cache {
+    use super::{Challenge, ReplayCache};
+    use alloc::collections::BTreeMap;
+
+    /// An in-memory replay cache
+    ///
+    /// ```
+    /// use oberon::*;
+    /// use rand::thread_rng;
+    ///
+    /// let key = ChallengeKey::new(thread_rng());
+    /// let challenge = key.issue(1_700_000_000, thread_rng());
+    /// let mut cache = MemoryReplayCache::new();
+    ///
+    /// assert!(key.check_once(&challenge, 1_700_000_010, 60, &mut cache).is_ok());
+    /// assert_eq!(key.check_once(&challenge, 1_700_000_020, 60, &mut cache), Err(Error::Replayed));
+    /// ```
+    #[derive(Clone, Debug, Default)]
+    pub struct MemoryReplayCache {
+        entries: BTreeMap<[u8; Challenge::BYTES], u64>,
+    }
+
+    impl MemoryReplayCache {
+        /// Create an empty cache
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// The number of challenges held
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        /// Is the cache empty
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+    }
+
+    impl ReplayCache for MemoryReplayCache {
+        fn insert(&mut self, challenge: &Challenge, expires_at: u64) -> bool {
+            let key = challenge.to_bytes();
+            if self.entries.contains_key(&key) {
+                return false;
+            }
+            self.entries.insert(key, expires_at);
+            true
+        }
+
+        fn prune(&mut self, now: u64) {
+            self.entries.retain(|_, expires_at| *expires_at >= now);
+        }
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use cache::*;
@@ -0,0 +1,253 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Blinding, Error, IdDigest, Proof, PublicKey, Token};
+use core::convert::TryFrom;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::ZeroizeOnDrop;
+
+/// The server's share of a token split between a device and a server
+/// Display is not implemented to prevent accidental leak of the share
+///
+/// The token is split as s = s_d + k.A where the device keeps s_d and
+/// the server keeps k. Neither share verifies or proves on its own
+/// and the two jointly create a normal `Proof` so the server can
+/// count attempts and disable a lost device by deleting its share.
+///
+/// The device sends U_d = r_d.A and D = p.s_d for random r_d and p.
+/// The server picks r_s and returns R = r_s.A, W = r_s.D and
+/// K = -k.(U + t.A) where U = U_d + R and t = H(U || nonce).
+/// The device computes Z = -(r_d + t).s_d - p^-1.W + K which is
+/// -(r + t).s for r = r_d + r_s, the same as `Proof::new`.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"test identity";
+/// let token = sk.sign(id).unwrap();
+/// let (device_share, server_share) = token.split(id, thread_rng()).unwrap();
+///
+/// let (request, secrets) = CoProofRequest::new(&device_share, &[], id, thread_rng()).unwrap();
+/// // sent to the server with the nonce
+/// let response = server_share.respond(&request, id, b"nonce", thread_rng()).unwrap();
+/// // sent back to the device
+/// let proof = response.finish(&secrets, pk, id, b"nonce").unwrap();
+/// assert_eq!(proof.open(pk, id, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct ServerTokenShare(Scalar);
+
+impl Default for ServerTokenShare {
+    fn default() -> Self {
+        Self(Scalar::ZERO)
+    }
+}
+
+impl PartialEq for ServerTokenShare {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for ServerTokenShare {}
+
+impl ConstantTimeEq for ServerTokenShare {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+try_from_bytes_impl!(ServerTokenShare);
+
+impl ServerTokenShare {
+    /// The number of bytes in a server token share
+    pub const BYTES: usize = 32;
+
+    /// Answer a device's request to prove the token for `id` with `nonce`
+    pub fn respond<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        request: &CoProofRequest,
+        id: B,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<CoProofResponse, Error> {
+        if (self.0.is_zero() | request.u.is_identity() | request.d.is_identity()).unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let a = IdDigest::try_new(id)?.a;
+        let r = non_zero_scalar(rng);
+        let big_r = a * r;
+        let u = request.u + big_r;
+        if u.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let t = Proof::challenge(u, nonce.as_ref());
+        Ok(CoProofResponse {
+            r: big_r,
+            w: request.d * r,
+            k: -((u + a * t) * self.0),
+        })
+    }
+
+    /// Convert this share into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_le_bytes()
+    }
+
+    /// Convert a byte sequence into a share
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        Scalar::from_le_bytes(data).map(Self)
+    }
+}
+
+/// The device's first message to start a joint proof
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CoProofRequest {
+    pub(crate) u: G1Projective,
+    pub(crate) d: G1Projective,
+}
+
+/// The values the device keeps to finish a joint proof
+/// Display is not implemented to prevent accidental leak of the secrets
+#[derive(Clone, Debug, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct CoProofSecrets {
+    r: Scalar,
+    rho: Scalar,
+    sigma: Token,
+}
+
+/// The server's contribution to a joint proof
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CoProofResponse {
+    pub(crate) r: G1Projective,
+    pub(crate) w: G1Projective,
+    pub(crate) k: G1Projective,
+}
+
+try_from_bytes_impl!(CoProofRequest);
+
+impl CoProofRequest {
+    /// The number of bytes in a co-proof request
+    pub const BYTES: usize = 96;
+
+    /// Start a proof with the device share of the token for `id`
+    /// and any blindings that were removed from it
+    pub fn new<B: AsRef<[u8]>>(
+        device_share: &Token,
+        blindings: &[Blinding],
+        id: B,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<(Self, CoProofSecrets), Error> {
+        let a = IdDigest::try_new(id)?.a;
+        let sigma = device_share.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>();
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let r = non_zero_scalar(&mut rng);
+        let rho = non_zero_scalar(&mut rng);
+        let request = Self {
+            u: a * r,
+            d: sigma * rho,
+        };
+        Ok((
+            request,
+            CoProofSecrets {
+                r,
+                rho,
+                sigma: Token(sigma),
+            },
+        ))
+    }
+
+    /// Convert this request into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..48].copy_from_slice(&self.u.to_affine().to_compressed());
+        out[48..].copy_from_slice(&self.d.to_affine().to_compressed());
+        out
+    }
+
+    /// Convert a byte sequence into a request
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let uu = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[..48]).unwrap())
+            .map(G1Projective::from);
+        let dd = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[48..]).unwrap())
+            .map(G1Projective::from);
+        uu.and_then(|u| dd.and_then(|d| CtOption::new(Self { u, d }, Choice::from(1u8))))
+    }
+}
+
+try_from_bytes_impl!(CoProofResponse);
+
+impl CoProofResponse {
+    /// The number of bytes in a co-proof response
+    pub const BYTES: usize = 144;
+
+    /// Combine the server's contribution into a proof and
+    /// check it is valid for `pk`, `id` and `nonce`
+    pub fn finish<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        secrets: &CoProofSecrets,
+        pk: PublicKey,
+        id: B,
+        nonce: N,
+    ) -> Result<Proof, Error> {
+        let digest = IdDigest::try_new(id)?;
+        let rho_inv =
+            Option::<Scalar>::from(secrets.rho.invert()).ok_or(Error::InvalidParameters)?;
+        let u = digest.a * secrets.r + self.r;
+        let t = Proof::challenge(u, nonce.as_ref());
+        let z = self.k - secrets.sigma.0 * (secrets.r + t) - self.w * rho_inv;
+        let proof = Proof { u, z };
+        proof.try_open_with_digest(pk, &digest, nonce)?;
+        Ok(proof)
+    }
+
+    /// Convert this response into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..48].copy_from_slice(&self.r.to_affine().to_compressed());
+        out[48..96].copy_from_slice(&self.w.to_affine().to_compressed());
+        out[96..].copy_from_slice(&self.k.to_affine().to_compressed());
+        out
+    }
+
+    /// Convert a byte sequence into a response
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let point = |i: usize| {
+            G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[i..i + 48]).unwrap())
+                .map(G1Projective::from)
+        };
+        let (rr, ww, kk) = (point(0), point(48), point(96));
+        rr.and_then(|r| {
+            ww.and_then(|w| kk.and_then(|k| CtOption::new(Self { r, w, k }, Choice::from(1u8))))
+        })
+    }
+}
+
+impl Token {
+    /// Split this token for `id` into a device share and a server share.
+    /// The device should delete the full token afterwards
+    pub fn split<B: AsRef<[u8]>>(
+        &self,
+        id: B,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(Token, ServerTokenShare), Error> {
+        let a = IdDigest::try_new(id)?.a;
+        let k = non_zero_scalar(rng);
+        let device_share = self.0 - a * k;
+        if device_share.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        Ok((Token(device_share), ServerTokenShare(k)))
+    }
+}
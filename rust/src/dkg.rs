@@ -0,0 +1,510 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::threshold::{evaluate_polynomial, random_polynomial};
+use crate::{util::*, PublicKey, PublicKeyShare, SecretKeyShare};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+const DKG_POK_DST: &[u8] = b"OBERON_DKG_PROOF_OF_KNOWLEDGE_";
+
+/// The rounds of the distributed key generation protocol
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DkgRound {
+    /// Create commitments and shares
+    Round1,
+    /// Verify received shares and issue complaints
+    Round2,
+    /// Answer complaints with justifications
+    Round3,
+    /// Resolve complaints and compute the final key shares
+    Finalize,
+    /// The protocol has completed
+    Complete,
+}
+
+/// The round 1 message each participant sends to everyone else over a
+/// broadcast channel. Contains the Feldman commitments to the participant's
+/// polynomials for `w`, `x`, `y` and a proof of knowledge of their constant terms.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Round1Broadcast {
+    pub(crate) sender: u16,
+    pub(crate) w_commitments: Vec<G2Projective>,
+    pub(crate) x_commitments: Vec<G2Projective>,
+    pub(crate) y_commitments: Vec<G2Projective>,
+    pub(crate) challenge: Scalar,
+    pub(crate) responses: [Scalar; 3],
+}
+
+impl Round1Broadcast {
+    /// The participant that sent this message
+    pub fn sender(&self) -> u16 {
+        self.sender
+    }
+
+    fn is_valid(&self, threshold: usize) -> bool {
+        if self.w_commitments.len() != threshold
+            || self.x_commitments.len() != threshold
+            || self.y_commitments.len() != threshold
+        {
+            return false;
+        }
+        let constants = [
+            self.w_commitments[0],
+            self.x_commitments[0],
+            self.y_commitments[0],
+        ];
+        if constants.iter().any(|c| c.is_identity().unwrap_u8() == 1) {
+            return false;
+        }
+        let mut nonces = [G2Projective::IDENTITY; 3];
+        for i in 0..3 {
+            nonces[i] = G2Projective::GENERATOR * self.responses[i] - constants[i] * self.challenge;
+        }
+        pok_challenge(self.sender, &constants, &nonces) == self.challenge
+    }
+
+    fn verify_share(&self, receiver: u16, share: &[Scalar; 3]) -> bool {
+        let x = Scalar::from(receiver as u64);
+        [
+            &self.w_commitments,
+            &self.x_commitments,
+            &self.y_commitments,
+        ]
+        .iter()
+        .zip(share.iter())
+        .all(|(commitments, s)| evaluate_commitments(commitments, x) == G2Projective::GENERATOR * s)
+    }
+}
+
+/// The round 1 message each participant sends privately to every other participant.
+/// Must be sent over a confidential and authenticated channel.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct Round1Share {
+    pub(crate) sender: u16,
+    pub(crate) receiver: u16,
+    pub(crate) w: Scalar,
+    pub(crate) x: Scalar,
+    pub(crate) y: Scalar,
+}
+
+impl Round1Share {
+    /// The participant that sent this share
+    pub fn sender(&self) -> u16 {
+        self.sender
+    }
+
+    /// The participant that should receive this share
+    pub fn receiver(&self) -> u16 {
+        self.receiver
+    }
+
+    fn values(&self) -> [Scalar; 3] {
+        [self.w, self.x, self.y]
+    }
+}
+
+/// A complaint broadcast by `accuser` when the share
+/// from `accused` was missing or didn't match its commitments
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Complaint {
+    /// The participant that received an invalid share
+    pub accuser: u16,
+    /// The participant that sent the invalid share
+    pub accused: u16,
+}
+
+/// The answer to a complaint broadcast by the accused participant.
+/// Reveals the share sent to the accuser so everyone can check it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Justification {
+    pub(crate) share: Round1Share,
+}
+
+impl Justification {
+    /// The accused participant answering the complaint
+    pub fn sender(&self) -> u16 {
+        self.share.sender
+    }
+
+    /// The participant that made the complaint
+    pub fn receiver(&self) -> u16 {
+        self.share.receiver
+    }
+}
+
+/// The result of a successful distributed key generation
+#[derive(Clone, Debug)]
+pub struct DkgOutput {
+    /// This participant's share of the secret key
+    pub secret_key_share: SecretKeyShare,
+    /// The joint public key
+    pub public_key: PublicKey,
+    /// The public key shares of all participants used to verify partial tokens
+    pub public_key_shares: Vec<PublicKeyShare>,
+    /// The participants whose contributions make up the joint key
+    pub qualified: Vec<u16>,
+}
+
+/// A participant in a Pedersen style distributed key generation
+/// that outputs shares of a `SecretKey` and the joint `PublicKey`
+/// without any party ever learning the full secret key.
+///
+/// The protocol is sans-IO: each round consumes the messages
+/// from the previous round and returns the messages to send.
+/// The caller is responsible for delivering broadcasts to all participants
+/// and shares to their receiver over authenticated channels.
+///
+/// 1. `round1` returns a broadcast and a private share for every other participant.
+/// 2. `round2` checks the received broadcasts and shares, returning complaints to broadcast.
+/// 3. `round3` takes all broadcast complaints and returns justifications to broadcast.
+/// 4. `finalize` resolves complaints with the justifications and returns the key shares.
+///
+/// Participants that send invalid broadcasts or fail to justify a complaint
+/// are disqualified and excluded from the joint key.
+#[derive(Debug)]
+pub struct DkgParticipant {
+    identifier: u16,
+    threshold: usize,
+    limit: usize,
+    round: DkgRound,
+    w: Vec<Scalar>,
+    x: Vec<Scalar>,
+    y: Vec<Scalar>,
+    broadcasts: BTreeMap<u16, Round1Broadcast>,
+    shares: BTreeMap<u16, [Scalar; 3]>,
+    complaints: BTreeSet<Complaint>,
+    disqualified: BTreeSet<u16>,
+}
+
+impl Drop for DkgParticipant {
+    fn drop(&mut self) {
+        self.w.zeroize();
+        self.x.zeroize();
+        self.y.zeroize();
+        for share in self.shares.values_mut() {
+            share.zeroize();
+        }
+    }
+}
+
+impl DkgParticipant {
+    /// Create a new participant with `identifier` in [1, `limit`]
+    /// where `threshold` participants are required to sign tokens
+    pub fn new(
+        identifier: u16,
+        threshold: usize,
+        limit: usize,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        if threshold < 2
+            || limit < threshold
+            || limit > u16::MAX as usize
+            || identifier == 0
+            || identifier as usize > limit
+        {
+            return None;
+        }
+        Some(Self {
+            identifier,
+            threshold,
+            limit,
+            round: DkgRound::Round1,
+            w: random_polynomial(Scalar::random(&mut rng), threshold, &mut rng),
+            x: random_polynomial(Scalar::random(&mut rng), threshold, &mut rng),
+            y: random_polynomial(Scalar::random(&mut rng), threshold, &mut rng),
+            broadcasts: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            complaints: BTreeSet::new(),
+            disqualified: BTreeSet::new(),
+        })
+    }
+
+    /// This participant's identifier
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// The round this participant is expecting to run next
+    pub fn round(&self) -> DkgRound {
+        self.round
+    }
+
+    /// The participants that have been disqualified so far
+    pub fn disqualified(&self) -> Vec<u16> {
+        self.disqualified.iter().copied().collect()
+    }
+
+    /// Compute the commitments to broadcast and the shares
+    /// to send to each of the other participants
+    pub fn round1(
+        &mut self,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Option<(Round1Broadcast, Vec<Round1Share>)> {
+        if self.round != DkgRound::Round1 {
+            return None;
+        }
+        let commit = |poly: &[Scalar]| -> Vec<G2Projective> {
+            poly.iter().map(|c| G2Projective::GENERATOR * c).collect()
+        };
+        let w_commitments = commit(&self.w);
+        let x_commitments = commit(&self.x);
+        let y_commitments = commit(&self.y);
+
+        let constants = [w_commitments[0], x_commitments[0], y_commitments[0]];
+        let mut k = [
+            Scalar::random(&mut rng),
+            Scalar::random(&mut rng),
+            Scalar::random(&mut rng),
+        ];
+        let nonces = [
+            G2Projective::GENERATOR * k[0],
+            G2Projective::GENERATOR * k[1],
+            G2Projective::GENERATOR * k[2],
+        ];
+        let challenge = pok_challenge(self.identifier, &constants, &nonces);
+        let responses = [
+            k[0] + challenge * self.w[0],
+            k[1] + challenge * self.x[0],
+            k[2] + challenge * self.y[0],
+        ];
+        k.zeroize();
+
+        let broadcast = Round1Broadcast {
+            sender: self.identifier,
+            w_commitments,
+            x_commitments,
+            y_commitments,
+            challenge,
+            responses,
+        };
+
+        let mut shares = Vec::with_capacity(self.limit - 1);
+        for receiver in 1..=self.limit as u16 {
+            let share = self.share_for(receiver);
+            if receiver == self.identifier {
+                self.shares.insert(receiver, share.values());
+            } else {
+                shares.push(share);
+            }
+        }
+
+        self.broadcasts.insert(self.identifier, broadcast.clone());
+        self.round = DkgRound::Round2;
+        Some((broadcast, shares))
+    }
+
+    /// Check the broadcasts from all participants and the shares sent to this participant.
+    /// Returns the complaints to broadcast, which can be empty.
+    ///
+    /// Broadcasts and shares from or to other participants are ignored
+    /// so the caller can pass all received messages.
+    pub fn round2(
+        &mut self,
+        broadcasts: &[Round1Broadcast],
+        shares: &[Round1Share],
+    ) -> Option<Vec<Complaint>> {
+        if self.round != DkgRound::Round2 {
+            return None;
+        }
+
+        for sender in self.others() {
+            let mut received = broadcasts.iter().filter(|b| b.sender == sender);
+            match (received.next(), received.next()) {
+                (Some(b), None) if b.is_valid(self.threshold) => {
+                    self.broadcasts.insert(sender, b.clone());
+                }
+                // Missing, invalid or conflicting broadcasts are seen by everyone
+                _ => {
+                    self.disqualified.insert(sender);
+                }
+            }
+        }
+
+        let mut complaints = Vec::new();
+        for (sender, broadcast) in self.broadcasts.iter() {
+            if *sender == self.identifier {
+                continue;
+            }
+            let share = shares
+                .iter()
+                .find(|s| s.sender == *sender && s.receiver == self.identifier)
+                .map(|s| s.values());
+            match share {
+                Some(share) if broadcast.verify_share(self.identifier, &share) => {
+                    self.shares.insert(*sender, share);
+                }
+                _ => {
+                    complaints.push(Complaint {
+                        accuser: self.identifier,
+                        accused: *sender,
+                    });
+                }
+            }
+        }
+
+        self.round = DkgRound::Round3;
+        Some(complaints)
+    }
+
+    /// Record the complaints broadcast by all participants, including this one, and
+    /// answer the ones against this participant.
+    /// Returns the justifications to broadcast, which can be empty.
+    pub fn round3(&mut self, complaints: &[Complaint]) -> Option<Vec<Justification>> {
+        if self.round != DkgRound::Round3 {
+            return None;
+        }
+
+        let mut justifications = Vec::new();
+        for complaint in complaints {
+            if complaint.accuser == complaint.accused
+                || !self.broadcasts.contains_key(&complaint.accuser)
+                || !self.broadcasts.contains_key(&complaint.accused)
+            {
+                continue;
+            }
+            if self.complaints.insert(*complaint) && complaint.accused == self.identifier {
+                justifications.push(Justification {
+                    share: self.share_for(complaint.accuser),
+                });
+            }
+        }
+
+        self.round = DkgRound::Finalize;
+        Some(justifications)
+    }
+
+    /// Resolve complaints using the broadcast justifications and compute
+    /// this participant's secret key share and the joint public key.
+    ///
+    /// Fails if fewer than `threshold` participants remain qualified.
+    pub fn finalize(&mut self, justifications: &[Justification]) -> Option<DkgOutput> {
+        if self.round != DkgRound::Finalize {
+            return None;
+        }
+
+        for complaint in self.complaints.iter() {
+            let broadcast = &self.broadcasts[&complaint.accused];
+            let justified = justifications
+                .iter()
+                .find(|j| j.sender() == complaint.accused && j.receiver() == complaint.accuser)
+                .map(|j| j.share.values())
+                .filter(|share| broadcast.verify_share(complaint.accuser, share));
+            match justified {
+                Some(share) => {
+                    if complaint.accuser == self.identifier {
+                        self.shares.insert(complaint.accused, share);
+                    }
+                }
+                None => {
+                    self.disqualified.insert(complaint.accused);
+                }
+            }
+        }
+        for id in self.disqualified.iter() {
+            self.broadcasts.remove(id);
+            self.shares.remove(id);
+        }
+        if self.disqualified.contains(&self.identifier) || self.broadcasts.len() < self.threshold {
+            return None;
+        }
+
+        let qualified: Vec<u16> = self.broadcasts.keys().copied().collect();
+        let mut secret_key_share = SecretKeyShare {
+            identifier: self.identifier,
+            w: Scalar::ZERO,
+            x: Scalar::ZERO,
+            y: Scalar::ZERO,
+        };
+        for id in qualified.iter() {
+            let share = self.shares.get(id)?;
+            secret_key_share.w += share[0];
+            secret_key_share.x += share[1];
+            secret_key_share.y += share[2];
+        }
+
+        let public_key = PublicKey {
+            w: self.broadcasts.values().map(|b| b.w_commitments[0]).sum(),
+            x: self.broadcasts.values().map(|b| b.x_commitments[0]).sum(),
+            y: self.broadcasts.values().map(|b| b.y_commitments[0]).sum(),
+        };
+        let public_key_shares = (1..=self.limit as u16)
+            .map(|identifier| {
+                let s = Scalar::from(identifier as u64);
+                PublicKeyShare {
+                    identifier,
+                    w: self
+                        .broadcasts
+                        .values()
+                        .map(|b| evaluate_commitments(&b.w_commitments, s))
+                        .sum(),
+                    x: self
+                        .broadcasts
+                        .values()
+                        .map(|b| evaluate_commitments(&b.x_commitments, s))
+                        .sum(),
+                    y: self
+                        .broadcasts
+                        .values()
+                        .map(|b| evaluate_commitments(&b.y_commitments, s))
+                        .sum(),
+                }
+            })
+            .collect();
+
+        self.round = DkgRound::Complete;
+        Some(DkgOutput {
+            secret_key_share,
+            public_key,
+            public_key_shares,
+            qualified,
+        })
+    }
+
+    fn others(&self) -> impl Iterator<Item = u16> {
+        let identifier = self.identifier;
+        (1..=self.limit as u16).filter(move |i| *i != identifier)
+    }
+
+    fn share_for(&self, receiver: u16) -> Round1Share {
+        let x = Scalar::from(receiver as u64);
+        Round1Share {
+            sender: self.identifier,
+            receiver,
+            w: evaluate_polynomial(&self.w, x),
+            x: evaluate_polynomial(&self.x, x),
+            y: evaluate_polynomial(&self.y, x),
+        }
+    }
+}
+
+/// Evaluate the polynomial committed to in the exponent at `x` using Horner's method
+fn evaluate_commitments(commitments: &[G2Projective], x: Scalar) -> G2Projective {
+    commitments
+        .iter()
+        .rev()
+        .fold(G2Projective::IDENTITY, |acc, c| acc * x + c)
+}
+
+fn pok_challenge(sender: u16, constants: &[G2Projective; 3], nonces: &[G2Projective; 3]) -> Scalar {
+    let c = constants.map(|p| p.to_affine().to_compressed());
+    let n = nonces.map(|p| p.to_affine().to_compressed());
+    hash_to_scalar(&[
+        DKG_POK_DST,
+        &sender.to_le_bytes(),
+        &c[0],
+        &c[1],
+        &c[2],
+        &n[0],
+        &n[1],
+        &n[2],
+    ])
+}
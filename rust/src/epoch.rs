@@ -0,0 +1,243 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Blinding, Error, IdDigest, Proof, PublicKey, SecretKey, Token};
+use core::convert::TryFrom;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, CtOption};
+
+const EPOCH_DST: &[u8] = b"OBERON_EPOCH_V1_";
+
+/// The validity period of a token in seconds since the unix epoch.
+/// Both ends are inclusive
+///
+/// Tokens issued for an epoch sign the canonical encoding
+///
+/// "OBERON_EPOCH_V1_" || I2OSP(len(id), 8) || id || I2OSP(not_before, 8) || I2OSP(not_after, 8)
+///
+/// in place of the id.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"test identity";
+/// let epoch = Epoch::new(1_700_000_000, 1_700_086_400).unwrap();
+///
+/// let token = sk.sign_with_epoch(id, epoch).unwrap();
+/// assert_eq!(token.verify_with_epoch(pk, id, epoch).unwrap_u8(), 1u8);
+///
+/// let proof = EpochProof::new(&token, &[], id, epoch, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(pk, id, b"nonce", 1_700_000_100, 60).unwrap_u8(), 1u8);
+/// assert_eq!(proof.open(pk, id, b"nonce", 1_800_000_000, 60).unwrap_u8(), 0u8);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct Epoch {
+    not_before: u64,
+    not_after: u64,
+}
+
+impl Epoch {
+    /// The number of bytes in an epoch
+    pub const BYTES: usize = 16;
+
+    /// Create a validity period, `None` if `not_before` is after `not_after`
+    pub fn new(not_before: u64, not_after: u64) -> Option<Self> {
+        if not_before > not_after {
+            return None;
+        }
+        Some(Self {
+            not_before,
+            not_after,
+        })
+    }
+
+    /// The first second the token is valid
+    pub fn not_before(&self) -> u64 {
+        self.not_before
+    }
+
+    /// The last second the token is valid
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+
+    /// Is `now` within the validity period allowing `skew` seconds
+    /// of clock difference on either end
+    pub fn contains(&self, now: u64, skew: u64) -> bool {
+        now.saturating_add(skew) >= self.not_before && now <= self.not_after.saturating_add(skew)
+    }
+
+    /// Compute the id digest for `id` bound to this epoch
+    pub fn digest<B: AsRef<[u8]>>(&self, id: B) -> Result<IdDigest, Error> {
+        let id = id.as_ref();
+        IdDigest::from_parts(&[
+            EPOCH_DST,
+            &(id.len() as u64).to_be_bytes(),
+            id,
+            &self.not_before.to_be_bytes(),
+            &self.not_after.to_be_bytes(),
+        ])
+    }
+
+    /// Convert this epoch into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..8].copy_from_slice(&self.not_before.to_be_bytes());
+        out[8..].copy_from_slice(&self.not_after.to_be_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into an epoch
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let not_before = u64::from_be_bytes(<[u8; 8]>::try_from(&data[..8]).unwrap());
+        let not_after = u64::from_be_bytes(<[u8; 8]>::try_from(&data[8..]).unwrap());
+        CtOption::new(
+            Self {
+                not_before,
+                not_after,
+            },
+            Choice::from((not_before <= not_after) as u8),
+        )
+    }
+}
+
+impl SecretKey {
+    /// Sign a new token for `id` that is only valid during `epoch`
+    pub fn sign_with_epoch<B: AsRef<[u8]>>(&self, id: B, epoch: Epoch) -> Option<Token> {
+        Token::new_with_epoch(self, id, epoch)
+    }
+}
+
+impl Token {
+    /// Create a new token for `id` that is only valid during `epoch`
+    pub fn new_with_epoch<B: AsRef<[u8]>>(sk: &SecretKey, id: B, epoch: Epoch) -> Option<Self> {
+        Self::try_new_with_epoch(sk, id, epoch).ok()
+    }
+
+    /// Create a new token for `id` that is only valid during `epoch`
+    /// returning the reason on failure
+    pub fn try_new_with_epoch<B: AsRef<[u8]>>(
+        sk: &SecretKey,
+        id: B,
+        epoch: Epoch,
+    ) -> Result<Self, Error> {
+        Self::try_new_with_digest(sk, &epoch.digest(id)?)
+    }
+
+    /// Check whether the token is valid to the public key for `id` and `epoch`.
+    /// This does not check the current time
+    pub fn verify_with_epoch<B: AsRef<[u8]>>(&self, pk: PublicKey, id: B, epoch: Epoch) -> Choice {
+        match epoch.digest(id) {
+            Err(_) => Choice::from(0u8),
+            Ok(digest) => self.verify_with_digest(pk, &digest),
+        }
+    }
+}
+
+/// A zero-knowledge proof of a valid token that
+/// discloses the token's validity period
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct EpochProof {
+    pub(crate) proof: Proof,
+    pub(crate) epoch: Epoch,
+}
+
+try_from_bytes_impl!(EpochProof);
+
+impl EpochProof {
+    /// The number of bytes in an epoch proof
+    pub const BYTES: usize = 112;
+
+    /// Create a new proof for a token issued for `id` and `epoch`
+    pub fn new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        epoch: Epoch,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_new(token, blindings, id, epoch, nonce, rng).ok()
+    }
+
+    /// Create a new proof returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        epoch: Epoch,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        let digest = epoch.digest(id)?;
+        let proof = Proof::try_new_with_digest(token, blindings, &digest, nonce, rng)?;
+        Ok(Self { proof, epoch })
+    }
+
+    /// The disclosed validity period
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    /// Check whether this proof is valid and `now` is within
+    /// the validity period allowing `skew` seconds of clock difference
+    pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        nonce: N,
+        now: u64,
+        skew: u64,
+    ) -> Choice {
+        if !self.epoch.contains(now, skew) {
+            return Choice::from(0u8);
+        }
+        match self.epoch.digest(id) {
+            Err(_) => Choice::from(0u8),
+            Ok(digest) => self.proof.open_with_digest(pk, &digest, nonce),
+        }
+    }
+
+    /// Check whether this proof is valid returning the reason on failure.
+    /// Returns `Error::Expired` if `now` is outside the validity period
+    pub fn try_open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        nonce: N,
+        now: u64,
+        skew: u64,
+    ) -> Result<(), Error> {
+        self.proof
+            .try_open_with_digest(pk, &self.epoch.digest(id)?, nonce)?;
+        if !self.epoch.contains(now, skew) {
+            return Err(Error::Expired);
+        }
+        Ok(())
+    }
+
+    /// Convert this proof into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..Proof::BYTES].copy_from_slice(&self.proof.to_bytes());
+        out[Proof::BYTES..].copy_from_slice(&self.epoch.to_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into a proof
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let proof =
+            Proof::from_bytes(&<[u8; Proof::BYTES]>::try_from(&data[..Proof::BYTES]).unwrap());
+        let epoch =
+            Epoch::from_bytes(&<[u8; Epoch::BYTES]>::try_from(&data[Proof::BYTES..]).unwrap());
+        match (Option::<Proof>::from(proof), Option::<Epoch>::from(epoch)) {
+            (Some(proof), Some(epoch)) => CtOption::new(Self { proof, epoch }, Choice::from(1u8)),
+            _ => CtOption::new(Self::default(), Choice::from(0u8)),
+        }
+    }
+}
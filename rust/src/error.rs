@@ -0,0 +1,78 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use core::fmt::{self, Display, Formatter};
+
+/// The errors returned by the `try_` variants of the API.
+///
+/// Each error has a stable numeric code that is also used
+/// by the ffi, python, php and wasm bindings.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// A byte sequence is not a valid encoding of the value
+    InvalidEncoding,
+    /// A byte sequence has the wrong length
+    InvalidLength,
+    /// The public key contains the identity point
+    InvalidPublicKey,
+    /// A computed or supplied point is the identity
+    IdentityPoint,
+    /// The identity hashed to zero
+    ZeroHash,
+    /// The token or proof did not verify
+    VerificationFailed,
+    /// The arguments are not valid for the operation
+    InvalidParameters,
+    /// The identity has been revoked
+    Revoked,
+    /// The current time is outside the validity period
+    Expired,
+    /// The challenge was already used
+    Replayed,
+    /// The key is not known to the verifier
+    UnknownKey,
+}
+
+impl Error {
+    /// The numeric code for this error. Zero is never used
+    /// so bindings can use it to signal success
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::InvalidEncoding => 1,
+            Self::InvalidLength => 2,
+            Self::InvalidPublicKey => 3,
+            Self::IdentityPoint => 4,
+            Self::ZeroHash => 5,
+            Self::VerificationFailed => 6,
+            Self::InvalidParameters => 7,
+            Self::Revoked => 8,
+            Self::Expired => 9,
+            Self::Replayed => 10,
+            Self::UnknownKey => 11,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::InvalidEncoding => "invalid encoding",
+            Self::InvalidLength => "invalid length",
+            Self::InvalidPublicKey => "invalid public key",
+            Self::IdentityPoint => "point is the identity",
+            Self::ZeroHash => "identity hashed to zero",
+            Self::VerificationFailed => "verification failed",
+            Self::InvalidParameters => "invalid parameters",
+            Self::Revoked => "identity is revoked",
+            Self::Expired => "outside the validity period",
+            Self::Replayed => "challenge was already used",
+            Self::UnknownKey => "unknown key",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
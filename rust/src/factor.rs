@@ -0,0 +1,300 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#[cfg(feature = "password")]
+use crate::PasswordParams;
+use crate::{Blinding, Error, Token};
+use core::fmt::{self, Debug, Formatter};
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+use alloc::vec::Vec;
+
+/// The kind of a factor in a `FactorSet`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub enum FactorKind {
+    /// A short numeric code
+    Pin,
+    /// A password stretched with a memory hard KDF
+    Password,
+    /// The contents of a file on the device
+    KeyFile,
+    /// A secret read from the environment
+    EnvSecret,
+    /// Any other factor
+    #[default]
+    Custom,
+}
+
+/// The name of a factor, at most 32 bytes of utf8
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct FactorLabel([u8; 32]);
+
+impl Debug for FactorLabel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl FactorLabel {
+    /// The maximum number of bytes in a label
+    pub const MAX_BYTES: usize = 32;
+
+    /// Create a label. It must not be empty or contain NUL
+    pub fn new(label: &str) -> Result<Self, Error> {
+        let bytes = label.as_bytes();
+        if bytes.is_empty() || bytes.len() > Self::MAX_BYTES || bytes.contains(&0) {
+            return Err(Error::InvalidParameters);
+        }
+        let mut out = [0u8; Self::MAX_BYTES];
+        out[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self(out))
+    }
+
+    /// The label as a string
+    pub fn as_str(&self) -> &str {
+        let len = self
+            .0
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(Self::MAX_BYTES);
+        core::str::from_utf8(&self.0[..len]).unwrap_or_default()
+    }
+}
+
+/// The non-secret description of a factor that can be stored with the token
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct FactorInfo {
+    kind: FactorKind,
+    label: FactorLabel,
+    #[cfg(feature = "password")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params: Option<PasswordParams>,
+}
+
+impl FactorInfo {
+    /// The kind of factor
+    pub fn kind(&self) -> FactorKind {
+        self.kind
+    }
+
+    /// The factor name
+    pub fn label(&self) -> &str {
+        self.label.as_str()
+    }
+
+    /// The KDF parameters if this is a password factor
+    #[cfg(feature = "password")]
+    pub fn params(&self) -> Option<PasswordParams> {
+        self.params
+    }
+}
+
+/// Labelled blinding factors for a token
+///
+/// `apply` subtracts every factor from a token and `blindings` gives
+/// the factors to add back in `Proof::new`. Only the `metadata` should
+/// be stored, the factors are derived again from the user each time.
+/// Without `alloc` at most `FactorSet::MAX_FACTORS` can be held.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"test identity";
+///
+/// let mut factors = FactorSet::new();
+/// factors.add_pin("unlock", b"1234").unwrap();
+/// factors.add_key_file("device", b"<key file contents>").unwrap();
+/// let blinded_token = factors.apply(&sk.sign(id).unwrap());
+///
+/// let proof = Proof::new(&blinded_token, factors.blindings(), id, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(pk, id, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Default)]
+pub struct FactorSet {
+    info: Slots<FactorInfo>,
+    blindings: Slots<Blinding>,
+}
+
+impl Debug for FactorSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FactorSet")
+            .field("metadata", &self.metadata())
+            .finish()
+    }
+}
+
+impl FactorSet {
+    /// The number of factors that can be held without `alloc`
+    pub const MAX_FACTORS: usize = 8;
+
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of factors
+    pub fn len(&self) -> usize {
+        self.blindings.as_slice().len()
+    }
+
+    /// Is the set empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add a factor. Labels must be unique
+    pub fn add(&mut self, kind: FactorKind, label: &str, blinding: Blinding) -> Result<(), Error> {
+        self.insert(
+            FactorInfo {
+                kind,
+                label: FactorLabel::new(label)?,
+                #[cfg(feature = "password")]
+                params: None,
+            },
+            blinding,
+        )
+    }
+
+    /// Add a PIN factor
+    pub fn add_pin(&mut self, label: &str, pin: &[u8]) -> Result<(), Error> {
+        self.add(FactorKind::Pin, label, Blinding::new(pin))
+    }
+
+    /// Add a key file factor from the file contents
+    pub fn add_key_file(&mut self, label: &str, contents: &[u8]) -> Result<(), Error> {
+        self.add(FactorKind::KeyFile, label, Blinding::new(contents))
+    }
+
+    /// Add a factor from an environment secret
+    pub fn add_env_secret(&mut self, label: &str, value: &[u8]) -> Result<(), Error> {
+        self.add(FactorKind::EnvSecret, label, Blinding::new(value))
+    }
+
+    /// Add any other factor
+    pub fn add_custom(&mut self, label: &str, data: &[u8]) -> Result<(), Error> {
+        self.add(FactorKind::Custom, label, Blinding::new(data))
+    }
+
+    /// Add a password factor for the token on `id` using `Blinding::from_password`.
+    /// The parameters are kept in the metadata
+    #[cfg_attr(docsrs, doc(cfg(feature = "password")))]
+    #[cfg(feature = "password")]
+    pub fn add_password<B: AsRef<[u8]>>(
+        &mut self,
+        label: &str,
+        password: &str,
+        id: B,
+        params: &PasswordParams,
+    ) -> Result<(), Error> {
+        let blinding = Blinding::from_password(password, id, params)?;
+        self.insert(
+            FactorInfo {
+                kind: FactorKind::Password,
+                label: FactorLabel::new(label)?,
+                params: Some(*params),
+            },
+            blinding,
+        )
+    }
+
+    /// Remove the factor with `label`
+    pub fn remove(&mut self, label: &str) -> Option<FactorInfo> {
+        let index = self.position(label)?;
+        self.blindings.remove(index);
+        Some(self.info.remove(index))
+    }
+
+    /// The description of the factor with `label`
+    pub fn get(&self, label: &str) -> Option<&FactorInfo> {
+        self.position(label).map(|i| &self.info.as_slice()[i])
+    }
+
+    /// Subtract every factor from `token`
+    pub fn apply(&self, token: &Token) -> Token {
+        self.blindings
+            .as_slice()
+            .iter()
+            .fold(token.clone(), |t, b| t - b)
+    }
+
+    /// The factors in the order they were added for `Proof::new`
+    pub fn blindings(&self) -> &[Blinding] {
+        self.blindings.as_slice()
+    }
+
+    /// The non-secret description of each factor in the order they were added
+    pub fn metadata(&self) -> &[FactorInfo] {
+        self.info.as_slice()
+    }
+
+    fn insert(&mut self, info: FactorInfo, blinding: Blinding) -> Result<(), Error> {
+        if self.position(info.label.as_str()).is_some() {
+            return Err(Error::InvalidParameters);
+        }
+        self.info.push(info)?;
+        self.blindings.push(blinding)
+    }
+
+    fn position(&self, label: &str) -> Option<usize> {
+        self.info
+            .as_slice()
+            .iter()
+            .position(|i| i.label.as_str() == label)
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[derive(Clone, Default)]
+struct Slots<T>(Vec<T>);
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl<T> Slots<T> {
+    fn push(&mut self, value: T) -> Result<(), Error> {
+        self.0.push(value);
+        Ok(())
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        self.0.remove(index)
+    }
+
+    fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+}
+
+#[cfg(not(any(feature = "alloc", feature = "std")))]
+#[derive(Clone, Default)]
+struct Slots<T: Copy + Default> {
+    items: [T; FactorSet::MAX_FACTORS],
+    len: usize,
+}
+
+#[cfg(not(any(feature = "alloc", feature = "std")))]
+impl<T: Copy + Default> Slots<T> {
+    fn push(&mut self, value: T) -> Result<(), Error> {
+        if self.len == FactorSet::MAX_FACTORS {
+            return Err(Error::InvalidParameters);
+        }
+        self.items[self.len] = value;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        let value = self.items[index];
+        self.items.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        self.items[self.len] = T::default();
+        value
+    }
+
+    fn as_slice(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+}
@@ -1,16 +1,19 @@
 #![allow(unused_doc_comments, missing_docs)]
-use crate::{Blinding, Proof, PublicKey, SecretKey, Token};
+use crate::{Blinding, Challenge, ChallengeKey, Error, Proof, PublicKey, SecretKey, Token};
 use ffi_support::{
     define_bytebuffer_destructor, define_handle_map_deleter, define_string_destructor, ByteBuffer,
     ConcurrentHandleMap, ErrorCode, ExternError,
 };
 use lazy_static::lazy_static;
-use std::{ptr, slice, string::String, vec::Vec};
+use std::{format, ptr, slice, vec::Vec};
 
 lazy_static! {
     /// The context manager for creating proofs
     pub static ref CREATE_PROOF_CONTEXT: ConcurrentHandleMap<CreateProofContext> =
         ConcurrentHandleMap::new();
+    /// The context manager for rebinding tokens
+    pub static ref REBIND_CONTEXT: ConcurrentHandleMap<RebindContext> =
+        ConcurrentHandleMap::new();
 }
 
 /// Cleanup created strings
@@ -19,6 +22,8 @@ define_string_destructor!(oberon_string_free);
 define_bytebuffer_destructor!(oberon_byte_buffer_free);
 /// Cleanup created proof contexts
 define_handle_map_deleter!(CREATE_PROOF_CONTEXT, oberon_create_proof_free);
+/// Cleanup created rebind contexts
+define_handle_map_deleter!(REBIND_CONTEXT, oberon_rebind_free);
 
 /// The proof context object
 pub struct CreateProofContext {
@@ -32,6 +37,14 @@ pub struct CreateProofContext {
     pub nonce: Option<Vec<u8>>,
 }
 
+/// The rebind context object
+pub struct RebindContext {
+    /// The blindings currently on the token
+    pub old_blindings: Vec<Blinding>,
+    /// The blindings to apply instead
+    pub new_blindings: Vec<Blinding>,
+}
+
 /// Used for receiving byte arrays
 #[repr(C)]
 pub struct ByteArray {
@@ -104,27 +117,16 @@ impl ByteArray {
     }
 }
 
-macro_rules! from_bytes {
-    ($name:ident, $type:ident) => {
-        fn $name(input: Vec<u8>) -> Option<$type> {
-            match <[u8; $type::BYTES]>::try_from(input.as_slice()) {
-                Err(_) => None,
-                Ok(bytes) => {
-                    let val = $type::from_bytes(&bytes);
-                    if val.is_some().unwrap_u8() == 1u8 {
-                        Some(val.unwrap())
-                    } else {
-                        None
-                    }
-                }
-            }
-        }
-    };
+/// Convert an error into its code and message for the caller
+fn extern_error(what: &str, e: Error) -> ExternError {
+    ExternError::new_error(ErrorCode::new(e.code()), format!("{}: {}", what, e))
+}
+
+/// Set the error and return its code
+fn set_error(err: &mut ExternError, what: &str, e: Error) -> i32 {
+    *err = extern_error(what, e);
+    e.code()
 }
-from_bytes!(secret_key, SecretKey);
-from_bytes!(public_key, PublicKey);
-from_bytes!(get_token, Token);
-from_bytes!(get_proof, Proof);
 
 /// The size of the secret key
 #[no_mangle]
@@ -156,6 +158,18 @@ pub extern "C" fn oberon_proof_size() -> i32 {
     Proof::BYTES as i32
 }
 
+/// The size of a challenge key
+#[no_mangle]
+pub extern "C" fn oberon_challenge_key_size() -> i32 {
+    ChallengeKey::BYTES as i32
+}
+
+/// The size of a challenge
+#[no_mangle]
+pub extern "C" fn oberon_challenge_size() -> i32 {
+    Challenge::BYTES as i32
+}
+
 /// Create new random secret key
 #[no_mangle]
 pub extern "C" fn oberon_new_secret_key(secret_key: &mut ByteBuffer) -> i32 {
@@ -171,13 +185,9 @@ pub extern "C" fn oberon_get_public_key(
     public_key: &mut ByteBuffer,
     err: &mut ExternError,
 ) -> i32 {
-    let t = sk.to_vec();
-    match secret_key(t) {
-        None => {
-            *err = ExternError::new_error(ErrorCode::new(1), String::from("Invalid secret key"));
-            1
-        }
-        Some(sk) => {
+    match SecretKey::try_from_bytes(&sk.to_vec()) {
+        Err(e) => set_error(err, "Invalid secret key", e),
+        Ok(sk) => {
             let pk = PublicKey::from(&sk);
             *public_key = ByteBuffer::from_vec(pk.to_bytes().to_vec());
             0
@@ -201,21 +211,11 @@ pub extern "C" fn oberon_new_token(
     token: &mut ByteBuffer,
     err: &mut ExternError,
 ) -> i32 {
-    let t = sk.to_vec();
-    match secret_key(t) {
-        None => {
-            *err = ExternError::new_error(ErrorCode::new(1), String::from("Invalid secret key"));
-            1
-        }
-        Some(sk) => match Token::new(&sk, id.to_vec()) {
-            None => {
-                *err = ExternError::new_error(
-                    ErrorCode::new(2),
-                    String::from("Unable to create token"),
-                );
-                2
-            }
-            Some(tk) => {
+    match SecretKey::try_from_bytes(&sk.to_vec()) {
+        Err(e) => set_error(err, "Invalid secret key", e),
+        Ok(sk) => match Token::try_new(&sk, id.to_vec()) {
+            Err(e) => set_error(err, "Unable to create token", e),
+            Ok(tk) => {
                 *token = ByteBuffer::from_vec(tk.to_bytes().to_vec());
                 0
             }
@@ -223,7 +223,9 @@ pub extern "C" fn oberon_new_token(
     }
 }
 
-/// Verify a token for a given ID
+/// Verify a token for a given ID.
+/// Returns 0 if valid. An invalid token returns a nonzero
+/// code without setting `err`, malformed inputs set `err`
 #[no_mangle]
 pub extern "C" fn oberon_verify_token(
     token: ByteArray,
@@ -231,18 +233,17 @@ pub extern "C" fn oberon_verify_token(
     id: ByteArray,
     err: &mut ExternError,
 ) -> i32 {
-    match (get_token(token.to_vec()), public_key(pk.to_vec())) {
-        (Some(tk), Some(pk)) => {
-            let res = tk.verify(pk, id.to_vec()).unwrap_u8() as i32;
-            -(res - 1)
-        }
-        (_, _) => {
-            *err = ExternError::new_error(
-                ErrorCode::new(1),
-                String::from("Invalid token and/or public key"),
-            );
-            1
-        }
+    match (
+        Token::try_from_bytes(&token.to_vec()),
+        PublicKey::try_from_bytes(&pk.to_vec()),
+    ) {
+        (Ok(tk), Ok(pk)) => match tk.try_verify(pk, id.to_vec()) {
+            Ok(()) => 0,
+            Err(Error::VerificationFailed) => Error::VerificationFailed.code(),
+            Err(e) => set_error(err, "Unable to verify token", e),
+        },
+        (Err(e), _) => set_error(err, "Invalid token", e),
+        (_, Err(e)) => set_error(err, "Invalid public key", e),
     }
 }
 
@@ -261,12 +262,9 @@ pub extern "C" fn oberon_add_blinding(
     new_token: &mut ByteBuffer,
     err: &mut ExternError,
 ) -> i32 {
-    match get_token(old_token.to_vec()) {
-        None => {
-            *err = ExternError::new_error(ErrorCode::new(1), String::from("Invalid token"));
-            1
-        }
-        Some(tk) => {
+    match Token::try_from_bytes(&old_token.to_vec()) {
+        Err(e) => set_error(err, "Invalid token", e),
+        Ok(tk) => {
             let b = Blinding::new(data.to_vec().as_slice());
             let new_tk = tk - b;
             *new_token = ByteBuffer::from_vec(new_tk.to_bytes().to_vec());
@@ -283,12 +281,9 @@ pub extern "C" fn oberon_remove_blinding(
     new_token: &mut ByteBuffer,
     err: &mut ExternError,
 ) -> i32 {
-    match get_token(old_token.to_vec()) {
-        None => {
-            *err = ExternError::new_error(ErrorCode::new(1), String::from("Invalid token"));
-            1
-        }
-        Some(tk) => {
+    match Token::try_from_bytes(&old_token.to_vec()) {
+        Err(e) => set_error(err, "Invalid token", e),
+        Ok(tk) => {
             let b = Blinding::new(data.to_vec().as_slice());
             let new_tk = tk + b;
             *new_token = ByteBuffer::from_vec(new_tk.to_bytes().to_vec());
@@ -297,6 +292,85 @@ pub extern "C" fn oberon_remove_blinding(
     }
 }
 
+/// Creates a context for replacing the blindings on a token
+#[no_mangle]
+pub extern "C" fn oberon_rebind_init(err: &mut ExternError) -> u64 {
+    REBIND_CONTEXT.insert_with_output(err, || RebindContext {
+        old_blindings: Vec::new(),
+        new_blindings: Vec::new(),
+    })
+}
+
+/// Add a blinding factor that is currently on the token
+#[no_mangle]
+pub extern "C" fn oberon_rebind_add_old_blinding(
+    handle: u64,
+    blinding: ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    REBIND_CONTEXT.call_with_output_mut(err, handle, move |ctx| {
+        ctx.old_blindings
+            .push(Blinding::new(blinding.to_vec().as_slice()));
+    });
+    err.get_code().code()
+}
+
+/// Add a blinding factor to apply to the token
+#[no_mangle]
+pub extern "C" fn oberon_rebind_add_new_blinding(
+    handle: u64,
+    blinding: ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    REBIND_CONTEXT.call_with_output_mut(err, handle, move |ctx| {
+        ctx.new_blindings
+            .push(Blinding::new(blinding.to_vec().as_slice()));
+    });
+    err.get_code().code()
+}
+
+/// Replace the blindings on the token after checking the old
+/// blindings remove to a valid token for the public key and id
+#[no_mangle]
+pub extern "C" fn oberon_rebind_finish(
+    handle: u64,
+    old_token: ByteArray,
+    pk: ByteArray,
+    id: ByteArray,
+    new_token: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    let tk = REBIND_CONTEXT.call_with_result(
+        err,
+        handle,
+        move |ctx| -> Result<ByteBuffer, ExternError> {
+            let token = Token::try_from_bytes(&old_token.to_vec())
+                .map_err(|e| extern_error("Invalid token", e))?;
+            let pub_key = PublicKey::try_from_bytes(&pk.to_vec())
+                .map_err(|e| extern_error("Invalid public key", e))?;
+            token
+                .rebind(
+                    ctx.old_blindings.as_slice(),
+                    ctx.new_blindings.as_slice(),
+                    pub_key,
+                    id.to_vec(),
+                )
+                .map(|t| ByteBuffer::from_vec(t.to_bytes().to_vec()))
+                .map_err(|e| extern_error("Unable to rebind token", e))
+        },
+    );
+    if err.get_code().is_success() {
+        *new_token = tk;
+        if let Err(e) = REBIND_CONTEXT.remove_u64(handle) {
+            *err = ExternError::new_error(
+                ErrorCode::new(Error::InvalidParameters.code()),
+                format!("{:?}", e),
+            )
+        }
+    }
+    err.get_code().code()
+}
+
 /// Creates a proof context
 #[no_mangle]
 pub extern "C" fn oberon_create_proof_init(err: &mut ExternError) -> u64 {
@@ -316,12 +390,9 @@ pub extern "C" fn oberon_create_proof_set_token(
     err: &mut ExternError,
 ) -> i32 {
     CREATE_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<(), ExternError> {
-        match get_token(token.to_vec()) {
-            None => Err(ExternError::new_error(
-                ErrorCode::new(1),
-                String::from("Invalid token"),
-            )),
-            Some(tk) => {
+        match Token::try_from_bytes(&token.to_vec()) {
+            Err(e) => Err(extern_error("Invalid token", e)),
+            Ok(tk) => {
                 ctx.token = Some(tk);
                 Ok(())
             }
@@ -382,56 +453,43 @@ pub extern "C" fn oberon_create_proof_finish(
         handle,
         move |ctx| -> Result<ByteBuffer, ExternError> {
             if ctx.id.is_none() {
-                return Err(ExternError::new_error(
-                    ErrorCode::new(1),
-                    String::from("Id must be set"),
-                ));
+                return Err(extern_error("Id must be set", Error::InvalidParameters));
             }
             if ctx.nonce.is_none() {
-                return Err(ExternError::new_error(
-                    ErrorCode::new(2),
-                    String::from("Nonce must be set"),
-                ));
+                return Err(extern_error("Nonce must be set", Error::InvalidParameters));
             }
             if ctx.token.is_none() {
-                return Err(ExternError::new_error(
-                    ErrorCode::new(3),
-                    String::from("Token must be set"),
-                ));
+                return Err(extern_error("Token must be set", Error::InvalidParameters));
             }
             match (ctx.id.as_ref(), ctx.nonce.as_ref(), ctx.token.as_ref()) {
-                (Some(id), Some(nonce), Some(token)) => {
-                    match Proof::new(
-                        token,
-                        ctx.blindings.as_slice(),
-                        id,
-                        nonce,
-                        rand::thread_rng(),
-                    ) {
-                        None => Err(ExternError::new_error(
-                            ErrorCode::new(4),
-                            String::from("Invalid proof parameters"),
-                        )),
-                        Some(p) => Ok(ByteBuffer::from_vec(p.to_bytes().to_vec())),
-                    }
-                }
-                (_, _, _) => Err(ExternError::new_error(
-                    ErrorCode::new(5),
-                    String::from("Invalid parameters"),
-                )),
+                (Some(id), Some(nonce), Some(token)) => Proof::try_new(
+                    token,
+                    ctx.blindings.as_slice(),
+                    id,
+                    nonce,
+                    rand::thread_rng(),
+                )
+                .map(|p| ByteBuffer::from_vec(p.to_bytes().to_vec()))
+                .map_err(|e| extern_error("Invalid proof parameters", e)),
+                (_, _, _) => Err(extern_error("Invalid parameters", Error::InvalidParameters)),
             }
         },
     );
     if err.get_code().is_success() {
         *proof = pf;
         if let Err(e) = CREATE_PROOF_CONTEXT.remove_u64(handle) {
-            *err = ExternError::new_error(ErrorCode::new(6), std::format!("{:?}", e))
+            *err = ExternError::new_error(
+                ErrorCode::new(Error::InvalidParameters.code()),
+                format!("{:?}", e),
+            )
         }
     }
     err.get_code().code()
 }
 
-/// Creates a proof using a nonce received from a verifier
+/// Verify a proof using a nonce sent to the prover.
+/// Returns 0 if valid. An invalid proof returns a nonzero
+/// code without setting `err`, malformed inputs set `err`
 #[no_mangle]
 pub extern "C" fn oberon_verify_proof(
     proof: ByteArray,
@@ -440,19 +498,69 @@ pub extern "C" fn oberon_verify_proof(
     nonce: ByteArray,
     err: &mut ExternError,
 ) -> i32 {
-    match (get_proof(proof.to_vec()), public_key(pk.to_vec())) {
-        (Some(pf), Some(pub_key)) => {
-            let res = pf
-                .open(pub_key, id.to_vec().as_slice(), nonce.to_vec().as_slice())
-                .unwrap_u8() as i32;
-            -(res - 1)
+    match (
+        Proof::try_from_bytes(&proof.to_vec()),
+        PublicKey::try_from_bytes(&pk.to_vec()),
+    ) {
+        (Ok(pf), Ok(pub_key)) => {
+            match pf.try_open(pub_key, id.to_vec().as_slice(), nonce.to_vec().as_slice()) {
+                Ok(()) => 0,
+                Err(Error::VerificationFailed) => Error::VerificationFailed.code(),
+                Err(e) => set_error(err, "Unable to verify proof", e),
+            }
         }
-        (_, _) => {
-            *err = ExternError::new_error(
-                ErrorCode::new(1),
-                String::from("Invalid proof and/or public key"),
-            );
-            1
+        (Err(e), _) => set_error(err, "Invalid proof", e),
+        (_, Err(e)) => set_error(err, "Invalid public key", e),
+    }
+}
+
+/// Create a new random challenge key for a verifier
+#[no_mangle]
+pub extern "C" fn oberon_new_challenge_key(key: &mut ByteBuffer) -> i32 {
+    let k = ChallengeKey::new(rand::thread_rng());
+    *key = ByteBuffer::from_vec(k.to_bytes().to_vec());
+    0
+}
+
+/// Mint a challenge issued at `now` seconds since the unix epoch.
+/// The challenge is sent to the prover to use as the proof nonce
+#[no_mangle]
+pub extern "C" fn oberon_new_challenge(
+    key: ByteArray,
+    now: u64,
+    challenge: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    match ChallengeKey::try_from_bytes(&key.to_vec()) {
+        Err(e) => set_error(err, "Invalid challenge key", e),
+        Ok(k) => {
+            let c = k.issue(now, rand::thread_rng());
+            *challenge = ByteBuffer::from_vec(c.to_bytes().to_vec());
+            0
         }
     }
 }
+
+/// Check a challenge was issued by `key` within `ttl` seconds before `now`.
+/// Returns 0 if valid. A forged or stale challenge returns a nonzero
+/// code without setting `err`, malformed inputs set `err`
+#[no_mangle]
+pub extern "C" fn oberon_verify_challenge(
+    challenge: ByteArray,
+    key: ByteArray,
+    now: u64,
+    ttl: u64,
+    err: &mut ExternError,
+) -> i32 {
+    match (
+        Challenge::try_from_bytes(&challenge.to_vec()),
+        ChallengeKey::try_from_bytes(&key.to_vec()),
+    ) {
+        (Ok(c), Ok(k)) => match k.check(&c, now, ttl) {
+            Ok(()) => 0,
+            Err(e) => e.code(),
+        },
+        (Err(e), _) => set_error(err, "Invalid challenge", e),
+        (_, Err(e)) => set_error(err, "Invalid challenge key", e),
+    }
+}
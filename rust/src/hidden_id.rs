@@ -0,0 +1,396 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Blinding, Error, IdDigest, PublicKey, Token};
+use core::convert::TryFrom;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+
+const COMMITMENT_GENERATOR_SEED: &[u8] = b"OBERON_ID_COMMITMENT_GENERATOR_H";
+const COMMITMENT_HASHED_ID_GENERATOR_SEED: &[u8] = b"OBERON_ID_COMMITMENT_GENERATOR_J";
+const HIDDEN_ID_PROOF_DST: &[u8] = b"OBERON_HIDDEN_ID_PROOF_";
+
+/// A Pedersen commitment C = m.G + m'.J + b.H to the id scalars
+/// m and m' where G is the generator of G1 and H, J are hashed from
+/// seeds. Committing to m' ties the commitment to the whole signed id
+/// since a proof cannot show m' = H(m)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct IdCommitment(pub(crate) G1Projective);
+
+impl Default for IdCommitment {
+    fn default() -> Self {
+        Self(G1Projective::IDENTITY)
+    }
+}
+
+impl ConstantTimeEq for IdCommitment {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(IdCommitment);
+
+try_from_bytes_impl!(IdCommitment);
+
+impl IdCommitment {
+    /// The number of bytes in a commitment
+    pub const BYTES: usize = 48;
+
+    /// Commit to `id` with `blinder`
+    pub fn new<B: AsRef<[u8]>>(id: B, blinder: Scalar) -> Option<Self> {
+        Self::try_new(id, blinder).ok()
+    }
+
+    /// Commit to `id` with `blinder` returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>>(id: B, blinder: Scalar) -> Result<Self, Error> {
+        let digest = IdDigest::try_new(id)?;
+        Ok(Self::commit(digest.m, digest.m_tick, blinder))
+    }
+
+    /// The generators G, J and H used by the commitment
+    pub fn generators() -> (G1Projective, G1Projective, G1Projective) {
+        (
+            G1Projective::GENERATOR,
+            hash_to_curve(COMMITMENT_HASHED_ID_GENERATOR_SEED),
+            hash_to_curve(COMMITMENT_GENERATOR_SEED),
+        )
+    }
+
+    /// The committed point for use in other proofs
+    pub fn point(&self) -> G1Projective {
+        self.0
+    }
+
+    /// Convert this commitment into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence into a commitment
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data).map(|p| Self(G1Projective::from(p)))
+    }
+
+    fn commit(m: Scalar, m_tick: Scalar, blinder: Scalar) -> Self {
+        let (g, j, h) = Self::generators();
+        Self(g * m + j * m_tick + h * blinder)
+    }
+}
+
+/// A zero-knowledge proof of a valid token that reveals only
+/// a Pedersen commitment to the id
+///
+/// The responses for m and m' are shared with the proof of the
+/// opening of the commitment, see Hidden identity in MATH.md.
+///
+/// ```
+/// use oberon::*;
+/// use oberon::inner_types::{Field, Scalar};
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"test identity";
+/// let token = sk.sign(id).unwrap();
+///
+/// let blinder = Scalar::random(thread_rng());
+/// let proof = HiddenIdProof::new(&token, &[], id, pk, blinder, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(pk, b"nonce").unwrap_u8(), 1u8);
+/// assert_eq!(proof.commitment(), IdCommitment::new(id, blinder).unwrap());
+/// ```
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct HiddenIdProof {
+    pub(crate) h: G1Projective,
+    pub(crate) sigma: G1Projective,
+    pub(crate) commitment: IdCommitment,
+    pub(crate) challenge: Scalar,
+    pub(crate) s_t: Scalar,
+    pub(crate) s_m: Scalar,
+    pub(crate) s_m_tick: Scalar,
+    pub(crate) s_blinder: Scalar,
+}
+
+impl Default for HiddenIdProof {
+    fn default() -> Self {
+        Self {
+            h: G1Projective::IDENTITY,
+            sigma: G1Projective::IDENTITY,
+            commitment: IdCommitment::default(),
+            challenge: Scalar::ZERO,
+            s_t: Scalar::ZERO,
+            s_m: Scalar::ZERO,
+            s_m_tick: Scalar::ZERO,
+            s_blinder: Scalar::ZERO,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(HiddenIdProof);
+
+try_from_bytes_impl!(HiddenIdProof);
+
+impl HiddenIdProof {
+    /// The number of bytes in a hidden id proof
+    pub const BYTES: usize = 304;
+
+    /// Create a new proof for a token issued under `pk` that
+    /// commits to the id with `blinder`
+    pub fn new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        pk: PublicKey,
+        blinder: Scalar,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_new(token, blindings, id, pk, blinder, nonce, rng).ok()
+    }
+
+    /// Create a new proof for a token issued under `pk` that
+    /// commits to the id with `blinder` returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        pk: PublicKey,
+        blinder: Scalar,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        let digest = IdDigest::try_new(id)?;
+        Self::try_new_with_digest(token, blindings, &digest, pk, blinder, nonce, rng)
+    }
+
+    /// Create a new proof from a precomputed id digest
+    pub(crate) fn try_new_with_digest<N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        digest: &IdDigest,
+        pk: PublicKey,
+        blinder: Scalar,
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        let sigma = token.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>();
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let commitment = IdCommitment::commit(digest.m, digest.m_tick, blinder);
+
+        let (h, sigma, t) = randomize(sigma, digest.a, &mut rng);
+
+        let k_t = Scalar::random(&mut rng);
+        let k_m = Scalar::random(&mut rng);
+        let k_m_tick = Scalar::random(&mut rng);
+        let k_blinder = Scalar::random(&mut rng);
+        let rhs = G2Projective::GENERATOR * k_t + pk.w * k_m_tick + pk.y * k_m;
+        let pairing_commitment = pairing(&h.to_affine(), &rhs.to_affine());
+        let opening_commitment = IdCommitment::commit(k_m, k_m_tick, k_blinder).0;
+        let challenge = hidden_id_challenge(
+            h,
+            sigma,
+            commitment,
+            pairing_commitment,
+            opening_commitment,
+            nonce.as_ref(),
+        );
+
+        Ok(Self {
+            h,
+            sigma,
+            commitment,
+            challenge,
+            s_t: k_t + challenge * t,
+            s_m: k_m + challenge * digest.m,
+            s_m_tick: k_m_tick + challenge * digest.m_tick,
+            s_blinder: k_blinder + challenge * blinder,
+        })
+    }
+
+    /// The commitment to the hidden id
+    pub fn commitment(&self) -> IdCommitment {
+        self.commitment
+    }
+
+    /// Check whether this proof is valid
+    pub fn open<N: AsRef<[u8]>>(&self, pk: PublicKey, nonce: N) -> Choice {
+        if (pk.is_invalid()
+            | self.h.is_identity()
+            | self.sigma.is_identity()
+            | self.commitment.0.is_identity())
+        .unwrap_u8()
+            == 1
+        {
+            return Choice::from(0u8);
+        }
+
+        let mut points = [G1Affine::identity(); 2];
+        G1Projective::batch_normalize(&[self.sigma, -self.h], &mut points);
+        let lhs = multi_miller_loop(&[
+            (&points[0], &G2Prepared::from(G2Affine::generator())),
+            (&points[1], &G2Prepared::from(pk.x.to_affine())),
+        ])
+        .final_exponentiation();
+        let rhs = G2Projective::GENERATOR * self.s_t + pk.w * self.s_m_tick + pk.y * self.s_m;
+        let pairing_commitment =
+            pairing(&self.h.to_affine(), &rhs.to_affine()) - lhs * self.challenge;
+        let opening_commitment = IdCommitment::commit(self.s_m, self.s_m_tick, self.s_blinder).0
+            - self.commitment.0 * self.challenge;
+
+        let challenge = hidden_id_challenge(
+            self.h,
+            self.sigma,
+            self.commitment,
+            pairing_commitment,
+            opening_commitment,
+            nonce.as_ref(),
+        );
+        challenge.ct_eq(&self.challenge)
+    }
+
+    /// Check whether this proof is valid returning the reason on failure
+    pub fn try_open<N: AsRef<[u8]>>(&self, pk: PublicKey, nonce: N) -> Result<(), Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        if (self.h.is_identity() | self.sigma.is_identity() | self.commitment.0.is_identity())
+            .unwrap_u8()
+            == 1
+        {
+            return Err(Error::IdentityPoint);
+        }
+        if self.open(pk, nonce).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+
+    /// Convert this proof into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..48].copy_from_slice(&self.h.to_affine().to_compressed());
+        out[48..96].copy_from_slice(&self.sigma.to_affine().to_compressed());
+        out[96..144].copy_from_slice(&self.commitment.to_bytes());
+        out[144..176].copy_from_slice(&self.challenge.to_le_bytes());
+        out[176..208].copy_from_slice(&self.s_t.to_le_bytes());
+        out[208..240].copy_from_slice(&self.s_m.to_le_bytes());
+        out[240..272].copy_from_slice(&self.s_m_tick.to_le_bytes());
+        out[272..].copy_from_slice(&self.s_blinder.to_le_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into a proof
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let point = |i: usize| {
+            G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[i..i + 48]).unwrap())
+                .map(G1Projective::from)
+        };
+        let scalar =
+            |i: usize| Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[i..i + 32]).unwrap());
+        let (h, sigma, commitment) = (point(0), point(48), point(96));
+        let (challenge, s_t, s_m, s_m_tick, s_blinder) = (
+            scalar(144),
+            scalar(176),
+            scalar(208),
+            scalar(240),
+            scalar(272),
+        );
+        let valid = h.is_some()
+            & sigma.is_some()
+            & commitment.is_some()
+            & challenge.is_some()
+            & s_t.is_some()
+            & s_m.is_some()
+            & s_m_tick.is_some()
+            & s_blinder.is_some();
+        CtOption::new(
+            Self {
+                h: h.unwrap_or(G1Projective::IDENTITY),
+                sigma: sigma.unwrap_or(G1Projective::IDENTITY),
+                commitment: IdCommitment(commitment.unwrap_or(G1Projective::IDENTITY)),
+                challenge: challenge.unwrap_or(Scalar::ZERO),
+                s_t: s_t.unwrap_or(Scalar::ZERO),
+                s_m: s_m.unwrap_or(Scalar::ZERO),
+                s_m_tick: s_m_tick.unwrap_or(Scalar::ZERO),
+                s_blinder: s_blinder.unwrap_or(Scalar::ZERO),
+            },
+            valid,
+        )
+    }
+}
+
+fn hidden_id_challenge(
+    h: G1Projective,
+    sigma: G1Projective,
+    commitment: IdCommitment,
+    pairing_commitment: Gt,
+    opening_commitment: G1Projective,
+    nonce: &[u8],
+) -> Scalar {
+    hash_to_scalar(&[
+        HIDDEN_ID_PROOF_DST,
+        &h.to_affine().to_compressed(),
+        &sigma.to_affine().to_compressed(),
+        &commitment.to_bytes(),
+        pairing_commitment.to_bytes().as_ref(),
+        &opening_commitment.to_affine().to_compressed(),
+        nonce,
+    ])
+}
+
+#[test]
+fn mismatched_blind_token() {
+    use crate::{BlindIssuanceLog, BlindTokenRequest, SecretKey};
+
+    struct OnceLog(Option<[u8; 32]>);
+
+    impl BlindIssuanceLog for OnceLog {
+        fn insert(&mut self, m_tick: &[u8; 32]) -> bool {
+            self.0.replace(*m_tick).is_none()
+        }
+    }
+
+    let sk = SecretKey::new(rand::thread_rng());
+    let pk = PublicKey::from(&sk);
+    let own = IdDigest::new(b"own identity").unwrap();
+    let victim = IdDigest::new(b"victim identity").unwrap();
+    // a blind token on the holder's m' and the victim's m
+    let mixed = IdDigest {
+        m: victim.m,
+        m_tick: own.m_tick,
+        a: own.a,
+    };
+    let (request, secrets) = BlindTokenRequest::new_with_digest(&mixed, rand::thread_rng());
+    let response = sk.blind_sign(&request, &mut OnceLog(None)).unwrap();
+    let token = Token(response.s * secrets.r.invert().unwrap());
+
+    let blinder = Scalar::random(rand::thread_rng());
+    let proof = HiddenIdProof::try_new_with_digest(
+        &token,
+        &[],
+        &mixed,
+        pk,
+        blinder,
+        b"nonce",
+        rand::thread_rng(),
+    )
+    .unwrap();
+    assert_eq!(proof.open(pk, b"nonce").unwrap_u8(), 1u8);
+
+    // the commitment does not open to the victim's id
+    let victim_commitment = IdCommitment::new(b"victim identity", blinder).unwrap();
+    assert_ne!(proof.commitment(), victim_commitment);
+    let (g, _, h) = IdCommitment::generators();
+    assert_ne!(proof.commitment().point(), g * victim.m + h * blinder);
+}
@@ -0,0 +1,361 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Blinding, Error, KeyId, Proof, PublicKey, Token};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use core::fmt::{self, Display, Formatter};
+use http::{header, request::Parts, HeaderMap, HeaderValue};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::Choice;
+
+/// The `Authorization` scheme used for oberon proofs
+pub const HTTP_AUTH_SCHEME: &str = "Oberon";
+
+/// A part of an http request that can be bound to a proof
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HttpComponent {
+    /// The request method
+    Method,
+    /// The authority followed by the path and query.
+    /// The scheme is left out since servers behind a TLS
+    /// terminator usually do not see it
+    TargetUri,
+    /// The `Content-Digest` header
+    ContentDigest,
+}
+
+impl HttpComponent {
+    /// The name of this component in the header
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Method => "@method",
+            Self::TargetUri => "@target-uri",
+            Self::ContentDigest => "content-digest",
+        }
+    }
+
+    /// Look up a component by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "@method" => Some(Self::Method),
+            "@target-uri" => Some(Self::TargetUri),
+            "content-digest" => Some(Self::ContentDigest),
+            _ => None,
+        }
+    }
+
+    /// The canonical value of this component in `parts`.
+    /// Returns `Error::InvalidParameters` if the request does not have it
+    pub fn value(&self, parts: &Parts) -> Result<String, Error> {
+        match self {
+            Self::Method => Ok(String::from(parts.method.as_str())),
+            Self::TargetUri => {
+                let authority = match parts.uri.authority() {
+                    Some(a) => String::from(a.as_str()),
+                    None => String::from(header_str(&parts.headers, header::HOST)?),
+                };
+                let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+                Ok(format!("{}{}", authority.to_ascii_lowercase(), path))
+            }
+            Self::ContentDigest => {
+                header_str(&parts.headers, "content-digest").map(|v| String::from(v.trim()))
+            }
+        }
+    }
+
+    /// Canonicalize `components` of `parts` into the message
+    /// and associated data passed to `Proof::sign_message`.
+    ///
+    /// Each component becomes the associated data `"<name>": <value>`
+    /// and the message is `"@signature-params": ("<name>" ...)`
+    /// so the list of covered components is also signed
+    pub fn canonicalize(
+        components: &[HttpComponent],
+        parts: &Parts,
+    ) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error> {
+        let mut names = Vec::with_capacity(components.len());
+        let mut associated_data = Vec::with_capacity(components.len());
+        for (i, c) in components.iter().enumerate() {
+            if components[..i].contains(c) {
+                return Err(Error::InvalidParameters);
+            }
+            names.push(format!("\"{}\"", c.name()));
+            associated_data.push(format!("\"{}\": {}", c.name(), c.value(parts)?).into_bytes());
+        }
+        let message = format!("\"@signature-params\": ({})", names.join(" ")).into_bytes();
+        Ok((message, associated_data))
+    }
+}
+
+/// The credentials sent in an `Authorization: Oberon ...` header
+///
+/// The header value is
+///
+/// `Oberon id="<id>", kid="<key id>", nonce="<nonce>", components="<names>", proof="<proof>"`
+///
+/// where id, key id, nonce and proof are unpadded base64url,
+/// `kid` is optional and names are separated by spaces.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let token = sk.sign(b"alice").unwrap();
+/// let components = [HttpComponent::Method, HttpComponent::TargetUri];
+///
+/// let (mut parts, _) = http::Request::post("https://api.example.com/v1/payments")
+///     .body(())
+///     .unwrap()
+///     .into_parts();
+/// let auth = HttpAuthorization::new(&token, &[], b"alice", &parts, &components, b"nonce", thread_rng()).unwrap();
+/// parts.headers.insert(http::header::AUTHORIZATION, auth.to_header_value());
+///
+/// let auth = HttpAuthorization::from_headers(&parts.headers).unwrap();
+/// assert_eq!(auth.id(), b"alice");
+/// assert_eq!(auth.verify(&parts, pk, &components).unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpAuthorization {
+    id: Vec<u8>,
+    key_id: Option<KeyId>,
+    nonce: Vec<u8>,
+    components: Vec<HttpComponent>,
+    proof: Proof,
+}
+
+impl HttpAuthorization {
+    /// Create a proof for `id` bound to `components` of the request
+    pub fn new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        parts: &Parts,
+        components: &[HttpComponent],
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_new(token, blindings, id, parts, components, nonce, rng).ok()
+    }
+
+    /// Create a proof for `id` bound to `components` of the request
+    /// returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        parts: &Parts,
+        components: &[HttpComponent],
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        let (message, associated_data) = HttpComponent::canonicalize(components, parts)?;
+        let proof = Proof::try_sign_message(
+            token,
+            blindings,
+            id.as_ref(),
+            message,
+            &associated_data,
+            nonce.as_ref(),
+            rng,
+        )?;
+        Ok(Self {
+            id: id.as_ref().to_vec(),
+            key_id: None,
+            nonce: nonce.as_ref().to_vec(),
+            components: components.to_vec(),
+            proof,
+        })
+    }
+
+    /// Include the id of the key the token was issued under
+    pub fn with_key_id(mut self, key_id: KeyId) -> Self {
+        self.key_id = Some(key_id);
+        self
+    }
+
+    /// The id the proof was made for
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// The id of the key the token was issued under if it was sent
+    pub fn key_id(&self) -> Option<KeyId> {
+        self.key_id
+    }
+
+    /// The nonce the proof was made with
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
+
+    /// The request components bound to the proof
+    pub fn components(&self) -> &[HttpComponent] {
+        &self.components
+    }
+
+    /// The proof
+    pub fn proof(&self) -> Proof {
+        self.proof
+    }
+
+    /// Encode these credentials as an `Authorization` header value
+    pub fn to_header_value(&self) -> HeaderValue {
+        // only base64url and component names are written so this is always visible ascii
+        HeaderValue::try_from(self.to_string()).expect("header value is visible ascii")
+    }
+
+    /// Parse the credentials from the `Authorization` header in `headers`
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, Error> {
+        Self::parse(header_str(headers, header::AUTHORIZATION)?)
+    }
+
+    /// Parse the credentials from an `Authorization` header value
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let value = value.trim();
+        let rest = value
+            .get(..HTTP_AUTH_SCHEME.len())
+            .filter(|s| s.eq_ignore_ascii_case(HTTP_AUTH_SCHEME))
+            .and_then(|_| value[HTTP_AUTH_SCHEME.len()..].strip_prefix(' '))
+            .ok_or(Error::InvalidEncoding)?;
+
+        let mut id = None;
+        let mut key_id = None;
+        let mut nonce = None;
+        let mut components = None;
+        let mut proof = None;
+        for param in rest.split(',') {
+            let (name, value) = param.trim().split_once('=').ok_or(Error::InvalidEncoding)?;
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .ok_or(Error::InvalidEncoding)?;
+            let slot = match name.trim() {
+                "id" => &mut id,
+                "kid" => &mut key_id,
+                "nonce" => &mut nonce,
+                "components" => &mut components,
+                "proof" => &mut proof,
+                _ => return Err(Error::InvalidEncoding),
+            };
+            if slot.replace(value).is_some() {
+                return Err(Error::InvalidEncoding);
+            }
+        }
+
+        let key_id = match key_id {
+            Some(k) => {
+                let bytes = decode(k)?;
+                let bytes = <&[u8; KeyId::BYTES]>::try_from(bytes.as_slice())
+                    .map_err(|_| Error::InvalidLength)?;
+                Some(
+                    Option::<KeyId>::from(KeyId::from_bytes(bytes))
+                        .ok_or(Error::InvalidEncoding)?,
+                )
+            }
+            None => None,
+        };
+        let components = components
+            .ok_or(Error::InvalidEncoding)?
+            .split(' ')
+            .filter(|n| !n.is_empty())
+            .map(|n| HttpComponent::from_name(n).ok_or(Error::InvalidEncoding))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = decode(proof.ok_or(Error::InvalidEncoding)?)?;
+        Ok(Self {
+            id: decode(id.ok_or(Error::InvalidEncoding)?)?,
+            key_id,
+            nonce: decode(nonce.ok_or(Error::InvalidEncoding)?)?,
+            components,
+            proof: Proof::try_from_bytes(&proof)?,
+        })
+    }
+
+    /// Check the proof is valid under `pk` for this request
+    /// and covers at least the `required` components
+    pub fn verify(&self, parts: &Parts, pk: PublicKey, required: &[HttpComponent]) -> Choice {
+        Choice::from(self.try_verify(parts, pk, required).is_ok() as u8)
+    }
+
+    /// Check the proof is valid under `pk` for this request
+    /// and covers at least the `required` components
+    /// returning the reason on failure
+    pub fn try_verify(
+        &self,
+        parts: &Parts,
+        pk: PublicKey,
+        required: &[HttpComponent],
+    ) -> Result<(), Error> {
+        if required.iter().any(|c| !self.components.contains(c)) {
+            return Err(Error::InvalidParameters);
+        }
+        let (message, associated_data) = HttpComponent::canonicalize(&self.components, parts)?;
+        self.proof
+            .try_verify_message(pk, &self.id, message, &associated_data, &self.nonce)
+    }
+
+    /// The `Content-Digest` header value for `body` using SHA-256
+    pub fn content_digest<B: AsRef<[u8]>>(body: B) -> HeaderValue {
+        let digest = Sha256::digest(body.as_ref());
+        HeaderValue::try_from(format!("sha-256=:{}:", STANDARD.encode(digest)))
+            .expect("header value is visible ascii")
+    }
+
+    /// Check the `Content-Digest` header in `parts` matches `body`.
+    /// Only SHA-256 digests are supported
+    pub fn check_content_digest<B: AsRef<[u8]>>(parts: &Parts, body: B) -> Result<(), Error> {
+        let sent = header_str(&parts.headers, "content-digest")?;
+        if sent.trim().as_bytes() == Self::content_digest(body).as_bytes() {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+impl Display for HttpAuthorization {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} id=\"{}\", ",
+            HTTP_AUTH_SCHEME,
+            URL_SAFE_NO_PAD.encode(&self.id)
+        )?;
+        if let Some(key_id) = &self.key_id {
+            write!(f, "kid=\"{}\", ", URL_SAFE_NO_PAD.encode(key_id.to_bytes()))?;
+        }
+        let names: Vec<&str> = self.components.iter().map(|c| c.name()).collect();
+        write!(
+            f,
+            "nonce=\"{}\", components=\"{}\", proof=\"{}\"",
+            URL_SAFE_NO_PAD.encode(&self.nonce),
+            names.join(" "),
+            URL_SAFE_NO_PAD.encode(self.proof.to_bytes())
+        )
+    }
+}
+
+fn header_str<K: header::AsHeaderName>(headers: &HeaderMap, name: K) -> Result<&str, Error> {
+    headers
+        .get(name)
+        .ok_or(Error::InvalidParameters)?
+        .to_str()
+        .map_err(|_| Error::InvalidEncoding)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, Error> {
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|_| Error::InvalidEncoding)
+}
@@ -0,0 +1,299 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Error};
+use core::convert::TryFrom;
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+
+/// The values derived from an identity that are used
+/// to create and verify tokens and proofs
+///
+/// m  = H_s(id)
+/// m' = H_s(m)
+/// A  = H_G(m')
+///
+/// Computing A requires hashing to the curve which is a large part
+/// of verification time. The digest can be computed once and
+/// reused for many proofs. Verifiers can also store the digest
+/// instead of the identity and load it with `from_bytes_unchecked`.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"test identity";
+/// let digest = IdDigest::new(id).unwrap();
+///
+/// let token = sk.sign(id).unwrap();
+/// let proof = Proof::new(&token, &[], id, b"nonce", thread_rng()).unwrap();
+///
+/// assert_eq!(token.verify_with_digest(pk, &digest).unwrap_u8(), 1u8);
+/// assert_eq!(proof.open_with_digest(pk, &digest, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "IdDigestFields", into = "IdDigestFields")]
+pub struct IdDigest {
+    pub(crate) m: Scalar,
+    pub(crate) m_tick: Scalar,
+    pub(crate) a: G1Projective,
+}
+
+/// The serialized form of `IdDigest` which is checked on deserialization
+#[derive(Copy, Clone, Deserialize, Serialize)]
+struct IdDigestFields {
+    m: Scalar,
+    m_tick: Scalar,
+    a: G1Projective,
+}
+
+impl From<IdDigest> for IdDigestFields {
+    fn from(d: IdDigest) -> Self {
+        Self {
+            m: d.m,
+            m_tick: d.m_tick,
+            a: d.a,
+        }
+    }
+}
+
+impl TryFrom<IdDigestFields> for IdDigest {
+    type Error = Error;
+
+    fn try_from(f: IdDigestFields) -> Result<Self, Error> {
+        Option::<Self>::from(Self::checked(f.m, f.m_tick, f.a)).ok_or(Error::InvalidEncoding)
+    }
+}
+
+impl Default for IdDigest {
+    fn default() -> Self {
+        Self {
+            m: Scalar::ZERO,
+            m_tick: Scalar::ZERO,
+            a: G1Projective::IDENTITY,
+        }
+    }
+}
+
+impl PartialEq for IdDigest {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for IdDigest {}
+
+impl ConstantTimeEq for IdDigest {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.m.ct_eq(&other.m) & self.m_tick.ct_eq(&other.m_tick) & self.a.ct_eq(&other.a)
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(IdDigest);
+
+try_from_bytes_impl!(IdDigest);
+
+impl IdDigest {
+    /// The number of bytes in an id digest
+    pub const BYTES: usize = 112;
+
+    /// Compute the digest for an identity, `None` if any
+    /// of the values are zero or the identity point
+    pub fn new<B: AsRef<[u8]>>(id: B) -> Option<Self> {
+        Self::try_new(id).ok()
+    }
+
+    /// Compute the digest for an identity
+    pub fn try_new<B: AsRef<[u8]>>(id: B) -> Result<Self, Error> {
+        Self::from_parts(&[id.as_ref()])
+    }
+
+    /// Compute the digest for the concatenation of `parts`
+    pub(crate) fn from_parts(parts: &[&[u8]]) -> Result<Self, Error> {
+        let m = hash_to_scalar(parts);
+        if m.is_zero().unwrap_u8() == 1 {
+            return Err(Error::ZeroHash);
+        }
+        let m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
+        if m_tick.is_zero().unwrap_u8() == 1 {
+            return Err(Error::ZeroHash);
+        }
+        let a = hash_to_curve(&m_tick.to_le_bytes()[..]);
+        if a.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(Self { m, m_tick, a })
+    }
+
+    /// The id scalar m = H_s(id)
+    pub fn id_scalar(&self) -> Scalar {
+        self.m
+    }
+
+    /// The scalar m' = H_s(m) the token base is hashed from.
+    /// Both are committed to by `IdCommitment`
+    pub fn hashed_id_scalar(&self) -> Scalar {
+        self.m_tick
+    }
+
+    /// Convert this digest into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..32].copy_from_slice(&self.m.to_le_bytes());
+        out[32..64].copy_from_slice(&self.m_tick.to_le_bytes());
+        out[64..].copy_from_slice(&self.a.to_affine().to_compressed());
+        out
+    }
+
+    /// Convert a byte sequence into a digest.
+    /// Checks that m' = H_s(m), A = H_G(m') and that m is not zero
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        Self::decode(data, Self::checked)
+    }
+
+    /// Convert a byte sequence from trusted storage into a digest.
+    /// Only checks that m' = H_s(m) and that no value is zero or the identity.
+    /// A is not recomputed from m' which skips hashing to the curve,
+    /// so use this only for digests this verifier computed and stored itself
+    pub fn from_bytes_unchecked(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        Self::decode(data, |m, m_tick, a| {
+            let expected_m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
+            CtOption::new(
+                Self { m, m_tick, a },
+                !m.is_zero()
+                    & !m_tick.is_zero()
+                    & expected_m_tick.ct_eq(&m_tick)
+                    & !a.is_identity(),
+            )
+        })
+    }
+
+    fn decode(
+        data: &[u8; Self::BYTES],
+        f: impl Fn(Scalar, Scalar, G1Projective) -> CtOption<Self>,
+    ) -> CtOption<Self> {
+        let m = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[..32]).unwrap());
+        let m_tick = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[32..64]).unwrap());
+        let a = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[64..]).unwrap())
+            .map(G1Projective::from);
+
+        m.and_then(|m| m_tick.and_then(|m_tick| a.and_then(|a| f(m, m_tick, a))))
+    }
+
+    /// Check the values were derived from each other
+    fn checked(m: Scalar, m_tick: Scalar, a: G1Projective) -> CtOption<Self> {
+        let expected_m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
+        let expected_a = hash_to_curve(&m_tick.to_le_bytes()[..]);
+        CtOption::new(
+            Self { m, m_tick, a },
+            !m.is_zero()
+                & !m_tick.is_zero()
+                & expected_m_tick.ct_eq(&m_tick)
+                & expected_a.ct_eq(&a)
+                & !a.is_identity(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+mod cache {
+    use super::IdDigest;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        vec::Vec,
+    };
+
+    /// A bounded least recently used cache of id digests
+    ///
+    /// ```
+    /// use oberon::*;
+    ///
+    /// let mut cache = IdDigestCache::new(2);
+    /// let digest = cache.get(b"alice").unwrap();
+    /// assert_eq!(digest, IdDigest::new(b"alice").unwrap());
+    /// cache.get(b"bobby");
+    /// cache.get(b"carol");
+    /// assert_eq!(cache.len(), 2);
+    /// assert!(!cache.contains(b"alice"));
+    /// ```
+    #[derive(Clone, Debug)]
+    pub struct IdDigestCache {
+        capacity: usize,
+        tick: u64,
+        entries: HashMap<Vec<u8>, (IdDigest, u64)>,
+        order: BTreeMap<u64, Vec<u8>>,
+    }
+
+    impl IdDigestCache {
+        /// Create a cache that holds at most `capacity` digests.
+        /// A capacity of zero disables caching
+        pub fn new(capacity: usize) -> Self {
+            Self {
+                capacity,
+                tick: 0,
+                entries: HashMap::with_capacity(capacity),
+                order: BTreeMap::new(),
+            }
+        }
+
+        /// The maximum number of digests held
+        pub fn capacity(&self) -> usize {
+            self.capacity
+        }
+
+        /// The number of digests held
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        /// Is the cache empty
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        /// Is the digest for `id` in the cache
+        pub fn contains<B: AsRef<[u8]>>(&self, id: B) -> bool {
+            self.entries.contains_key(id.as_ref())
+        }
+
+        /// Remove all digests
+        pub fn clear(&mut self) {
+            self.entries.clear();
+            self.order.clear();
+        }
+
+        /// Get the digest for `id`, computing and caching it if not present.
+        /// Evicts the least recently used digest if the cache is full
+        pub fn get<B: AsRef<[u8]>>(&mut self, id: B) -> Option<IdDigest> {
+            let id = id.as_ref();
+            self.tick += 1;
+            if let Some((digest, last)) = self.entries.get_mut(id) {
+                self.order.remove(last);
+                *last = self.tick;
+                self.order.insert(self.tick, id.to_vec());
+                return Some(*digest);
+            }
+
+            let digest = IdDigest::new(id)?;
+            if self.capacity == 0 {
+                return Some(digest);
+            }
+            if self.entries.len() >= self.capacity {
+                if let Some((_, oldest)) = self.order.pop_first() {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.entries.insert(id.to_vec(), (digest, self.tick));
+            self.order.insert(self.tick, id.to_vec());
+            Some(digest)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use cache::*;
@@ -0,0 +1,206 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{or_proof, util::*, Blinding, Error, IdDigest, PublicKey, Token};
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConditionallySelectable};
+
+const ISSUER_HIDING_DST: &[u8] = b"OBERON_ISSUER_HIDING_PROOF_";
+
+/// A zero-knowledge proof of a token valid under one of a list
+/// of public keys that does not reveal which one
+///
+/// See Issuer hiding in MATH.md for the OR proof with one branch
+/// per key. The proof is 96 + 64n bytes.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let partners: Vec<SecretKey> = (0..3).map(|_| SecretKey::new(thread_rng())).collect();
+/// let keys: Vec<PublicKey> = partners.iter().map(PublicKey::from).collect();
+///
+/// let id = b"test identity";
+/// let token = partners[1].sign(id).unwrap();
+/// let proof = IssuerHidingProof::new(&token, &[], id, &keys, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(&keys, id, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IssuerHidingProof {
+    pub(crate) h: G1Projective,
+    pub(crate) sigma: G1Projective,
+    pub(crate) challenges: Vec<Scalar>,
+    pub(crate) responses: Vec<Scalar>,
+}
+
+impl IssuerHidingProof {
+    /// Create a new proof that `token` is valid under one of `keys`
+    pub fn new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        keys: &[PublicKey],
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_new(token, blindings, id, keys, nonce, rng).ok()
+    }
+
+    /// Create a new proof returning the reason on failure.
+    /// Returns `Error::VerificationFailed` if the token is not
+    /// valid under any of the keys
+    pub fn try_new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        keys: &[PublicKey],
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        check_keys(keys)?;
+        let digest = IdDigest::try_new(id)?;
+        let unblinded = Token(token.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>());
+        if unblinded.0.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+
+        // find the issuer without branching on which key matched
+        let mut found = Choice::from(0u8);
+        let mut index = 0u32;
+        for (i, pk) in keys.iter().enumerate() {
+            let valid = unblinded.verify_with_digest(*pk, &digest);
+            index.conditional_assign(&(i as u32), valid & !found);
+            found |= valid;
+        }
+        if found.unwrap_u8() == 0 {
+            return Err(Error::VerificationFailed);
+        }
+        let index = index as usize;
+
+        let (h, sigma, t) = randomize(unblinded.0, digest.a, &mut rng);
+        let (base, statements) = statements(h, sigma, keys, &digest);
+        let (challenges, responses) = or_proof::prove(
+            base,
+            &statements,
+            index,
+            t,
+            |commitments| {
+                issuer_hiding_challenge(h, sigma, keys, &digest, commitments, nonce.as_ref())
+            },
+            &mut rng,
+        );
+
+        Ok(Self {
+            h,
+            sigma,
+            challenges,
+            responses,
+        })
+    }
+
+    /// The number of keys this proof was made for
+    pub fn keys(&self) -> usize {
+        self.challenges.len()
+    }
+
+    /// Check whether this proof is valid under one of `keys`
+    pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        keys: &[PublicKey],
+        id: B,
+        nonce: N,
+    ) -> Choice {
+        Choice::from(self.try_open(keys, id, nonce).is_ok() as u8)
+    }
+
+    /// Check whether this proof is valid under one of `keys`
+    /// returning the reason on failure
+    pub fn try_open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        keys: &[PublicKey],
+        id: B,
+        nonce: N,
+    ) -> Result<(), Error> {
+        check_keys(keys)?;
+        if keys.len() != self.challenges.len() || keys.len() != self.responses.len() {
+            return Err(Error::InvalidParameters);
+        }
+        if (self.h.is_identity() | self.sigma.is_identity()).unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let digest = IdDigest::try_new(id)?;
+
+        let (base, statements) = statements(self.h, self.sigma, keys, &digest);
+        let valid = or_proof::verify(
+            base,
+            &statements,
+            &self.challenges,
+            &self.responses,
+            |commitments| {
+                issuer_hiding_challenge(
+                    self.h,
+                    self.sigma,
+                    keys,
+                    &digest,
+                    commitments,
+                    nonce.as_ref(),
+                )
+            },
+        );
+        if valid.unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+fn check_keys(keys: &[PublicKey]) -> Result<(), Error> {
+    if keys.is_empty() || keys.len() > u32::MAX as usize {
+        return Err(Error::InvalidParameters);
+    }
+    if keys.iter().any(|pk| pk.is_invalid().unwrap_u8() == 1) {
+        return Err(Error::InvalidPublicKey);
+    }
+    Ok(())
+}
+
+fn statements(
+    h: G1Projective,
+    sigma: G1Projective,
+    keys: &[PublicKey],
+    digest: &IdDigest,
+) -> (Gt, Vec<Gt>) {
+    or_proof::statements(
+        h,
+        sigma,
+        keys.iter()
+            .map(|pk| pk.sum_of_products(digest.m_tick, digest.m)),
+    )
+}
+
+fn issuer_hiding_challenge(
+    h: G1Projective,
+    sigma: G1Projective,
+    keys: &[PublicKey],
+    digest: &IdDigest,
+    commitments: &[Gt],
+    nonce: &[u8],
+) -> Scalar {
+    let mut data = Vec::new();
+    data.extend_from_slice(ISSUER_HIDING_DST);
+    data.extend_from_slice(&h.to_affine().to_compressed());
+    data.extend_from_slice(&sigma.to_affine().to_compressed());
+    data.extend_from_slice(&digest.m.to_le_bytes());
+    data.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+    for (pk, commitment) in keys.iter().zip(commitments.iter()) {
+        data.extend_from_slice(&pk.to_bytes());
+        data.extend_from_slice(commitment.to_bytes().as_ref());
+    }
+    data.extend_from_slice(nonce);
+    hash_to_scalar(&[&data])
+}
@@ -0,0 +1,346 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Proof, PublicKey};
+use core::convert::TryFrom;
+use digest::{ExtendableOutput, Update, XofReader};
+use serde::{Deserialize, Serialize};
+use sha3::Shake256;
+use subtle::{Choice, ConstantTimeEq, CtOption};
+
+const KEY_ID_DST: &[u8] = b"OBERON_KEY_ID_SHAKE-256_";
+
+/// A stable fingerprint of a public key
+#[derive(
+    Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize, Serialize,
+)]
+pub struct KeyId([u8; 32]);
+
+impl From<&PublicKey> for KeyId {
+    fn from(pk: &PublicKey) -> Self {
+        let mut hasher = Shake256::default();
+        hasher.update(KEY_ID_DST);
+        hasher.update(&pk.to_bytes());
+        let mut reader = hasher.finalize_xof();
+        let mut id = [0u8; Self::BYTES];
+        reader.read(&mut id);
+        Self(id)
+    }
+}
+
+impl ConstantTimeEq for KeyId {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+try_from_bytes_impl!(KeyId);
+
+impl KeyId {
+    /// The number of bytes in a key id
+    pub const BYTES: usize = 32;
+
+    /// Convert this key id into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0
+    }
+
+    /// Convert a byte sequence into a key id
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        CtOption::new(Self(*data), Choice::from(1u8))
+    }
+}
+
+impl PublicKey {
+    /// The fingerprint of this public key
+    pub fn key_id(&self) -> KeyId {
+        KeyId::from(self)
+    }
+}
+
+/// A proof with the id of the key its token was issued under
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProofEnvelope {
+    pub(crate) version: u8,
+    pub(crate) key_id: KeyId,
+    pub(crate) proof: Proof,
+}
+
+try_from_bytes_impl!(ProofEnvelope);
+
+impl ProofEnvelope {
+    /// The number of bytes in a proof envelope
+    pub const BYTES: usize = 1 + KeyId::BYTES + Proof::BYTES;
+    /// The current envelope version
+    pub const VERSION: u8 = 1;
+
+    /// Wrap a proof made with a token issued under `pk`
+    pub fn new(proof: Proof, pk: &PublicKey) -> Self {
+        Self {
+            version: Self::VERSION,
+            key_id: pk.key_id(),
+            proof,
+        }
+    }
+
+    /// The envelope version
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The id of the key the token was issued under
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    /// The wrapped proof
+    pub fn proof(&self) -> Proof {
+        self.proof
+    }
+
+    /// Convert this envelope into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[0] = self.version;
+        out[1..33].copy_from_slice(&self.key_id.0);
+        out[33..].copy_from_slice(&self.proof.to_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into an envelope.
+    /// Fails for unknown versions
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let key_id = KeyId(<[u8; 32]>::try_from(&data[1..33]).unwrap());
+        let proof = Proof::from_bytes(&<[u8; Proof::BYTES]>::try_from(&data[33..]).unwrap());
+        match Option::<Proof>::from(proof) {
+            Some(proof) => CtOption::new(
+                Self {
+                    version: data[0],
+                    key_id,
+                    proof,
+                },
+                data[0].ct_eq(&Self::VERSION),
+            ),
+            None => CtOption::new(Self::default(), Choice::from(0u8)),
+        }
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod ring {
+    use super::{KeyId, ProofEnvelope};
+    use crate::{Error, PublicKey};
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+    use serde::{Deserialize, Serialize};
+    use subtle::Choice;
+
+    /// A public key with its validity dates in seconds since the unix epoch
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    #[serde(try_from = "KeyringEntryFields")]
+    pub struct KeyringEntry {
+        key_id: KeyId,
+        public_key: PublicKey,
+        not_before: u64,
+        retired_at: Option<u64>,
+    }
+
+    /// The serialized form of an entry before the key id is checked
+    #[derive(Deserialize)]
+    struct KeyringEntryFields {
+        key_id: KeyId,
+        public_key: PublicKey,
+        not_before: u64,
+        retired_at: Option<u64>,
+    }
+
+    impl TryFrom<KeyringEntryFields> for KeyringEntry {
+        type Error = Error;
+
+        fn try_from(f: KeyringEntryFields) -> Result<Self, Error> {
+            if f.key_id != f.public_key.key_id() {
+                return Err(Error::InvalidEncoding);
+            }
+            Ok(Self {
+                key_id: f.key_id,
+                public_key: f.public_key,
+                not_before: f.not_before,
+                retired_at: f.retired_at,
+            })
+        }
+    }
+
+    impl KeyringEntry {
+        /// The key fingerprint
+        pub fn key_id(&self) -> KeyId {
+            self.key_id
+        }
+
+        /// The public key
+        pub fn public_key(&self) -> PublicKey {
+            self.public_key
+        }
+
+        /// When the key became valid
+        pub fn not_before(&self) -> u64 {
+            self.not_before
+        }
+
+        /// When the key was retired if it has been
+        pub fn retired_at(&self) -> Option<u64> {
+            self.retired_at
+        }
+    }
+
+    /// The issuer public keys accepted by a verifier
+    ///
+    /// Retired keys still verify for `grace_period` seconds
+    /// after they were retired.
+    ///
+    /// ```
+    /// use oberon::*;
+    /// use rand::thread_rng;
+    ///
+    /// let old_sk = SecretKey::new(thread_rng());
+    /// let new_sk = SecretKey::new(thread_rng());
+    /// let mut keyring = Keyring::new(3600);
+    /// keyring.add(PublicKey::from(&old_sk), 1_600_000_000);
+    /// keyring.add(PublicKey::from(&new_sk), 1_700_000_000);
+    /// keyring.retire(&PublicKey::from(&old_sk).key_id(), 1_700_000_000).unwrap();
+    ///
+    /// let token = old_sk.sign(b"test identity").unwrap();
+    /// let proof = Proof::new(&token, &[], b"test identity", b"nonce", thread_rng()).unwrap();
+    /// let envelope = ProofEnvelope::new(proof, &PublicKey::from(&old_sk));
+    ///
+    /// assert_eq!(keyring.open(&envelope, b"test identity", b"nonce", 1_700_000_100).unwrap_u8(), 1u8);
+    /// assert_eq!(keyring.open(&envelope, b"test identity", b"nonce", 1_700_010_000).unwrap_u8(), 0u8);
+    /// ```
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    pub struct Keyring {
+        grace_period: u64,
+        entries: Vec<KeyringEntry>,
+    }
+
+    impl Keyring {
+        /// Create an empty keyring where retired keys verify
+        /// for `grace_period` seconds
+        pub fn new(grace_period: u64) -> Self {
+            Self {
+                grace_period,
+                entries: Vec::new(),
+            }
+        }
+
+        /// The number of seconds retired keys still verify
+        pub fn grace_period(&self) -> u64 {
+            self.grace_period
+        }
+
+        /// The number of keys
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        /// Is the keyring empty
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        /// All keys in the order they were added
+        pub fn entries(&self) -> &[KeyringEntry] {
+            &self.entries
+        }
+
+        /// Add a key that is valid from `not_before`. Adding a key
+        /// that is already present updates its start date
+        pub fn add(&mut self, public_key: PublicKey, not_before: u64) -> KeyId {
+            let key_id = public_key.key_id();
+            match self.entries.iter_mut().find(|e| e.key_id == key_id) {
+                Some(entry) => entry.not_before = not_before,
+                None => self.entries.push(KeyringEntry {
+                    key_id,
+                    public_key,
+                    not_before,
+                    retired_at: None,
+                }),
+            }
+            key_id
+        }
+
+        /// Retire a key at `retired_at`
+        pub fn retire(&mut self, key_id: &KeyId, retired_at: u64) -> Result<(), Error> {
+            let entry = self
+                .entries
+                .iter_mut()
+                .find(|e| e.key_id == *key_id)
+                .ok_or(Error::UnknownKey)?;
+            entry.retired_at = Some(retired_at);
+            Ok(())
+        }
+
+        /// Remove a key
+        pub fn remove(&mut self, key_id: &KeyId) -> Option<KeyringEntry> {
+            let index = self.entries.iter().position(|e| e.key_id == *key_id)?;
+            Some(self.entries.remove(index))
+        }
+
+        /// Get a key by its id
+        pub fn get(&self, key_id: &KeyId) -> Option<&KeyringEntry> {
+            self.entries.iter().find(|e| e.key_id == *key_id)
+        }
+
+        /// The most recent key that is valid at `now` and not retired.
+        /// This is the key new tokens should be issued under
+        pub fn current(&self, now: u64) -> Option<&KeyringEntry> {
+            self.entries
+                .iter()
+                .filter(|e| e.not_before <= now && e.retired_at.is_none_or(|r| now < r))
+                .max_by_key(|e| e.not_before)
+        }
+
+        /// Is the key valid at `now` including the grace period
+        pub fn is_valid(&self, key_id: &KeyId, now: u64) -> bool {
+            self.get(key_id).is_some_and(|e| self.entry_valid(e, now))
+        }
+
+        /// Check the proof with the key named in the envelope
+        pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+            &self,
+            envelope: &ProofEnvelope,
+            id: B,
+            nonce: N,
+            now: u64,
+        ) -> Choice {
+            Choice::from(self.try_open(envelope, id, nonce, now).is_ok() as u8)
+        }
+
+        /// Check the proof with the key named in the envelope
+        /// returning the reason on failure. Returns `Error::UnknownKey`
+        /// if the key is not in the keyring and `Error::Expired` if
+        /// it is not valid at `now`
+        pub fn try_open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+            &self,
+            envelope: &ProofEnvelope,
+            id: B,
+            nonce: N,
+            now: u64,
+        ) -> Result<(), Error> {
+            let entry = self.get(&envelope.key_id).ok_or(Error::UnknownKey)?;
+            if !self.entry_valid(entry, now) {
+                return Err(Error::Expired);
+            }
+            envelope.proof.try_open(entry.public_key, id, nonce)
+        }
+
+        fn entry_valid(&self, entry: &KeyringEntry, now: u64) -> bool {
+            now >= entry.not_before
+                && entry
+                    .retired_at
+                    .is_none_or(|r| now <= r.saturating_add(self.grace_period))
+        }
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use ring::*;
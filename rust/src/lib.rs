@@ -28,12 +28,15 @@
 //! let blinding = Blinding::new(b"<your passcode>");
 //! let blinded_token = token - &blinding;
 //!
-//! let timestamp = [0x00, 0x05, 0xc0, 0xba, 0xea, 0x9c, 0x82, 0xb0];
+//! // the verifier sends a fresh challenge to use as the nonce
+//! let challenge_key = ChallengeKey::new(thread_rng());
+//! let now = 1_700_000_000;
+//! let challenge = challenge_key.issue(now, thread_rng());
 //!
-//! match Proof::new(&blinded_token, &[blinding], id, &timestamp, thread_rng()) {
+//! match Proof::new(&blinded_token, &[blinding], id, challenge.to_bytes(), thread_rng()) {
 //!     None => panic!(""),
 //!     Some(proof) => {
-//!         assert_eq!(proof.open(pk, id, &timestamp).unwrap_u8(), 1u8);
+//!         assert!(proof.open_with_challenge(pk, id, &challenge, &challenge_key, now + 5, 60).is_ok());
 //!     }
 //! }
 //! ```
@@ -64,6 +67,9 @@
 //! reverse all but two of the blindings by adding them back in.
 //! This restriction doesn't apply when `alloc` or `std` features are used.
 //!
+//! `FactorSet` keeps labelled factors together, applies them in the right order
+//! and gives the blindings to pass to `Proof::new`.
+//!
 //! This crate also supports compiling to wasm. Make sure to use --features=wasm
 //! to get the necessary functions
 
@@ -78,9 +84,9 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
-#[cfg(feature = "alloc")]
+#[cfg(any(feature = "alloc", feature = "std"))]
 extern crate alloc;
-#[cfg(feature = "std")]
+#[cfg(any(feature = "std", feature = "tower"))]
 extern crate std;
 
 #[cfg(feature = "wasm")]
@@ -145,17 +151,66 @@ macro_rules! wasm_slice_impl {
     };
 }
 
+macro_rules! try_from_bytes_impl {
+    ($name:ident) => {
+        impl $name {
+            /// Convert a byte slice into this type checking
+            /// the length and the encoding
+            pub fn try_from_bytes(data: &[u8]) -> Result<Self, crate::Error> {
+                let bytes: &[u8; $name::BYTES] = core::convert::TryFrom::try_from(data)
+                    .map_err(|_| crate::Error::InvalidLength)?;
+                Option::<Self>::from($name::from_bytes(bytes)).ok_or(crate::Error::InvalidEncoding)
+            }
+        }
+    };
+}
+
+mod aggregate;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod batch;
+mod blind;
 mod blinding;
+mod challenge;
+mod co_proof;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod dkg;
+mod epoch;
+mod error;
+mod factor;
 #[cfg(feature = "ffi")]
 mod ffi;
+mod hidden_id;
+#[cfg(feature = "http")]
+mod http_auth;
+mod id_digest;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod issuer_hiding;
+mod keyring;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod membership;
+mod message;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod multi;
+mod oprf;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod or_proof;
+#[cfg(feature = "password")]
+mod password;
 #[cfg(feature = "php")]
 mod php;
+mod prepared;
 mod proof;
+mod pseudonym;
 mod public_key;
 #[cfg(feature = "python")]
 mod python;
+mod revocation;
 mod secret_key;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod threshold;
 mod token;
+#[cfg(feature = "tower")]
+mod tower_auth;
 mod util;
 #[cfg(feature = "wasm")]
 mod web;
@@ -182,20 +237,61 @@ pub mod inner_types {
     };
 }
 
+pub use aggregate::*;
+#[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use batch::*;
+pub use blind::*;
 pub use blinding::*;
+pub use challenge::*;
+pub use co_proof::*;
+#[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use dkg::*;
+pub use epoch::*;
+pub use error::*;
+pub use factor::*;
 #[cfg_attr(docsrs, doc(cfg(feature = "ffi")))]
 #[cfg(feature = "ffi")]
 pub use ffi::*;
+pub use hidden_id::*;
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+#[cfg(feature = "http")]
+pub use http_auth::*;
+pub use id_digest::*;
+#[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use issuer_hiding::*;
+pub use keyring::*;
+#[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use membership::*;
+#[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use multi::*;
+pub use oprf::*;
+#[cfg_attr(docsrs, doc(cfg(feature = "password")))]
+#[cfg(feature = "password")]
+pub use password::*;
 #[cfg_attr(docsrs, doc(cfg(feature = "php")))]
 #[cfg(feature = "php")]
 pub use php::*;
+pub use prepared::*;
 pub use proof::*;
+pub use pseudonym::*;
 pub use public_key::*;
 #[cfg_attr(docsrs, doc(cfg(feature = "python")))]
 #[cfg(feature = "python")]
 pub use python::*;
+pub use revocation::*;
 pub use secret_key::*;
+#[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use threshold::*;
 pub use token::*;
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[cfg(feature = "tower")]
+pub use tower_auth::*;
 #[cfg_attr(docsrs, doc(cfg(feature = "wasm")))]
 #[cfg(feature = "wasm")]
 pub use web::*;
@@ -0,0 +1,198 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{or_proof, util::*, Blinding, Error, IdDigest, PublicKey, Token};
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::Choice;
+
+const MEMBERSHIP_DST: &[u8] = b"OBERON_MEMBERSHIP_PROOF_";
+
+/// A zero-knowledge proof of a valid token for one of a list
+/// of ids that does not reveal which one
+///
+/// This is a Schnorr OR proof with one branch per id as described
+/// under Group membership in MATH.md. The proof is 96 + 64n bytes.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let on_call = [b"alice".as_slice(), b"bob", b"carol"];
+///
+/// let token = sk.sign(b"bob").unwrap();
+/// let proof = MembershipProof::new(&token, &[], b"bob", &on_call, pk, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(pk, &on_call, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MembershipProof {
+    pub(crate) h: G1Projective,
+    pub(crate) sigma: G1Projective,
+    pub(crate) challenges: Vec<Scalar>,
+    pub(crate) responses: Vec<Scalar>,
+}
+
+impl MembershipProof {
+    /// Create a new proof that the token for `id` is valid under `pk`
+    /// and `id` is one of `group`
+    pub fn new<B: AsRef<[u8]>, G: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        group: &[G],
+        pk: PublicKey,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_new(token, blindings, id, group, pk, nonce, rng).ok()
+    }
+
+    /// Create a new proof returning the reason on failure.
+    /// Returns `Error::InvalidParameters` if `id` is not in `group`
+    pub fn try_new<B: AsRef<[u8]>, G: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        group: &[G],
+        pk: PublicKey,
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        let digests = group_digests(group)?;
+        let index = group
+            .iter()
+            .position(|g| g.as_ref() == id.as_ref())
+            .ok_or(Error::InvalidParameters)?;
+        let digest = &digests[index];
+        let sigma = token.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>();
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+
+        let (h, sigma, t) = randomize(sigma, digest.a, &mut rng);
+        let (base, statements) = statements(h, sigma, pk, &digests);
+        let (challenges, responses) = or_proof::prove(
+            base,
+            &statements,
+            index,
+            t,
+            |commitments| membership_challenge(h, sigma, pk, &digests, commitments, nonce.as_ref()),
+            &mut rng,
+        );
+
+        Ok(Self {
+            h,
+            sigma,
+            challenges,
+            responses,
+        })
+    }
+
+    /// The number of ids this proof was made for
+    pub fn members(&self) -> usize {
+        self.challenges.len()
+    }
+
+    /// Check whether this proof is valid for one of the ids in `group`
+    pub fn open<G: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        group: &[G],
+        nonce: N,
+    ) -> Choice {
+        Choice::from(self.try_open(pk, group, nonce).is_ok() as u8)
+    }
+
+    /// Check whether this proof is valid for one of the ids in `group`
+    /// returning the reason on failure
+    pub fn try_open<G: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        group: &[G],
+        nonce: N,
+    ) -> Result<(), Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        let digests = group_digests(group)?;
+        if group.len() != self.challenges.len() || group.len() != self.responses.len() {
+            return Err(Error::InvalidParameters);
+        }
+        if (self.h.is_identity() | self.sigma.is_identity()).unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+
+        let (base, statements) = statements(self.h, self.sigma, pk, &digests);
+        let valid = or_proof::verify(
+            base,
+            &statements,
+            &self.challenges,
+            &self.responses,
+            |commitments| {
+                membership_challenge(
+                    self.h,
+                    self.sigma,
+                    pk,
+                    &digests,
+                    commitments,
+                    nonce.as_ref(),
+                )
+            },
+        );
+        if valid.unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+fn group_digests<G: AsRef<[u8]>>(group: &[G]) -> Result<Vec<IdDigest>, Error> {
+    if group.is_empty() || group.len() > u32::MAX as usize {
+        return Err(Error::InvalidParameters);
+    }
+    group.iter().map(IdDigest::try_new).collect()
+}
+
+fn statements(
+    h: G1Projective,
+    sigma: G1Projective,
+    pk: PublicKey,
+    digests: &[IdDigest],
+) -> (Gt, Vec<Gt>) {
+    or_proof::statements(
+        h,
+        sigma,
+        digests.iter().map(|d| pk.sum_of_products(d.m_tick, d.m)),
+    )
+}
+
+fn membership_challenge(
+    h: G1Projective,
+    sigma: G1Projective,
+    pk: PublicKey,
+    digests: &[IdDigest],
+    commitments: &[Gt],
+    nonce: &[u8],
+) -> Scalar {
+    let mut data = Vec::new();
+    data.extend_from_slice(MEMBERSHIP_DST);
+    data.extend_from_slice(&h.to_affine().to_compressed());
+    data.extend_from_slice(&sigma.to_affine().to_compressed());
+    data.extend_from_slice(&pk.to_bytes());
+    data.extend_from_slice(&(digests.len() as u32).to_le_bytes());
+    for (digest, commitment) in digests.iter().zip(commitments.iter()) {
+        data.extend_from_slice(&digest.m.to_le_bytes());
+        data.extend_from_slice(commitment.to_bytes().as_ref());
+    }
+    data.extend_from_slice(nonce);
+    hash_to_scalar(&[&data])
+}
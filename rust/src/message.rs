@@ -0,0 +1,111 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Blinding, Error, Proof, PublicKey, Token};
+use digest::{ExtendableOutput, Update, XofReader};
+use rand_core::{CryptoRng, RngCore};
+use sha3::Shake256;
+use subtle::Choice;
+
+const MESSAGE_TRANSCRIPT_DST: &[u8] = b"OBERON_MESSAGE_PROOF_V1_";
+
+impl Proof {
+    /// The number of bytes in a message binding
+    pub const MESSAGE_BINDING_BYTES: usize = 64;
+
+    /// Compute the value used as the proof nonce when signing
+    /// a message. This is SHAKE-256 over the transcript
+    ///
+    /// "OBERON_MESSAGE_PROOF_V1_" || I2OSP(|nonce|, 8) || nonce
+    /// || I2OSP(|message|, 8) || message
+    /// || I2OSP(k, 8) || I2OSP(|ad_1|, 8) || ad_1 || ... || I2OSP(|ad_k|, 8) || ad_k
+    ///
+    /// where I2OSP is the big endian encoding and ad_i are the
+    /// associated data such as the request method, path and audience
+    pub fn message_binding<M: AsRef<[u8]>, A: AsRef<[u8]>, N: AsRef<[u8]>>(
+        message: M,
+        associated_data: &[A],
+        nonce: N,
+    ) -> [u8; Self::MESSAGE_BINDING_BYTES] {
+        let mut hasher = Shake256::default();
+        hasher.update(MESSAGE_TRANSCRIPT_DST);
+        update_with_length(&mut hasher, nonce.as_ref());
+        update_with_length(&mut hasher, message.as_ref());
+        hasher.update(&(associated_data.len() as u64).to_be_bytes());
+        for ad in associated_data {
+            update_with_length(&mut hasher, ad.as_ref());
+        }
+        let mut reader = hasher.finalize_xof();
+        let mut out = [0u8; Self::MESSAGE_BINDING_BYTES];
+        reader.read(&mut out);
+        out
+    }
+
+    /// Create a proof that also signs `message` and `associated_data`
+    /// so it cannot be replayed with a different request
+    pub fn sign_message<B: AsRef<[u8]>, M: AsRef<[u8]>, A: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        message: M,
+        associated_data: &[A],
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_sign_message(token, blindings, id, message, associated_data, nonce, rng).ok()
+    }
+
+    /// Create a proof that also signs `message` and `associated_data`
+    /// returning the reason on failure
+    pub fn try_sign_message<B: AsRef<[u8]>, M: AsRef<[u8]>, A: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        message: M,
+        associated_data: &[A],
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        let binding = Self::message_binding(message, associated_data, nonce);
+        Self::try_new(token, blindings, id, binding, rng)
+    }
+
+    /// Check whether this proof is valid and signs `message` and `associated_data`
+    pub fn verify_message<B: AsRef<[u8]>, M: AsRef<[u8]>, A: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        message: M,
+        associated_data: &[A],
+        nonce: N,
+    ) -> Choice {
+        self.open(
+            pk,
+            id,
+            Self::message_binding(message, associated_data, nonce),
+        )
+    }
+
+    /// Check whether this proof is valid and signs `message` and `associated_data`
+    /// returning the reason on failure
+    pub fn try_verify_message<B: AsRef<[u8]>, M: AsRef<[u8]>, A: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        message: M,
+        associated_data: &[A],
+        nonce: N,
+    ) -> Result<(), Error> {
+        self.try_open(
+            pk,
+            id,
+            Self::message_binding(message, associated_data, nonce),
+        )
+    }
+}
+
+fn update_with_length(hasher: &mut Shake256, data: &[u8]) {
+    hasher.update(&(data.len() as u64).to_be_bytes());
+    hasher.update(data);
+}
@@ -0,0 +1,412 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Error, PublicKey, SecretKey, Token};
+use alloc::{vec, vec::Vec};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::Zeroize;
+
+const DISCLOSURE_DST: &[u8] = b"OBERON_MULTI_ATTRIBUTE_DISCLOSURE_";
+
+/// The secret key for signing tokens on a vector of attributes
+/// Display is not implemented to prevent accidental leak of the key
+///
+/// A key with one attribute is the same as a [`SecretKey`]
+/// and creates the same tokens.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = MultiSecretKey::new(3, thread_rng());
+/// let pk = MultiPublicKey::from(&sk);
+/// let attributes = [&b"user id"[..], b"tenant", b"admin"];
+/// let token = sk.sign(&attributes).unwrap();
+/// assert_eq!(token.verify(&pk, &attributes).unwrap_u8(), 1u8);
+///
+/// // reveal only the role
+/// let proof = DisclosureProof::new(&token, &pk, &attributes, &[2], b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(&pk, &[(2, b"admin")], b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MultiSecretKey {
+    pub(crate) w: Scalar,
+    pub(crate) x: Scalar,
+    pub(crate) y: Vec<Scalar>,
+}
+
+impl Drop for MultiSecretKey {
+    fn drop(&mut self) {
+        self.w.zeroize();
+        self.x.zeroize();
+        self.y.iter_mut().for_each(|y| y.zeroize());
+    }
+}
+
+impl From<&SecretKey> for MultiSecretKey {
+    fn from(sk: &SecretKey) -> Self {
+        Self {
+            w: sk.w,
+            x: sk.x,
+            y: vec![sk.y],
+        }
+    }
+}
+
+impl PartialEq for MultiSecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for MultiSecretKey {}
+
+impl ConstantTimeEq for MultiSecretKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        if self.y.len() != other.y.len() {
+            return Choice::from(0u8);
+        }
+        self.y.iter().zip(other.y.iter()).fold(
+            self.w.ct_eq(&other.w) & self.x.ct_eq(&other.x),
+            |acc, (a, b)| acc & a.ct_eq(b),
+        )
+    }
+}
+
+impl MultiSecretKey {
+    /// Generate a new random key for signing `attributes` values
+    pub fn new(attributes: usize, mut rng: impl RngCore + CryptoRng) -> Self {
+        Self {
+            w: Scalar::random(&mut rng),
+            x: Scalar::random(&mut rng),
+            y: (0..attributes).map(|_| Scalar::random(&mut rng)).collect(),
+        }
+    }
+
+    /// The number of attributes signed by this key
+    pub fn attributes(&self) -> usize {
+        self.y.len()
+    }
+
+    /// Sign the attributes, `None` if the values are not
+    /// valid for this key
+    pub fn sign<B: AsRef<[u8]>>(&self, attributes: &[B]) -> Option<MultiToken> {
+        self.try_sign(attributes).ok()
+    }
+
+    /// Sign the attributes
+    pub fn try_sign<B: AsRef<[u8]>>(&self, attributes: &[B]) -> Result<MultiToken, Error> {
+        if attributes.is_empty() || attributes.len() != self.y.len() {
+            return Err(Error::InvalidParameters);
+        }
+        let digest = AttributeDigest::new(attributes)?;
+        let exp = self
+            .y
+            .iter()
+            .zip(digest.m.iter())
+            .fold(self.x + self.w * digest.m_tick, |acc, (y, m)| acc + *y * m);
+        let sigma = digest.a * exp;
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(MultiToken(sigma))
+    }
+}
+
+/// The public key for verifying multi-attribute tokens
+/// with one G2 element per attribute
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct MultiPublicKey {
+    pub(crate) w: G2Projective,
+    pub(crate) x: G2Projective,
+    pub(crate) y: Vec<G2Projective>,
+}
+
+impl From<&MultiSecretKey> for MultiPublicKey {
+    fn from(sk: &MultiSecretKey) -> Self {
+        Self {
+            w: G2Projective::GENERATOR * sk.w,
+            x: G2Projective::GENERATOR * sk.x,
+            y: sk.y.iter().map(|y| G2Projective::GENERATOR * y).collect(),
+        }
+    }
+}
+
+impl From<PublicKey> for MultiPublicKey {
+    fn from(pk: PublicKey) -> Self {
+        Self {
+            w: pk.w,
+            x: pk.x,
+            y: vec![pk.y],
+        }
+    }
+}
+
+impl MultiPublicKey {
+    /// The number of attributes verified by this key
+    pub fn attributes(&self) -> usize {
+        self.y.len()
+    }
+
+    /// Is this public key invalid
+    pub fn is_invalid(&self) -> Choice {
+        self.y.iter().fold(
+            self.w.is_identity() | self.x.is_identity() | Choice::from(self.y.is_empty() as u8),
+            |acc, y| acc | y.is_identity(),
+        )
+    }
+}
+
+/// A token signed over a vector of attributes
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct MultiToken(pub(crate) G1Projective);
+
+impl From<Token> for MultiToken {
+    fn from(token: Token) -> Self {
+        Self(token.0)
+    }
+}
+
+try_from_bytes_impl!(MultiToken);
+
+impl MultiToken {
+    /// The number of bytes in a token
+    pub const BYTES: usize = 48;
+
+    /// Check whether the token is valid for the attributes
+    pub fn verify<B: AsRef<[u8]>>(&self, pk: &MultiPublicKey, attributes: &[B]) -> Choice {
+        if attributes.len() != pk.y.len()
+            || (pk.is_invalid() | self.0.is_identity()).unwrap_u8() == 1
+        {
+            return Choice::from(0u8);
+        }
+        let digest = match AttributeDigest::new(attributes) {
+            Ok(d) => d,
+            Err(_) => return Choice::from(0u8),
+        };
+        let rhs =
+            pk.y.iter()
+                .zip(digest.m.iter())
+                .fold(pk.x + pk.w * digest.m_tick, |acc, (y, m)| acc + *y * m);
+        multi_miller_loop(&[
+            (&digest.a.to_affine(), &G2Prepared::from(rhs.to_affine())),
+            (
+                &(-self.0).to_affine(),
+                &G2Prepared::from(G2Affine::generator()),
+            ),
+        ])
+        .final_exponentiation()
+        .is_identity()
+    }
+
+    /// Convert this token into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence into a token
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data).map(|p| Self(G1Projective::from(p)))
+    }
+}
+
+/// A zero-knowledge proof of a multi-attribute token that
+/// reveals a chosen subset of the attributes
+///
+/// The hidden attributes, m' and t are proven with a Schnorr
+/// proof in the target group, see Multiple attributes in MATH.md
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DisclosureProof {
+    pub(crate) h: G1Projective,
+    pub(crate) sigma: G1Projective,
+    pub(crate) challenge: Scalar,
+    pub(crate) s_t: Scalar,
+    pub(crate) s_m_tick: Scalar,
+    pub(crate) s_hidden: Vec<Scalar>,
+}
+
+impl DisclosureProof {
+    /// Create a proof that reveals the attributes at the `disclosed` indices
+    pub fn new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &MultiToken,
+        pk: &MultiPublicKey,
+        attributes: &[B],
+        disclosed: &[usize],
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        if attributes.is_empty() || attributes.len() != pk.y.len() {
+            return Err(Error::InvalidParameters);
+        }
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        let mut revealed = vec![false; attributes.len()];
+        for i in disclosed {
+            *revealed.get_mut(*i).ok_or(Error::InvalidParameters)? = true;
+        }
+        let digest = AttributeDigest::new(attributes)?;
+
+        let (h, sigma, t) = randomize(token.0, digest.a, &mut rng);
+
+        let k_t = Scalar::random(&mut rng);
+        let k_m_tick = Scalar::random(&mut rng);
+        let k_hidden: Vec<Scalar> = revealed
+            .iter()
+            .filter(|d| !**d)
+            .map(|_| Scalar::random(&mut rng))
+            .collect();
+        let hidden_keys = pk.y.iter().zip(revealed.iter()).filter(|(_, d)| !**d);
+        let rhs = hidden_keys.zip(k_hidden.iter()).fold(
+            G2Projective::GENERATOR * k_t + pk.w * k_m_tick,
+            |acc, ((y, _), k)| acc + *y * k,
+        );
+        let commitment = pairing(&h.to_affine(), &rhs.to_affine());
+
+        let disclosed_values = revealed
+            .iter()
+            .zip(digest.m.iter())
+            .enumerate()
+            .filter(|(_, (d, _))| **d)
+            .map(|(i, (_, m))| (i, *m));
+        let challenge =
+            disclosure_challenge(h, sigma, commitment, disclosed_values, nonce.as_ref());
+
+        let hidden_values = digest
+            .m
+            .iter()
+            .zip(revealed.iter())
+            .filter(|(_, d)| !**d)
+            .map(|(m, _)| m);
+        Ok(Self {
+            h,
+            sigma,
+            challenge,
+            s_t: k_t + challenge * t,
+            s_m_tick: k_m_tick + challenge * digest.m_tick,
+            s_hidden: k_hidden
+                .iter()
+                .zip(hidden_values)
+                .map(|(k, m)| k + challenge * m)
+                .collect(),
+        })
+    }
+
+    /// Check the proof against the disclosed attributes given
+    /// as (index, value) pairs
+    pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: &MultiPublicKey,
+        disclosed: &[(usize, B)],
+        nonce: N,
+    ) -> Choice {
+        if (pk.is_invalid() | self.h.is_identity() | self.sigma.is_identity()).unwrap_u8() == 1 {
+            return Choice::from(0u8);
+        }
+        let mut revealed = vec![None; pk.y.len()];
+        for (i, value) in disclosed {
+            let m = hash_to_scalar(&[value.as_ref()]);
+            match revealed.get_mut(*i) {
+                Some(Some(prev)) if prev != &m => return Choice::from(0u8),
+                Some(slot) => *slot = Some(m),
+                None => return Choice::from(0u8),
+            }
+        }
+        let hidden_keys: Vec<&G2Projective> =
+            pk.y.iter()
+                .zip(revealed.iter())
+                .filter(|(_, m)| m.is_none())
+                .map(|(y, _)| y)
+                .collect();
+        if hidden_keys.len() != self.s_hidden.len() {
+            return Choice::from(0u8);
+        }
+
+        let disclosed_rhs =
+            pk.y.iter()
+                .zip(revealed.iter())
+                .filter_map(|(y, m)| m.map(|m| *y * m))
+                .fold(pk.x, |acc, p| acc + p);
+        let mut points = [G1Affine::identity(); 2];
+        G1Projective::batch_normalize(&[self.sigma, -self.h], &mut points);
+        let lhs = multi_miller_loop(&[
+            (&points[0], &G2Prepared::from(G2Affine::generator())),
+            (&points[1], &G2Prepared::from(disclosed_rhs.to_affine())),
+        ])
+        .final_exponentiation();
+
+        let rhs = hidden_keys.iter().zip(self.s_hidden.iter()).fold(
+            G2Projective::GENERATOR * self.s_t + pk.w * self.s_m_tick,
+            |acc, (y, s)| acc + **y * s,
+        );
+        let commitment = pairing(&self.h.to_affine(), &rhs.to_affine()) - lhs * self.challenge;
+
+        let disclosed_values = revealed
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.map(|m| (i, m)));
+        let challenge = disclosure_challenge(
+            self.h,
+            self.sigma,
+            commitment,
+            disclosed_values,
+            nonce.as_ref(),
+        );
+        challenge.ct_eq(&self.challenge)
+    }
+}
+
+struct AttributeDigest {
+    m: Vec<Scalar>,
+    m_tick: Scalar,
+    a: G1Projective,
+}
+
+impl AttributeDigest {
+    /// m_i = H_s(attribute_i), m' = H_s(m_1 || ... || m_n), A = H_G(m').
+    /// With one attribute these are the values of `IdDigest`
+    fn new<B: AsRef<[u8]>>(attributes: &[B]) -> Result<Self, Error> {
+        let m: Vec<Scalar> = attributes
+            .iter()
+            .map(|a| hash_to_scalar(&[a.as_ref()]))
+            .collect();
+        if m.iter().any(|m| m.is_zero().unwrap_u8() == 1) {
+            return Err(Error::ZeroHash);
+        }
+        let bytes: Vec<[u8; 32]> = m.iter().map(|m| m.to_le_bytes()).collect();
+        let slices: Vec<&[u8]> = bytes.iter().map(|b| &b[..]).collect();
+        let m_tick = hash_to_scalar(&slices);
+        if m_tick.is_zero().unwrap_u8() == 1 {
+            return Err(Error::ZeroHash);
+        }
+        let a = hash_to_curve(&m_tick.to_le_bytes()[..]);
+        if a.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(Self { m, m_tick, a })
+    }
+}
+
+fn disclosure_challenge(
+    h: G1Projective,
+    sigma: G1Projective,
+    commitment: Gt,
+    disclosed: impl Iterator<Item = (usize, Scalar)>,
+    nonce: &[u8],
+) -> Scalar {
+    let mut data = Vec::new();
+    data.extend_from_slice(DISCLOSURE_DST);
+    data.extend_from_slice(&h.to_affine().to_compressed());
+    data.extend_from_slice(&sigma.to_affine().to_compressed());
+    data.extend_from_slice(commitment.to_bytes().as_ref());
+    for (i, m) in disclosed {
+        data.extend_from_slice(&(i as u32).to_le_bytes());
+        data.extend_from_slice(&m.to_le_bytes());
+    }
+    data.extend_from_slice(nonce);
+    hash_to_scalar(&[&data])
+}
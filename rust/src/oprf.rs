@@ -0,0 +1,198 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Blinding, Error};
+use digest::{ExtendableOutput, Update, XofReader};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::Shake256;
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::ZeroizeOnDrop;
+
+const OPRF_KEY_DST: &[u8] = b"OBERON_OPRF_KEY_";
+const OPRF_INPUT_DST: &[u8] = b"OBERON_OPRF_INPUT_";
+const OPRF_OUTPUT_DST: &[u8] = b"OBERON_OPRF_OUTPUT_";
+
+/// The server key for deriving blindings from PINs with an oblivious PRF
+/// Display is not implemented to prevent accidental leak of the key
+///
+/// A stolen blinded token with a `Blinding::new(pin)` can be checked against
+/// every PIN offline. A blinding derived through the server can only be
+/// guessed by asking the server, which should rate limit evaluations per id.
+/// The server never learns the PIN or the blinding.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let server_key = OprfKey::new(thread_rng());
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let id = b"test identity";
+///
+/// let (request, secrets) = OprfRequest::blind(id, b"1234", thread_rng()).unwrap();
+/// // sent to the server which rate limits requests for this id
+/// let response = server_key.evaluate(&request).unwrap();
+/// // sent back to the user
+/// let blinding = response.finalize(&secrets, id, b"1234").unwrap();
+///
+/// let blinded_token = sk.sign(id).unwrap() - &blinding;
+/// let proof = Proof::new(&blinded_token, &[blinding], id, b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(pk, id, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct OprfKey(Scalar);
+
+impl Default for OprfKey {
+    fn default() -> Self {
+        Self(Scalar::ZERO)
+    }
+}
+
+impl PartialEq for OprfKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for OprfKey {}
+
+impl ConstantTimeEq for OprfKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+try_from_bytes_impl!(OprfKey);
+
+impl OprfKey {
+    /// The number of bytes in an oprf key
+    pub const BYTES: usize = 32;
+
+    /// Generate a new random key
+    pub fn new(rng: impl RngCore + CryptoRng) -> Self {
+        Self(non_zero_scalar(rng))
+    }
+
+    /// Generate a new key from a seed using SHAKE-256
+    pub fn hash(data: &[u8]) -> Self {
+        Self(hash_to_scalar(&[OPRF_KEY_DST, data]))
+    }
+
+    /// Evaluate the PRF on a blinded input without learning it
+    pub fn evaluate(&self, request: &OprfRequest) -> Result<OprfResponse, Error> {
+        if (self.0.is_zero() | request.0.is_identity()).unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(OprfResponse(request.0 * self.0))
+    }
+
+    /// Convert this key into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_le_bytes()
+    }
+
+    /// Convert a byte sequence into a key
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        Scalar::from_le_bytes(data).map(Self)
+    }
+}
+
+/// A PIN blinded by the user to send to the server
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OprfRequest(pub(crate) G1Projective);
+
+/// The value the user keeps to unblind the server's response
+/// Display is not implemented to prevent accidental leak of the secret
+#[derive(Clone, Debug, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct OprfSecrets {
+    r: Scalar,
+}
+
+/// The server's evaluation of a blinded PIN
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OprfResponse(pub(crate) G1Projective);
+
+try_from_bytes_impl!(OprfRequest);
+
+impl OprfRequest {
+    /// The number of bytes in an oprf request
+    pub const BYTES: usize = 48;
+
+    /// Blind `pin` for the token on `id`
+    pub fn blind<B: AsRef<[u8]>, P: AsRef<[u8]>>(
+        id: B,
+        pin: P,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(Self, OprfSecrets), Error> {
+        let point = hash_to_curve(&oprf_hash(OPRF_INPUT_DST, id.as_ref(), pin.as_ref(), None));
+        if point.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let r = non_zero_scalar(rng);
+        Ok((Self(point * r), OprfSecrets { r }))
+    }
+
+    /// Convert this request into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence into a request
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data).map(|p| Self(G1Projective::from(p)))
+    }
+}
+
+try_from_bytes_impl!(OprfResponse);
+
+impl OprfResponse {
+    /// The number of bytes in an oprf response
+    pub const BYTES: usize = 48;
+
+    /// Remove the blinding and derive the token blinding for `pin`
+    pub fn finalize<B: AsRef<[u8]>, P: AsRef<[u8]>>(
+        &self,
+        secrets: &OprfSecrets,
+        id: B,
+        pin: P,
+    ) -> Result<Blinding, Error> {
+        if self.0.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let r_inv = Option::<Scalar>::from(secrets.r.invert()).ok_or(Error::InvalidParameters)?;
+        let evaluated = (self.0 * r_inv).to_affine().to_compressed();
+        let output = oprf_hash(OPRF_OUTPUT_DST, id.as_ref(), pin.as_ref(), Some(&evaluated));
+        Ok(Blinding::new(&output))
+    }
+
+    /// Convert this response into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence into a response
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data).map(|p| Self(G1Projective::from(p)))
+    }
+}
+
+fn oprf_hash(dst: &[u8], id: &[u8], pin: &[u8], evaluated: Option<&[u8; 48]>) -> [u8; 64] {
+    let mut hasher = Shake256::default();
+    hasher.update(dst);
+    hasher.update(&(id.len() as u64).to_be_bytes());
+    hasher.update(id);
+    hasher.update(&(pin.len() as u64).to_be_bytes());
+    hasher.update(pin);
+    if let Some(evaluated) = evaluated {
+        hasher.update(evaluated);
+    }
+    let mut reader = hasher.finalize_xof();
+    let mut out = [0u8; 64];
+    reader.read(&mut out);
+    out
+}
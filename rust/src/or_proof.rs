@@ -0,0 +1,78 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+
+/// Returns e(h, P) and e(s', P) - e(h, R_i) for each R_i = W.m' + X + Y.m
+pub(crate) fn statements(
+    h: G1Projective,
+    sigma: G1Projective,
+    rhs: impl Iterator<Item = G2Projective>,
+) -> (Gt, Vec<Gt>) {
+    let h_affine = h.to_affine();
+    let base = pairing(&h_affine, &G2Affine::generator());
+    let lhs = pairing(&sigma.to_affine(), &G2Affine::generator());
+    let statements = rhs
+        .map(|r| lhs - pairing(&h_affine, &r.to_affine()))
+        .collect();
+    (base, statements)
+}
+
+/// Prove knowledge of t for the statement at `index` where
+/// `challenge` hashes the commitments into the Fiat-Shamir challenge.
+/// Returns the challenges and responses for each statement
+pub(crate) fn prove(
+    base: Gt,
+    statements: &[Gt],
+    index: usize,
+    t: Scalar,
+    challenge: impl FnOnce(&[Gt]) -> Scalar,
+    mut rng: impl RngCore + CryptoRng,
+) -> (Vec<Scalar>, Vec<Scalar>) {
+    let k = Scalar::random(&mut rng);
+    let mut challenges = Vec::with_capacity(statements.len());
+    let mut responses = Vec::with_capacity(statements.len());
+    let mut commitments = Vec::with_capacity(statements.len());
+    for (i, y) in statements.iter().enumerate() {
+        if i == index {
+            challenges.push(Scalar::ZERO);
+            responses.push(Scalar::ZERO);
+            commitments.push(base * k);
+        } else {
+            let c = Scalar::random(&mut rng);
+            let s = Scalar::random(&mut rng);
+            commitments.push(base * s - *y * c);
+            challenges.push(c);
+            responses.push(s);
+        }
+    }
+
+    let c = challenge(&commitments) - challenges.iter().sum::<Scalar>();
+    challenges[index] = c;
+    responses[index] = k + c * t;
+    (challenges, responses)
+}
+
+/// Check the challenges sum to the Fiat-Shamir challenge
+/// of the recomputed commitments
+pub(crate) fn verify(
+    base: Gt,
+    statements: &[Gt],
+    challenges: &[Scalar],
+    responses: &[Scalar],
+    challenge: impl FnOnce(&[Gt]) -> Scalar,
+) -> Choice {
+    if statements.len() != challenges.len() || statements.len() != responses.len() {
+        return Choice::from(0u8);
+    }
+    let commitments: Vec<Gt> = statements
+        .iter()
+        .zip(challenges.iter().zip(responses.iter()))
+        .map(|(y, (c, s))| base * s - *y * c)
+        .collect();
+    challenge(&commitments).ct_eq(&challenges.iter().sum::<Scalar>())
+}
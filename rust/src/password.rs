@@ -0,0 +1,146 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{Blinding, Error};
+use argon2::{Algorithm, Argon2, Params, Version};
+use core::convert::TryFrom;
+use digest::{ExtendableOutput, Update, XofReader};
+use serde::{Deserialize, Serialize};
+use sha3::Shake256;
+use subtle::{Choice, CtOption};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use alloc::string::String;
+
+const PASSWORD_SALT_DST: &[u8] = b"OBERON_PASSWORD_SALT_";
+
+/// The Argon2id cost parameters used to derive a blinding from a password.
+///
+/// The parameters must be the same every time the blinding is derived
+/// so store `to_bytes` alongside the blinded token.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PasswordParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl Default for PasswordParams {
+    /// The OWASP recommended minimum of 19 MiB, 2 passes and 1 lane
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+try_from_bytes_impl!(PasswordParams);
+
+impl PasswordParams {
+    /// The number of bytes in the encoded parameters
+    pub const BYTES: usize = 12;
+
+    /// Create new parameters with memory size `m_cost` in KiB,
+    /// `t_cost` passes and `p_cost` lanes
+    pub fn new(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Self, Error> {
+        let params = Self {
+            m_cost,
+            t_cost,
+            p_cost,
+        };
+        params.argon2().map(|_| params)
+    }
+
+    /// The memory size in KiB
+    pub fn m_cost(&self) -> u32 {
+        self.m_cost
+    }
+
+    /// The number of passes
+    pub fn t_cost(&self) -> u32 {
+        self.t_cost
+    }
+
+    /// The number of lanes
+    pub fn p_cost(&self) -> u32 {
+        self.p_cost
+    }
+
+    /// Convert these parameters into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..4].copy_from_slice(&self.m_cost.to_be_bytes());
+        out[4..8].copy_from_slice(&self.t_cost.to_be_bytes());
+        out[8..].copy_from_slice(&self.p_cost.to_be_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into parameters
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let params = Self {
+            m_cost: u32::from_be_bytes(<[u8; 4]>::try_from(&data[..4]).unwrap()),
+            t_cost: u32::from_be_bytes(<[u8; 4]>::try_from(&data[4..8]).unwrap()),
+            p_cost: u32::from_be_bytes(<[u8; 4]>::try_from(&data[8..]).unwrap()),
+        };
+        let valid = Choice::from(params.argon2().is_ok() as u8);
+        CtOption::new(params, valid)
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, Error> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(64))
+            .map_err(|_| Error::InvalidParameters)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl Blinding {
+    /// Create a blinding factor from a password for the token on `id`.
+    ///
+    /// The password is NFKC normalized so equivalent unicode forms give
+    /// the same blinding, then stretched with Argon2id using a salt
+    /// derived from `id`. Use `Blinding::new` for high entropy factors.
+    ///
+    /// ```
+    /// use oberon::{Blinding, PasswordParams};
+    ///
+    /// let params = PasswordParams::new(64, 1, 1).unwrap();
+    /// let blinding = Blinding::from_password("correct horse", b"test identity", &params).unwrap();
+    ///
+    /// assert_ne!(blinding.to_bytes(), Blinding::new(b"correct horse").to_bytes());
+    /// ```
+    pub fn from_password<B: AsRef<[u8]>>(
+        password: &str,
+        id: B,
+        params: &PasswordParams,
+    ) -> Result<Self, Error> {
+        let password = Zeroizing::new(password.nfkc().collect::<String>());
+        if password.is_empty() {
+            return Err(Error::InvalidParameters);
+        }
+        let mut output = Zeroizing::new([0u8; 64]);
+        params
+            .argon2()?
+            .hash_password_into(
+                password.as_bytes(),
+                &password_salt(id.as_ref()),
+                &mut *output,
+            )
+            .map_err(|_| Error::InvalidParameters)?;
+        Ok(Self::new(&*output))
+    }
+}
+
+fn password_salt(id: &[u8]) -> [u8; 32] {
+    let mut hasher = Shake256::default();
+    hasher.update(PASSWORD_SALT_DST);
+    hasher.update(&(id.len() as u64).to_be_bytes());
+    hasher.update(id);
+    let mut reader = hasher.finalize_xof();
+    let mut salt = [0u8; 32];
+    reader.read(&mut salt);
+    salt
+}
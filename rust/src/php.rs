@@ -2,14 +2,19 @@
     Copyright Michael Lodder. All Rights Reserved.
     SPDX-License-Identifier: Apache-2.0
 */
-use crate::{Blinding, Proof, PublicKey, SecretKey, Token};
+use crate::{Blinding, Error, PasswordParams, Proof, PublicKey, SecretKey, Token};
 #[cfg_attr(windows, feature(abi_vectorcall))]
 use ext_php_rs::prelude::*;
 use rand::thread_rng;
 
-use ext_php_rs::zend::ModuleEntry;
+use ext_php_rs::zend::{ce, ModuleEntry};
 use ext_php_rs::{info_table_end, info_table_row, info_table_start};
-use std::{convert::TryFrom, string::String, vec::Vec};
+use std::{format, string::String, vec::Vec};
+
+/// Convert an error into an exception with the shared error code
+fn php_error(what: &str, e: Error) -> PhpException {
+    PhpException::new(format!("{}: {}", what, e), e.code(), ce::exception())
+}
 
 /// Create a new secret key
 ///
@@ -25,12 +30,12 @@ pub fn oberon_new_secret_key() -> Vec<u8> {
 /// @param string $sk The secret key
 ///
 /// @return string The public key
+/// @throws Exception if the secret key is invalid
 #[php_function]
-pub fn oberon_get_public_key(sk: Vec<u8>) -> Option<Vec<u8>> {
-    match secret_key(sk) {
-        None => None,
-        Some(sk) => Some(PublicKey::from(&sk).to_bytes().to_vec()),
-    }
+pub fn oberon_get_public_key(sk: Vec<u8>) -> PhpResult<Vec<u8>> {
+    SecretKey::try_from_bytes(&sk)
+        .map(|sk| PublicKey::from(&sk).to_bytes().to_vec())
+        .map_err(|e| php_error("Invalid secret key", e))
 }
 
 /// Create new secret key from a seed
@@ -48,13 +53,14 @@ pub fn oberon_secret_key_from_seed(seed: Vec<u8>) -> Vec<u8> {
 /// @param string $sk The secret key
 /// @param string $id The identifier
 ///
-/// @return string|null The token if successful, null on failure
+/// @return string The token
+/// @throws Exception on failure
 #[php_function]
-pub fn oberon_new_token(sk: Vec<u8>, id: String) -> Option<Vec<u8>> {
-    match secret_key(sk) {
-        None => None,
-        Some(sk) => Some(Token::new(&sk, id.as_bytes()).unwrap().to_bytes().to_vec()),
-    }
+pub fn oberon_new_token(sk: Vec<u8>, id: String) -> PhpResult<Vec<u8>> {
+    let sk = SecretKey::try_from_bytes(&sk).map_err(|e| php_error("Invalid secret key", e))?;
+    Token::try_new(&sk, id.as_bytes())
+        .map(|t| t.to_bytes().to_vec())
+        .map_err(|e| php_error("Unable to create token", e))
 }
 
 /// Verify a token for a given ID
@@ -64,11 +70,15 @@ pub fn oberon_new_token(sk: Vec<u8>, id: String) -> Option<Vec<u8>> {
 /// @param string $id The identifier
 ///
 /// @param bool result of check
+/// @throws Exception if the inputs are malformed
 #[php_function]
-pub fn oberon_verify_token(token: Vec<u8>, pk: Vec<u8>, id: String) -> bool {
-    match (get_token(token), public_key(pk)) {
-        (Some(t), Some(k)) => t.verify(k, id.as_bytes()).unwrap_u8() == 1,
-        (_, _) => false,
+pub fn oberon_verify_token(token: Vec<u8>, pk: Vec<u8>, id: String) -> PhpResult<bool> {
+    let t = Token::try_from_bytes(&token).map_err(|e| php_error("Invalid token", e))?;
+    let k = PublicKey::try_from_bytes(&pk).map_err(|e| php_error("Invalid public key", e))?;
+    match t.try_verify(k, id.as_bytes()) {
+        Ok(()) => Ok(true),
+        Err(Error::VerificationFailed) => Ok(false),
+        Err(e) => Err(php_error("Unable to verify token", e)),
     }
 }
 
@@ -82,21 +92,42 @@ pub fn oberon_create_blinding(data: Vec<u8>) -> Vec<u8> {
     Blinding::new(&data).to_bytes().to_vec()
 }
 
+/// Create a blinding factor from a password for the token on the identifier
+///
+/// @param string $password The password
+/// @param string $id The identifier
+/// @param string|null $params The encoded password parameters or null for the defaults
+///
+/// @return string The blinding factor
+/// @throws Exception if the parameters are invalid
+#[php_function]
+pub fn oberon_create_password_blinding(
+    password: String,
+    id: String,
+    params: Option<Vec<u8>>,
+) -> PhpResult<Vec<u8>> {
+    let params = params
+        .map(|p| PasswordParams::try_from_bytes(&p))
+        .transpose()
+        .map_err(|e| php_error("Invalid password parameters", e))?
+        .unwrap_or_default();
+    Blinding::from_password(&password, id.as_bytes(), &params)
+        .map(|b| b.to_bytes().to_vec())
+        .map_err(|e| php_error("Unable to create blinding", e))
+}
+
 /// Adds a blinding factor to the token
 ///
 /// @param string $token The token
 /// @param string $data an arbitrary byte sequence
 ///
-/// @param string|null The blinding factor or null if invalid token
+/// @param string The new token
+/// @throws Exception if the token is invalid
 #[php_function]
-pub fn oberon_add_blinding(token: Vec<u8>, data: Vec<u8>) -> Option<Vec<u8>> {
-    match get_token(token) {
-        None => None,
-        Some(t) => {
-            let val = t - Blinding::new(&data);
-            Some(val.to_bytes().to_vec())
-        }
-    }
+pub fn oberon_add_blinding(token: Vec<u8>, data: Vec<u8>) -> PhpResult<Vec<u8>> {
+    let t = Token::try_from_bytes(&token).map_err(|e| php_error("Invalid token", e))?;
+    let val = t - Blinding::new(&data);
+    Ok(val.to_bytes().to_vec())
 }
 
 /// Removes a blinding factor to the token
@@ -104,16 +135,41 @@ pub fn oberon_add_blinding(token: Vec<u8>, data: Vec<u8>) -> Option<Vec<u8>> {
 /// @param string $token The token
 /// @param string $data an arbitrary byte sequence
 ///
-/// @param string The blinding factor or null if invalid token
+/// @param string The new token
+/// @throws Exception if the token is invalid
 #[php_function]
-pub fn oberon_remove_blinding(token: Vec<u8>, data: Vec<u8>) -> Option<Vec<u8>> {
-    match get_token(token) {
-        None => None,
-        Some(t) => {
-            let val = t + Blinding::new(&data);
-            Some(val.to_bytes().to_vec())
-        }
-    }
+pub fn oberon_remove_blinding(token: Vec<u8>, data: Vec<u8>) -> PhpResult<Vec<u8>> {
+    let t = Token::try_from_bytes(&token).map_err(|e| php_error("Invalid token", e))?;
+    let val = t + Blinding::new(&data);
+    Ok(val.to_bytes().to_vec())
+}
+
+/// Replaces the blinding factors on the token
+///
+/// @param string $token The token
+/// @param array[string] $old_blindings The blinding factors on the token
+/// @param array[string] $new_blindings The blinding factors to apply instead
+/// @param string $pk The public key
+/// @param string $id The identifier
+///
+/// @return string The new token
+/// @throws Exception if the old blinding factors do not give a valid token
+#[php_function]
+pub fn oberon_rebind(
+    token: Vec<u8>,
+    old_blindings: Vec<Vec<u8>>,
+    new_blindings: Vec<Vec<u8>>,
+    pk: Vec<u8>,
+    id: String,
+) -> PhpResult<Vec<u8>> {
+    let old: Vec<Blinding> = old_blindings.iter().map(|b| Blinding::new(b)).collect();
+    let new: Vec<Blinding> = new_blindings.iter().map(|b| Blinding::new(b)).collect();
+
+    let t = Token::try_from_bytes(&token).map_err(|e| php_error("Invalid token", e))?;
+    let k = PublicKey::try_from_bytes(&pk).map_err(|e| php_error("Invalid public key", e))?;
+    t.rebind(&old, &new, k, id.as_bytes())
+        .map(|t| t.to_bytes().to_vec())
+        .map_err(|e| php_error("Unable to rebind token", e))
 }
 
 /// Creates a proof using a nonce received from a verifier
@@ -123,21 +179,22 @@ pub fn oberon_remove_blinding(token: Vec<u8>, data: Vec<u8>) -> Option<Vec<u8>>
 /// @param array[string] $blindings The blinding factors
 /// @param string $nonce The proof nonce
 ///
-/// @return string|null The proof is successful, null on failure
+/// @return string The proof
+/// @throws Exception on failure
 #[php_function]
 pub fn oberon_create_proof(
     token: Vec<u8>,
     id: String,
     blindings: Vec<Vec<u8>>,
     nonce: Vec<u8>,
-) -> Option<Vec<u8>> {
+) -> PhpResult<Vec<u8>> {
     let bs: Vec<Blinding> = blindings.iter().map(|b| Blinding::new(b)).collect();
 
     let rng = thread_rng();
-    match get_token(token) {
-        None => None,
-        Some(t) => Proof::new(&t, &bs, id.as_bytes(), nonce, rng).map(|p| p.to_bytes().to_vec()),
-    }
+    let t = Token::try_from_bytes(&token).map_err(|e| php_error("Invalid token", e))?;
+    Proof::try_new(&t, &bs, id.as_bytes(), nonce, rng)
+        .map(|p| p.to_bytes().to_vec())
+        .map_err(|e| php_error("Unable to create proof", e))
 }
 
 /// Creates a proof using a nonce received from a verifier
@@ -148,36 +205,23 @@ pub fn oberon_create_proof(
 /// @param string $nonce The proof nonce
 ///
 /// @return bool The check proof result
+/// @throws Exception if the inputs are malformed
 #[php_function]
-pub fn oberon_verify_proof(proof: Vec<u8>, pk: Vec<u8>, id: String, nonce: Vec<u8>) -> bool {
-    match (get_proof(proof), public_key(pk)) {
-        (Some(p), Some(k)) => p.open(k, id.as_bytes(), nonce).unwrap_u8() == 1,
-        (_, _) => false,
+pub fn oberon_verify_proof(
+    proof: Vec<u8>,
+    pk: Vec<u8>,
+    id: String,
+    nonce: Vec<u8>,
+) -> PhpResult<bool> {
+    let p = Proof::try_from_bytes(&proof).map_err(|e| php_error("Invalid proof", e))?;
+    let k = PublicKey::try_from_bytes(&pk).map_err(|e| php_error("Invalid public key", e))?;
+    match p.try_open(k, id.as_bytes(), nonce) {
+        Ok(()) => Ok(true),
+        Err(Error::VerificationFailed) => Ok(false),
+        Err(e) => Err(php_error("Unable to verify proof", e)),
     }
 }
 
-macro_rules! from_bytes {
-    ($name:ident, $type:ident) => {
-        fn $name(input: Vec<u8>) -> Option<$type> {
-            match <[u8; $type::BYTES]>::try_from(input.as_slice()) {
-                Err(_) => None,
-                Ok(bytes) => {
-                    let val = $type::from_bytes(&bytes);
-                    if val.is_some().unwrap_u8() == 1u8 {
-                        Some(val.unwrap())
-                    } else {
-                        None
-                    }
-                }
-            }
-        }
-    };
-}
-from_bytes!(secret_key, SecretKey);
-from_bytes!(public_key, PublicKey);
-from_bytes!(get_token, Token);
-from_bytes!(get_proof, Proof);
-
 /// The php init function
 #[no_mangle]
 pub extern "C" fn php_module_info(_module: *mut ModuleEntry) {
@@ -0,0 +1,115 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{Error, IdDigest, Proof, PublicKey, Token};
+use subtle::Choice;
+
+/// A public key that has been validated and had its pairing
+/// computations cached for verifying many proofs and tokens.
+///
+/// `Proof::open` and `Token::verify` compute W.m' + X + Y.m in G2
+/// and prepare it for the pairing on every call. Since the public key
+/// is fixed, the scalars are instead applied in G1 and the G2 elements
+/// are prepared once so each check only needs cheaper G1 operations.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let verifier = PreparedPublicKey::new(pk).unwrap();
+///
+/// let id = b"test identity";
+/// let token = sk.sign(id).unwrap();
+/// let proof = Proof::new(&token, &[], id, b"nonce", thread_rng()).unwrap();
+///
+/// assert_eq!(verifier.verify_token(&token, id).unwrap_u8(), 1u8);
+/// assert_eq!(verifier.open(&proof, id, b"nonce").unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey {
+    pk: PublicKey,
+    w: G2Prepared,
+    x: G2Prepared,
+    y: G2Prepared,
+    generator: G2Prepared,
+}
+
+impl PreparedPublicKey {
+    /// Validate and prepare a public key, `None` if the public key is invalid
+    pub fn new(pk: PublicKey) -> Option<Self> {
+        Self::try_new(pk).ok()
+    }
+
+    /// Validate and prepare a public key
+    pub fn try_new(pk: PublicKey) -> Result<Self, Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        Ok(Self {
+            pk,
+            w: G2Prepared::from(pk.w.to_affine()),
+            x: G2Prepared::from(pk.x.to_affine()),
+            y: G2Prepared::from(pk.y.to_affine()),
+            generator: G2Prepared::from(G2Affine::generator()),
+        })
+    }
+
+    /// The public key that was prepared
+    pub fn public_key(&self) -> PublicKey {
+        self.pk
+    }
+
+    /// Check whether a proof is valid with the same semantics as `Proof::open`
+    pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(&self, proof: &Proof, id: B, nonce: N) -> Choice {
+        match IdDigest::new(id) {
+            None => 0u8.into(),
+            Some(digest) => self.open_with_digest(proof, &digest, nonce),
+        }
+    }
+
+    /// Check whether a proof is valid using a precomputed id digest
+    pub fn open_with_digest<N: AsRef<[u8]>>(
+        &self,
+        proof: &Proof,
+        digest: &IdDigest,
+        nonce: N,
+    ) -> Choice {
+        if (proof.u.is_identity() | proof.z.is_identity()).unwrap_u8() == 1u8 {
+            return 0u8.into();
+        }
+        let t = Proof::challenge(proof.u, nonce.as_ref());
+        self.pairing_check(digest.a * t + proof.u, digest.m_tick, digest.m, proof.z)
+    }
+
+    /// Check whether a token is valid with the same semantics as `Token::verify`
+    pub fn verify_token<B: AsRef<[u8]>>(&self, token: &Token, id: B) -> Choice {
+        match IdDigest::new(id) {
+            None => 0u8.into(),
+            Some(digest) => self.verify_token_with_digest(token, &digest),
+        }
+    }
+
+    /// Check whether a token is valid using a precomputed id digest
+    pub fn verify_token_with_digest(&self, token: &Token, digest: &IdDigest) -> Choice {
+        self.pairing_check(digest.a, digest.m_tick, digest.m, -token.0)
+    }
+
+    /// e(L, W.m' + X + Y.m).e(Z, P) == 1 computed as
+    /// e(m'.L, W).e(L, X).e(m.L, Y).e(Z, P) == 1
+    fn pairing_check(&self, l: G1Projective, m_tick: Scalar, m: Scalar, z: G1Projective) -> Choice {
+        let mut points = [G1Affine::identity(); 4];
+        G1Projective::batch_normalize(&[l * m_tick, l, l * m, z], &mut points);
+        multi_miller_loop(&[
+            (&points[0], &self.w),
+            (&points[1], &self.x),
+            (&points[2], &self.y),
+            (&points[3], &self.generator),
+        ])
+        .final_exponentiation()
+        .is_identity()
+    }
+}
@@ -3,7 +3,7 @@
     SPDX-License-Identifier: Apache-2.0
 */
 use crate::inner_types::*;
-use crate::{util::*, Blinding, PublicKey, Token};
+use crate::{util::*, Blinding, Error, IdDigest, PublicKey, Token};
 use rand_core::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use subtle::{Choice, CtOption};
@@ -11,13 +11,15 @@ use subtle::{Choice, CtOption};
 /// A zero-knowledge proof of a valid token
 #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Proof {
-    u: G1Projective,
-    z: G1Projective,
+    pub(crate) u: G1Projective,
+    pub(crate) z: G1Projective,
 }
 
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(Proof);
 
+try_from_bytes_impl!(Proof);
+
 impl Proof {
     /// The number of bytes in a proof
     pub const BYTES: usize = 96;
@@ -41,64 +43,105 @@ impl Proof {
         blindings: &[Blinding],
         id: B,
         nonce: N,
-        mut rng: impl RngCore + CryptoRng,
+        rng: impl RngCore + CryptoRng,
     ) -> Option<Self> {
-        let id = id.as_ref();
-        let m = hash_to_scalar(&[id]);
-        if m.is_zero().unwrap_u8() == 1 {
-            return None;
-        }
-        let m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
-        if m_tick.is_zero().unwrap_u8() == 1 {
-            return None;
-        }
-        let a = hash_to_curve(&m_tick.to_le_bytes()[..]);
-        if a.is_identity().unwrap_u8() == 1 {
-            return None;
+        Self::try_new(token, blindings, id, nonce, rng).ok()
+    }
+
+    /// Create a new ZKP based proof returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        Self::try_new_with_digest(token, blindings, &IdDigest::try_new(id)?, nonce, rng)
+    }
+
+    /// Create a new ZKP based proof from a precomputed id digest
+    pub(crate) fn try_new_with_digest<N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        digest: &IdDigest,
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        let a = digest.a;
+
+        let sigma = token.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>();
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
         }
 
         let r = gen_nonz_rnd_scalar(&mut rng);
         let u = a * r;
-        let t = hash_to_scalar(&[&u.to_affine().to_compressed(), nonce.as_ref()]);
+        let t = Self::challenge(u, nonce.as_ref());
 
-        let z: G1Projective =
-            (token.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>()) * (r + t);
-        Some(Self { u, z: -z })
+        let z = sigma * (r + t);
+        Ok(Self { u, z: -z })
+    }
+
+    /// The challenge t = H(U || nonce)
+    pub(crate) fn challenge(u: G1Projective, nonce: &[u8]) -> Scalar {
+        hash_to_scalar(&[&u.to_affine().to_compressed(), nonce])
     }
 
     /// Check whether this proof is valid
     pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(&self, pk: PublicKey, id: B, nonce: N) -> Choice {
-        if (self.u.is_identity() | self.z.is_identity() | pk.is_invalid()).unwrap_u8() == 1u8 {
-            return 0u8.into();
+        match IdDigest::new(id) {
+            None => 0u8.into(),
+            Some(digest) => self.open_with_digest(pk, &digest, nonce),
         }
+    }
 
-        let id = id.as_ref();
-        let m = hash_to_scalar(&[id]);
-        if m.is_zero().unwrap_u8() == 1 {
-            return 0u8.into();
+    /// Check whether this proof is valid returning the reason on failure.
+    /// Unlike `open`, this is not constant time with respect to why it failed
+    pub fn try_open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        id: B,
+        nonce: N,
+    ) -> Result<(), Error> {
+        self.try_open_with_digest(pk, &IdDigest::try_new(id)?, nonce)
+    }
+
+    /// Check whether this proof is valid using a precomputed id digest
+    /// returning the reason on failure
+    pub(crate) fn try_open_with_digest<N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        digest: &IdDigest,
+        nonce: N,
+    ) -> Result<(), Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
         }
-        let m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
-        if m_tick.is_zero().unwrap_u8() == 1u8 {
-            return 0u8.into();
+        if (self.u.is_identity() | self.z.is_identity()).unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
         }
-        let a = hash_to_curve(&m_tick.to_le_bytes()[..]);
-        if a.is_identity().unwrap_u8() == 1 {
+        if self.open_with_digest(pk, digest, nonce).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+
+    /// Check whether this proof is valid using a precomputed id digest
+    pub fn open_with_digest<N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        digest: &IdDigest,
+        nonce: N,
+    ) -> Choice {
+        if (self.u.is_identity() | self.z.is_identity() | pk.is_invalid()).unwrap_u8() == 1u8 {
             return 0u8.into();
         }
 
-        let t = hash_to_scalar(&[&self.u.to_affine().to_compressed(), nonce.as_ref()]);
-
-        let u = a * t + self.u;
-        #[cfg(feature = "std")]
-        let rhs = G2Projective::sum_of_products(
-            &[pk.w, pk.x, pk.y],
-            &[m_tick, Scalar::ONE, m],
-        );
-        #[cfg(all(feature = "rust", not(feature = "std")))]
-        let rhs = G2Projective::sum_of_products_in_place(
-            &[pk.w, pk.x, pk.y],
-            &mut [m_tick, Scalar::ONE, m],
-        );
+        let t = Self::challenge(self.u, nonce.as_ref());
+
+        let u = digest.a * t + self.u;
+        let rhs = pk.sum_of_products(digest.m_tick, digest.m);
 
         multi_miller_loop(&[
             (&u.to_affine(), &G2Prepared::from(rhs.to_affine())),
@@ -0,0 +1,339 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Blinding, Error, IdDigest, PublicKey, Token};
+use core::convert::TryFrom;
+use digest::{ExtendableOutput, Update, XofReader};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::Shake256;
+use subtle::{Choice, ConstantTimeEq, CtOption};
+
+const SCOPE_DST: &[u8] = b"OBERON_PSEUDONYM_SCOPE_";
+const PSEUDONYM_PROOF_DST: &[u8] = b"OBERON_PSEUDONYM_PROOF_";
+
+/// A per-scope pseudonym m.G_s + m'.G'_s for the hidden id where
+/// G_s and G'_s are hashed from the scope.
+///
+/// Including m' ties the pseudonym to the whole signed id since a
+/// proof cannot show m' = H(m) without revealing m.
+/// The same id always has the same pseudonym in a scope
+/// but pseudonyms from different scopes cannot be linked.
+/// Anyone who knows the id can compute its pseudonym so
+/// ids must not be guessable for pseudonyms to hide them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Pseudonym(pub(crate) G1Projective);
+
+impl Default for Pseudonym {
+    fn default() -> Self {
+        Self(G1Projective::IDENTITY)
+    }
+}
+
+impl ConstantTimeEq for Pseudonym {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(Pseudonym);
+
+try_from_bytes_impl!(Pseudonym);
+
+impl Pseudonym {
+    /// The number of bytes in a pseudonym
+    pub const BYTES: usize = 48;
+
+    /// Compute the pseudonym of `id` in `scope`
+    pub fn new<B: AsRef<[u8]>, S: AsRef<[u8]>>(id: B, scope: S) -> Option<Self> {
+        Self::try_new(id, scope).ok()
+    }
+
+    /// Compute the pseudonym of `id` in `scope` returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>, S: AsRef<[u8]>>(id: B, scope: S) -> Result<Self, Error> {
+        let digest = IdDigest::try_new(id)?;
+        let (g, g_tick) = scope_generators(scope.as_ref());
+        Ok(Self(g * digest.m + g_tick * digest.m_tick))
+    }
+
+    /// Convert this pseudonym into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence into a pseudonym
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data).map(|p| Self(G1Projective::from(p)))
+    }
+}
+
+/// A zero-knowledge proof of a valid token that does not reveal
+/// the id but carries its pseudonym for the verifier's scope
+///
+/// The responses for m and m' are shared with the proof of the pseudonym,
+/// see Pseudonyms in MATH.md.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let token = sk.sign(b"test identity").unwrap();
+///
+/// let id = b"test identity";
+/// let proof = PseudonymProof::new(&token, &[], id, pk, b"shop.example", b"nonce", thread_rng()).unwrap();
+/// assert_eq!(proof.open(pk, b"shop.example", b"nonce").unwrap_u8(), 1u8);
+///
+/// // the same user has the same pseudonym in the scope next time
+/// let proof2 = PseudonymProof::new(&token, &[], id, pk, b"shop.example", b"nonce2", thread_rng()).unwrap();
+/// assert_eq!(proof.pseudonym(), proof2.pseudonym());
+/// ```
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct PseudonymProof {
+    pub(crate) h: G1Projective,
+    pub(crate) sigma: G1Projective,
+    pub(crate) pseudonym: Pseudonym,
+    pub(crate) challenge: Scalar,
+    pub(crate) s_t: Scalar,
+    pub(crate) s_m: Scalar,
+    pub(crate) s_m_tick: Scalar,
+}
+
+impl Default for PseudonymProof {
+    fn default() -> Self {
+        Self {
+            h: G1Projective::IDENTITY,
+            sigma: G1Projective::IDENTITY,
+            pseudonym: Pseudonym::default(),
+            challenge: Scalar::ZERO,
+            s_t: Scalar::ZERO,
+            s_m: Scalar::ZERO,
+            s_m_tick: Scalar::ZERO,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(PseudonymProof);
+
+try_from_bytes_impl!(PseudonymProof);
+
+impl PseudonymProof {
+    /// The number of bytes in a pseudonym proof
+    pub const BYTES: usize = 272;
+
+    /// Create a new proof for a token issued under `pk`
+    /// with the pseudonym for `scope`
+    pub fn new<B: AsRef<[u8]>, S: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        pk: PublicKey,
+        scope: S,
+        nonce: N,
+        rng: impl RngCore + CryptoRng,
+    ) -> Option<Self> {
+        Self::try_new(token, blindings, id, pk, scope, nonce, rng).ok()
+    }
+
+    /// Create a new proof for a token issued under `pk`
+    /// with the pseudonym for `scope` returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>, S: AsRef<[u8]>, N: AsRef<[u8]>>(
+        token: &Token,
+        blindings: &[Blinding],
+        id: B,
+        pk: PublicKey,
+        scope: S,
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        let digest = IdDigest::try_new(id)?;
+        let sigma = token.0 + blindings.iter().map(|b| b.0).sum::<G1Projective>();
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+        let (g, g_tick) = scope_generators(scope.as_ref());
+        let pseudonym = Pseudonym(g * digest.m + g_tick * digest.m_tick);
+
+        let (h, sigma, t) = randomize(sigma, digest.a, &mut rng);
+
+        let k_t = Scalar::random(&mut rng);
+        let k_m = Scalar::random(&mut rng);
+        let k_m_tick = Scalar::random(&mut rng);
+        let rhs = G2Projective::GENERATOR * k_t + pk.w * k_m_tick + pk.y * k_m;
+        let commitment = pairing(&h.to_affine(), &rhs.to_affine());
+        let challenge = pseudonym_challenge(
+            h,
+            sigma,
+            pseudonym,
+            commitment,
+            g * k_m + g_tick * k_m_tick,
+            scope.as_ref(),
+            nonce.as_ref(),
+        );
+
+        Ok(Self {
+            h,
+            sigma,
+            pseudonym,
+            challenge,
+            s_t: k_t + challenge * t,
+            s_m: k_m + challenge * digest.m,
+            s_m_tick: k_m_tick + challenge * digest.m_tick,
+        })
+    }
+
+    /// The pseudonym of the hidden id in the scope of this proof
+    pub fn pseudonym(&self) -> Pseudonym {
+        self.pseudonym
+    }
+
+    /// Check whether this proof is valid for `scope`. On success
+    /// `pseudonym` identifies the holder in this scope
+    pub fn open<S: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        scope: S,
+        nonce: N,
+    ) -> Choice {
+        if (pk.is_invalid()
+            | self.h.is_identity()
+            | self.sigma.is_identity()
+            | self.pseudonym.0.is_identity())
+        .unwrap_u8()
+            == 1
+        {
+            return Choice::from(0u8);
+        }
+        let (g, g_tick) = scope_generators(scope.as_ref());
+
+        let mut points = [G1Affine::identity(); 2];
+        G1Projective::batch_normalize(&[self.sigma, -self.h], &mut points);
+        let lhs = multi_miller_loop(&[
+            (&points[0], &G2Prepared::from(G2Affine::generator())),
+            (&points[1], &G2Prepared::from(pk.x.to_affine())),
+        ])
+        .final_exponentiation();
+        let rhs = G2Projective::GENERATOR * self.s_t + pk.w * self.s_m_tick + pk.y * self.s_m;
+        let commitment = pairing(&self.h.to_affine(), &rhs.to_affine()) - lhs * self.challenge;
+        let pseudonym_commitment =
+            g * self.s_m + g_tick * self.s_m_tick - self.pseudonym.0 * self.challenge;
+
+        let challenge = pseudonym_challenge(
+            self.h,
+            self.sigma,
+            self.pseudonym,
+            commitment,
+            pseudonym_commitment,
+            scope.as_ref(),
+            nonce.as_ref(),
+        );
+        challenge.ct_eq(&self.challenge)
+    }
+
+    /// Check whether this proof is valid for `scope` returning the reason on failure
+    pub fn try_open<S: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: PublicKey,
+        scope: S,
+        nonce: N,
+    ) -> Result<(), Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        if (self.h.is_identity() | self.sigma.is_identity() | self.pseudonym.0.is_identity())
+            .unwrap_u8()
+            == 1
+        {
+            return Err(Error::IdentityPoint);
+        }
+        if self.open(pk, scope, nonce).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+
+    /// Convert this proof into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..48].copy_from_slice(&self.h.to_affine().to_compressed());
+        out[48..96].copy_from_slice(&self.sigma.to_affine().to_compressed());
+        out[96..144].copy_from_slice(&self.pseudonym.to_bytes());
+        out[144..176].copy_from_slice(&self.challenge.to_le_bytes());
+        out[176..208].copy_from_slice(&self.s_t.to_le_bytes());
+        out[208..240].copy_from_slice(&self.s_m.to_le_bytes());
+        out[240..].copy_from_slice(&self.s_m_tick.to_le_bytes());
+        out
+    }
+
+    /// Convert a byte sequence into a proof
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let point = |i: usize| {
+            G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[i..i + 48]).unwrap())
+                .map(G1Projective::from)
+        };
+        let scalar =
+            |i: usize| Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[i..i + 32]).unwrap());
+        let (h, sigma, pseudonym) = (point(0), point(48), point(96));
+        let (challenge, s_t, s_m, s_m_tick) = (scalar(144), scalar(176), scalar(208), scalar(240));
+        let valid = h.is_some()
+            & sigma.is_some()
+            & pseudonym.is_some()
+            & challenge.is_some()
+            & s_t.is_some()
+            & s_m.is_some()
+            & s_m_tick.is_some();
+        CtOption::new(
+            Self {
+                h: h.unwrap_or(G1Projective::IDENTITY),
+                sigma: sigma.unwrap_or(G1Projective::IDENTITY),
+                pseudonym: Pseudonym(pseudonym.unwrap_or(G1Projective::IDENTITY)),
+                challenge: challenge.unwrap_or(Scalar::ZERO),
+                s_t: s_t.unwrap_or(Scalar::ZERO),
+                s_m: s_m.unwrap_or(Scalar::ZERO),
+                s_m_tick: s_m_tick.unwrap_or(Scalar::ZERO),
+            },
+            valid,
+        )
+    }
+}
+
+fn pseudonym_challenge(
+    h: G1Projective,
+    sigma: G1Projective,
+    pseudonym: Pseudonym,
+    commitment: Gt,
+    pseudonym_commitment: G1Projective,
+    scope: &[u8],
+    nonce: &[u8],
+) -> Scalar {
+    hash_to_scalar(&[
+        PSEUDONYM_PROOF_DST,
+        &h.to_affine().to_compressed(),
+        &sigma.to_affine().to_compressed(),
+        &pseudonym.to_bytes(),
+        commitment.to_bytes().as_ref(),
+        &pseudonym_commitment.to_affine().to_compressed(),
+        &(scope.len() as u64).to_be_bytes(),
+        scope,
+        nonce,
+    ])
+}
+
+fn scope_generators(scope: &[u8]) -> (G1Projective, G1Projective) {
+    let mut hasher = Shake256::default();
+    hasher.update(SCOPE_DST);
+    hasher.update(scope);
+    let mut reader = hasher.finalize_xof();
+    let mut data = [0u8; 64];
+    reader.read(&mut data);
+    (hash_to_curve(&data[..32]), hash_to_curve(&data[32..]))
+}
@@ -39,6 +39,8 @@ impl From<&SecretKey> for PublicKey {
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(PublicKey);
 
+try_from_bytes_impl!(PublicKey);
+
 impl PublicKey {
     /// The number of bytes in a public key
     pub const BYTES: usize = 288;
@@ -75,4 +77,17 @@ impl PublicKey {
     pub fn verify_token<B: AsRef<[u8]>>(&self, id: B, token: &Token) -> Choice {
         token.verify(*self, id)
     }
+
+    /// Compute W.m' + X + Y.m
+    pub(crate) fn sum_of_products(&self, m_tick: Scalar, m: Scalar) -> G2Projective {
+        #[cfg(feature = "std")]
+        let rhs =
+            G2Projective::sum_of_products(&[self.w, self.x, self.y], &[m_tick, Scalar::ONE, m]);
+        #[cfg(all(feature = "rust", not(feature = "std")))]
+        let rhs = G2Projective::sum_of_products_in_place(
+            &[self.w, self.x, self.y],
+            &mut [m_tick, Scalar::ONE, m],
+        );
+        rhs
+    }
 }
@@ -2,11 +2,32 @@
     Copyright Michael Lodder. All Rights Reserved.
     SPDX-License-Identifier: Apache-2.0
 */
-use crate::{Blinding, Proof, PublicKey, SecretKey, Token};
-use pyo3::{exceptions::PyValueError, prelude::*};
+use crate::{
+    Blinding, Challenge, ChallengeKey, Error, PasswordParams, Proof, PublicKey, SecretKey, Token,
+};
+use pyo3::prelude::*;
 use rand::thread_rng;
 
-use std::{convert::TryFrom, vec::Vec};
+use std::{format, vec::Vec};
+
+// pyo3 0.19 expands create_exception to a cfg the compiler does not know about
+#[allow(unexpected_cfgs)]
+mod exception {
+    use pyo3::{create_exception, exceptions::PyValueError};
+
+    create_exception!(
+        oberon,
+        OberonError,
+        PyValueError,
+        "Raised when an operation fails. The arguments are the message and the error code"
+    );
+}
+
+pub use exception::OberonError;
+
+fn py_error(what: &str, e: Error) -> PyErr {
+    OberonError::new_err((format!("{}: {}", what, e), e.code()))
+}
 
 /// Create a new secret key
 ///
@@ -20,10 +41,9 @@ pub fn new_secret_key() -> Vec<u8> {
 /// Get the public key from the secret key
 #[pyfunction]
 pub fn get_public_key(sk: Vec<u8>) -> PyResult<Vec<u8>> {
-    match secret_key(sk) {
-        None => Err(PyValueError::new_err("Invalid secret key")),
-        Some(sk) => Ok(PublicKey::from(&sk).to_bytes().to_vec()),
-    }
+    SecretKey::try_from_bytes(&sk)
+        .map(|sk| PublicKey::from(&sk).to_bytes().to_vec())
+        .map_err(|e| py_error("Invalid secret key", e))
 }
 
 /// Create new secret key from a seed
@@ -37,13 +57,13 @@ pub fn secret_key_from_seed(seed: Vec<u8>) -> Vec<u8> {
 /// @param string $sk The secret key
 /// @param string $id The identifier
 ///
-/// @return string|null The token if successful, null on failure
+/// @return string The token, raises OberonError on failure
 #[pyfunction]
 pub fn new_token(sk: Vec<u8>, id: Vec<u8>) -> PyResult<Vec<u8>> {
-    match secret_key(sk) {
-        None => Err(PyValueError::new_err("Invalid secret key")),
-        Some(sk) => Ok(Token::new(&sk, &id).unwrap().to_bytes().to_vec()),
-    }
+    let sk = SecretKey::try_from_bytes(&sk).map_err(|e| py_error("Invalid secret key", e))?;
+    Token::try_new(&sk, &id)
+        .map(|t| t.to_bytes().to_vec())
+        .map_err(|e| py_error("Unable to create token", e))
 }
 
 /// Verify a token for a given ID
@@ -52,12 +72,15 @@ pub fn new_token(sk: Vec<u8>, id: Vec<u8>) -> PyResult<Vec<u8>> {
 /// @param string $pk The public key
 /// @param string $id The identifier
 ///
-/// @param bool result of check
+/// @param bool result of check, raises OberonError on malformed inputs
 #[pyfunction]
-pub fn verify_token(token: Vec<u8>, pk: Vec<u8>, id: Vec<u8>) -> bool {
-    match (get_token(token), public_key(pk)) {
-        (Some(t), Some(k)) => t.verify(k, &id).unwrap_u8() == 1,
-        (_, _) => false,
+pub fn verify_token(token: Vec<u8>, pk: Vec<u8>, id: Vec<u8>) -> PyResult<bool> {
+    let t = Token::try_from_bytes(&token).map_err(|e| py_error("Invalid token", e))?;
+    let k = PublicKey::try_from_bytes(&pk).map_err(|e| py_error("Invalid public key", e))?;
+    match t.try_verify(k, &id) {
+        Ok(()) => Ok(true),
+        Err(Error::VerificationFailed) => Ok(false),
+        Err(e) => Err(py_error("Unable to verify token", e)),
     }
 }
 
@@ -71,6 +94,30 @@ pub fn create_blinding(data: Vec<u8>) -> Vec<u8> {
     Blinding::new(&data).to_bytes().to_vec()
 }
 
+/// Create a blinding factor from a password for the token on the identifier
+///
+/// @param string $password The password
+/// @param string $id The identifier
+/// @param string|null $params The encoded password parameters or null for the defaults
+///
+/// @return string The blinding factor, raises OberonError on failure
+#[pyfunction]
+#[pyo3(signature = (password, id, params = None))]
+pub fn create_password_blinding(
+    password: &str,
+    id: Vec<u8>,
+    params: Option<Vec<u8>>,
+) -> PyResult<Vec<u8>> {
+    let params = params
+        .map(|p| PasswordParams::try_from_bytes(&p))
+        .transpose()
+        .map_err(|e| py_error("Invalid password parameters", e))?
+        .unwrap_or_default();
+    Blinding::from_password(password, id, &params)
+        .map(|b| b.to_bytes().to_vec())
+        .map_err(|e| py_error("Unable to create blinding", e))
+}
+
 /// Adds a blinding factor to the token
 ///
 /// @param string $token The token
@@ -79,9 +126,9 @@ pub fn create_blinding(data: Vec<u8>) -> Vec<u8> {
 /// @param string|null The blinding factor or null if invalid token
 #[pyfunction]
 pub fn add_blinding(token: Vec<u8>, data: Vec<u8>) -> PyResult<Vec<u8>> {
-    match get_token(token) {
-        None => Err(PyValueError::new_err("Invalid token")),
-        Some(t) => {
+    match Token::try_from_bytes(&token) {
+        Err(e) => Err(py_error("Invalid token", e)),
+        Ok(t) => {
             let val = t - Blinding::new(&data);
             Ok(val.to_bytes().to_vec())
         }
@@ -96,15 +143,42 @@ pub fn add_blinding(token: Vec<u8>, data: Vec<u8>) -> PyResult<Vec<u8>> {
 /// @param string The blinding factor or null if invalid token
 #[pyfunction]
 pub fn remove_blinding(token: Vec<u8>, data: Vec<u8>) -> PyResult<Vec<u8>> {
-    match get_token(token) {
-        None => Err(PyValueError::new_err("Invalid token")),
-        Some(t) => {
+    match Token::try_from_bytes(&token) {
+        Err(e) => Err(py_error("Invalid token", e)),
+        Ok(t) => {
             let val = t + Blinding::new(&data);
             Ok(val.to_bytes().to_vec())
         }
     }
 }
 
+/// Replaces the blinding factors on the token
+///
+/// @param string $token The token
+/// @param array[string] $old_blindings The blinding factors on the token
+/// @param array[string] $new_blindings The blinding factors to apply instead
+/// @param string $pk The public key
+/// @param string $id The identifier
+///
+/// @return string The new token, raises OberonError if the old blinding factors are wrong
+#[pyfunction]
+pub fn rebind(
+    token: Vec<u8>,
+    old_blindings: Vec<Vec<u8>>,
+    new_blindings: Vec<Vec<u8>>,
+    pk: Vec<u8>,
+    id: Vec<u8>,
+) -> PyResult<Vec<u8>> {
+    let old: Vec<Blinding> = old_blindings.iter().map(|b| Blinding::new(b)).collect();
+    let new: Vec<Blinding> = new_blindings.iter().map(|b| Blinding::new(b)).collect();
+
+    let t = Token::try_from_bytes(&token).map_err(|e| py_error("Invalid token", e))?;
+    let k = PublicKey::try_from_bytes(&pk).map_err(|e| py_error("Invalid public key", e))?;
+    t.rebind(&old, &new, k, id)
+        .map(|t| t.to_bytes().to_vec())
+        .map_err(|e| py_error("Unable to rebind token", e))
+}
+
 /// Creates a proof using a nonce received from a verifier
 ///
 /// @param string $token The token
@@ -123,12 +197,10 @@ pub fn create_proof(
     let bs: Vec<Blinding> = blindings.iter().map(|b| Blinding::new(b)).collect();
 
     let rng = thread_rng();
-    match get_token(token) {
-        None => Err(PyValueError::new_err("Invalid token")),
-        Some(t) => Proof::new(&t, &bs, id, nonce, rng)
-            .map(|p| p.to_bytes().to_vec())
-            .ok_or_else(|| PyValueError::new_err("")),
-    }
+    let t = Token::try_from_bytes(&token).map_err(|e| py_error("Invalid token", e))?;
+    Proof::try_new(&t, &bs, id, nonce, rng)
+        .map(|p| p.to_bytes().to_vec())
+        .map_err(|e| py_error("Unable to create proof", e))
 }
 
 /// Creates a proof using a nonce received from a verifier
@@ -138,49 +210,72 @@ pub fn create_proof(
 /// @param string $id The identifier
 /// @param string $nonce The proof nonce
 ///
-/// @return bool The check proof result
+/// @return bool The check proof result, raises OberonError on malformed inputs
 #[pyfunction]
-pub fn verify_proof(proof: Vec<u8>, pk: Vec<u8>, id: Vec<u8>, nonce: Vec<u8>) -> bool {
-    match (get_proof(proof), public_key(pk)) {
-        (Some(p), Some(k)) => p.open(k, id, nonce).unwrap_u8() == 1,
-        (_, _) => false,
+pub fn verify_proof(proof: Vec<u8>, pk: Vec<u8>, id: Vec<u8>, nonce: Vec<u8>) -> PyResult<bool> {
+    let p = Proof::try_from_bytes(&proof).map_err(|e| py_error("Invalid proof", e))?;
+    let k = PublicKey::try_from_bytes(&pk).map_err(|e| py_error("Invalid public key", e))?;
+    match p.try_open(k, id, nonce) {
+        Ok(()) => Ok(true),
+        Err(Error::VerificationFailed) => Ok(false),
+        Err(e) => Err(py_error("Unable to verify proof", e)),
     }
 }
 
-macro_rules! from_bytes {
-    ($name:ident, $type:ident) => {
-        fn $name(input: Vec<u8>) -> Option<$type> {
-            match <[u8; $type::BYTES]>::try_from(input.as_slice()) {
-                Err(_) => None,
-                Ok(bytes) => {
-                    let val = $type::from_bytes(&bytes);
-                    if val.is_some().unwrap_u8() == 1u8 {
-                        Some(val.unwrap())
-                    } else {
-                        None
-                    }
-                }
-            }
-        }
-    };
+/// Create a new random challenge key for a verifier
+///
+/// @return string The challenge key
+#[pyfunction]
+pub fn new_challenge_key() -> Vec<u8> {
+    ChallengeKey::new(thread_rng()).to_bytes().to_vec()
+}
+
+/// Mint a challenge to send to a prover as the proof nonce
+///
+/// @param string $key The challenge key
+/// @param int $now The current time in seconds since the unix epoch
+///
+/// @return string The challenge, raises OberonError on failure
+#[pyfunction]
+pub fn new_challenge(key: Vec<u8>, now: u64) -> PyResult<Vec<u8>> {
+    ChallengeKey::try_from_bytes(&key)
+        .map(|k| k.issue(now, thread_rng()).to_bytes().to_vec())
+        .map_err(|e| py_error("Invalid challenge key", e))
+}
+
+/// Check a challenge was issued by the key within `ttl` seconds before `now`
+///
+/// @param string $challenge The challenge
+/// @param string $key The challenge key
+/// @param int $now The current time in seconds since the unix epoch
+/// @param int $ttl The number of seconds the challenge is valid
+///
+/// @return bool The check result, raises OberonError on malformed inputs
+#[pyfunction]
+pub fn verify_challenge(challenge: Vec<u8>, key: Vec<u8>, now: u64, ttl: u64) -> PyResult<bool> {
+    let c = Challenge::try_from_bytes(&challenge).map_err(|e| py_error("Invalid challenge", e))?;
+    let k = ChallengeKey::try_from_bytes(&key).map_err(|e| py_error("Invalid challenge key", e))?;
+    Ok(k.check(&c, now, ttl).is_ok())
 }
-from_bytes!(secret_key, SecretKey);
-from_bytes!(public_key, PublicKey);
-from_bytes!(get_token, Token);
-from_bytes!(get_proof, Proof);
 
 /// Boilerplate function
 #[pymodule]
-pub fn oberon(_py: Python, m: &PyModule) -> PyResult<()> {
+pub fn oberon(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("OberonError", py.get_type::<OberonError>())?;
     m.add_function(wrap_pyfunction!(new_secret_key, m)?)?;
     m.add_function(wrap_pyfunction!(get_public_key, m)?)?;
     m.add_function(wrap_pyfunction!(secret_key_from_seed, m)?)?;
     m.add_function(wrap_pyfunction!(new_token, m)?)?;
     m.add_function(wrap_pyfunction!(verify_token, m)?)?;
     m.add_function(wrap_pyfunction!(create_blinding, m)?)?;
+    m.add_function(wrap_pyfunction!(create_password_blinding, m)?)?;
     m.add_function(wrap_pyfunction!(add_blinding, m)?)?;
     m.add_function(wrap_pyfunction!(remove_blinding, m)?)?;
+    m.add_function(wrap_pyfunction!(rebind, m)?)?;
     m.add_function(wrap_pyfunction!(create_proof, m)?)?;
     m.add_function(wrap_pyfunction!(verify_proof, m)?)?;
+    m.add_function(wrap_pyfunction!(new_challenge_key, m)?)?;
+    m.add_function(wrap_pyfunction!(new_challenge, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_challenge, m)?)?;
     Ok(())
 }
@@ -0,0 +1,610 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{util::*, Error, Proof};
+use core::convert::TryFrom;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::ZeroizeOnDrop;
+
+const NON_MEMBERSHIP_DST: &[u8] = b"OBERON_REVOCATION_NON_MEMBERSHIP_";
+
+/// The secret key for managing revocations
+/// Display is not implemented to prevent accidental leak of the key
+///
+/// Revoked ids are accumulated as V = (m_1 + a)...(m_n + a).G
+/// where a is this key, m_i = H_s(id_i) and G is the G1 generator.
+#[derive(Clone, Debug, Eq, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct AccumulatorSecretKey(pub(crate) Scalar);
+
+impl Default for AccumulatorSecretKey {
+    fn default() -> Self {
+        Self(Scalar::ZERO)
+    }
+}
+
+impl PartialEq for AccumulatorSecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).unwrap_u8() == 1
+    }
+}
+
+try_from_bytes_impl!(AccumulatorSecretKey);
+
+impl AccumulatorSecretKey {
+    /// The number of bytes in an accumulator secret key
+    pub const BYTES: usize = 32;
+
+    /// Generate a new random key
+    pub fn new(mut rng: impl RngCore + CryptoRng) -> Self {
+        let mut s = Scalar::random(&mut rng);
+        while s.is_zero().unwrap_u8() == 1 {
+            s = Scalar::random(&mut rng);
+        }
+        Self(s)
+    }
+
+    /// Convert this secret key into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_le_bytes()
+    }
+
+    /// Convert a byte sequence to a secret key
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        Scalar::from_le_bytes(data).and_then(|s| CtOption::new(Self(s), !s.is_zero()))
+    }
+}
+
+/// The public key for verifying non-membership
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AccumulatorPublicKey(pub(crate) G2Projective);
+
+impl Default for AccumulatorPublicKey {
+    fn default() -> Self {
+        Self(G2Projective::IDENTITY)
+    }
+}
+
+impl From<&AccumulatorSecretKey> for AccumulatorPublicKey {
+    fn from(sk: &AccumulatorSecretKey) -> Self {
+        Self(G2Projective::GENERATOR * sk.0)
+    }
+}
+
+try_from_bytes_impl!(AccumulatorPublicKey);
+
+impl AccumulatorPublicKey {
+    /// The number of bytes in an accumulator public key
+    pub const BYTES: usize = 96;
+
+    /// Is this public key invalid
+    pub fn is_invalid(&self) -> Choice {
+        self.0.is_identity()
+    }
+
+    /// Convert this public key into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence to a public key
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G2Affine::from_compressed(data).map(|p| Self(G2Projective::from(p)))
+    }
+}
+
+/// The published accumulator value of all revoked ids
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Accumulator(pub(crate) G1Projective);
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self(G1Projective::GENERATOR)
+    }
+}
+
+try_from_bytes_impl!(Accumulator);
+
+impl Accumulator {
+    /// The number of bytes in an accumulator
+    pub const BYTES: usize = 48;
+
+    /// Convert this accumulator into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Convert a byte sequence to an accumulator
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        G1Affine::from_compressed(data)
+            .map(G1Projective::from)
+            .and_then(|p| CtOption::new(Self(p), !p.is_identity()))
+    }
+}
+
+/// A single change to the accumulator published by the revocation manager.
+///
+/// Holders apply updates in order to keep their witness current
+/// without contacting the manager. Only the hashed id is published.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AccumulatorUpdate {
+    pub(crate) element: Scalar,
+    pub(crate) revoked: bool,
+    pub(crate) accumulator: Accumulator,
+}
+
+impl Default for AccumulatorUpdate {
+    fn default() -> Self {
+        Self {
+            element: Scalar::ZERO,
+            revoked: false,
+            accumulator: Accumulator::default(),
+        }
+    }
+}
+
+try_from_bytes_impl!(AccumulatorUpdate);
+
+impl AccumulatorUpdate {
+    /// The number of bytes in an accumulator update
+    pub const BYTES: usize = 81;
+
+    /// True if the element was revoked, false if it was reinstated
+    pub fn is_revocation(&self) -> bool {
+        self.revoked
+    }
+
+    /// The accumulator value after this update
+    pub fn accumulator(&self) -> Accumulator {
+        self.accumulator
+    }
+
+    /// Convert this update into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..32].copy_from_slice(&self.element.to_le_bytes());
+        out[32] = self.revoked as u8;
+        out[33..].copy_from_slice(&self.accumulator.to_bytes());
+        out
+    }
+
+    /// Convert a byte sequence to an update
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let element = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[..32]).unwrap());
+        let point = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[33..]).unwrap())
+            .map(G1Projective::from);
+        let flag = data[32];
+        element.and_then(|element| {
+            point.and_then(|p| {
+                CtOption::new(
+                    Self {
+                        element,
+                        revoked: flag == 1,
+                        accumulator: Accumulator(p),
+                    },
+                    Choice::from((flag <= 1) as u8) & !element.is_zero() & !p.is_identity(),
+                )
+            })
+        })
+    }
+}
+
+/// A witness that an id is not in the accumulator
+/// Display is not implemented to prevent accidental leak of the witness
+///
+/// C.(m + a) + d.G = V where d != 0
+#[derive(Clone, Debug, Eq, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct NonMembershipWitness {
+    pub(crate) c: G1Projective,
+    pub(crate) d: Scalar,
+}
+
+impl Default for NonMembershipWitness {
+    fn default() -> Self {
+        Self {
+            c: G1Projective::IDENTITY,
+            d: Scalar::ZERO,
+        }
+    }
+}
+
+impl PartialEq for NonMembershipWitness {
+    fn eq(&self, other: &Self) -> bool {
+        (self.c.ct_eq(&other.c) & self.d.ct_eq(&other.d)).unwrap_u8() == 1
+    }
+}
+
+try_from_bytes_impl!(NonMembershipWitness);
+
+impl NonMembershipWitness {
+    /// The number of bytes in a witness
+    pub const BYTES: usize = 80;
+
+    /// Check whether this witness shows `id` is not in `accumulator`
+    pub fn verify<B: AsRef<[u8]>>(
+        &self,
+        id: B,
+        accumulator: Accumulator,
+        pk: AccumulatorPublicKey,
+    ) -> Choice {
+        let m = match id_scalar(id.as_ref()) {
+            Err(_) => return Choice::from(0u8),
+            Ok(m) => m,
+        };
+        let rhs = G2Projective::GENERATOR * m + pk.0;
+        let lhs = G1Projective::GENERATOR * self.d - accumulator.0;
+        !(self.d.is_zero() | pk.is_invalid())
+            & multi_miller_loop(&[
+                (&self.c.to_affine(), &G2Prepared::from(rhs.to_affine())),
+                (&lhs.to_affine(), &G2Prepared::from(G2Affine::generator())),
+            ])
+            .final_exponentiation()
+            .is_identity()
+    }
+
+    /// Apply published updates in order to this witness.
+    /// `accumulator` is the value this witness is currently valid for.
+    /// Returns `Error::Revoked` if `id` was revoked by one of the updates
+    /// in which case the witness is left unchanged
+    pub fn update<B: AsRef<[u8]>>(
+        &mut self,
+        id: B,
+        accumulator: Accumulator,
+        updates: &[AccumulatorUpdate],
+    ) -> Result<(), Error> {
+        let m = id_scalar(id.as_ref())?;
+        let mut c = self.c;
+        let mut d = self.d;
+        let mut previous = accumulator.0;
+        for update in updates {
+            let diff = update.element - m;
+            if update.revoked {
+                if diff.is_zero().unwrap_u8() == 1 {
+                    return Err(Error::Revoked);
+                }
+                // C' = (y - m).C + V, d' = d.(y - m)
+                c = c * diff + previous;
+                d *= diff;
+            } else {
+                // C' = (C - V').(y - m)^-1, d' = d.(y - m)^-1
+                let inv = Option::<Scalar>::from(diff.invert()).ok_or(Error::InvalidParameters)?;
+                c = (c - update.accumulator.0) * inv;
+                d *= inv;
+            }
+            previous = update.accumulator.0;
+        }
+        self.c = c;
+        self.d = d;
+        Ok(())
+    }
+
+    /// Convert this witness into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..48].copy_from_slice(&self.c.to_affine().to_compressed());
+        out[48..].copy_from_slice(&self.d.to_le_bytes());
+        out
+    }
+
+    /// Convert a byte sequence to a witness
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let cc = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[..48]).unwrap())
+            .map(G1Projective::from);
+        let dd = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[48..]).unwrap());
+        cc.and_then(|c| dd.and_then(|d| CtOption::new(Self { c, d }, !d.is_zero())))
+    }
+}
+
+/// A zero-knowledge proof that the id of a `Proof` is not revoked.
+///
+/// The witness is randomized as C' = r.C and E = r.V - r.d.G so that
+/// e(C', m.P + Q) = e(E, P) where Q is the accumulator public key.
+/// A Schnorr proof shows G = a.V + b.E which is only possible if d != 0. The challenge binds the `Proof`
+/// and nonce so the two cannot be separated.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct NonMembershipProof {
+    pub(crate) c: G1Projective,
+    pub(crate) e: G1Projective,
+    pub(crate) challenge: Scalar,
+    pub(crate) s_a: Scalar,
+    pub(crate) s_b: Scalar,
+}
+
+try_from_bytes_impl!(NonMembershipProof);
+
+impl NonMembershipProof {
+    /// The number of bytes in a non-membership proof
+    pub const BYTES: usize = 192;
+
+    /// Create a proof that `id` is not in `accumulator` bound to `proof` and `nonce`
+    pub fn new<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        witness: &NonMembershipWitness,
+        id: B,
+        accumulator: Accumulator,
+        proof: &Proof,
+        nonce: N,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self, Error> {
+        let m = id_scalar(id.as_ref())?;
+        if witness.d.is_zero().unwrap_u8() == 1 {
+            return Err(Error::Revoked);
+        }
+        let r = non_zero_scalar(&mut rng);
+        let c = witness.c * r;
+        let e = accumulator.0 * r - G1Projective::GENERATOR * (r * witness.d);
+        if (c.is_identity() | e.is_identity()).unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
+        }
+
+        // G = (r/rd).V - (1/rd).E
+        let rd_inv = (r * witness.d).invert().unwrap();
+        let a = r * rd_inv;
+        let b = -rd_inv;
+
+        let k_a = Scalar::random(&mut rng);
+        let k_b = Scalar::random(&mut rng);
+        let t = accumulator.0 * k_a + e * k_b;
+        let challenge = non_membership_challenge(m, accumulator, c, e, t, proof, nonce.as_ref());
+        Ok(Self {
+            c,
+            e,
+            challenge,
+            s_a: k_a + challenge * a,
+            s_b: k_b + challenge * b,
+        })
+    }
+
+    /// Check whether this proof shows the id of `proof` is not in `accumulator`
+    pub fn open<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        pk: AccumulatorPublicKey,
+        accumulator: Accumulator,
+        id: B,
+        proof: &Proof,
+        nonce: N,
+    ) -> Choice {
+        if (self.c.is_identity() | self.e.is_identity() | pk.is_invalid()).unwrap_u8() == 1 {
+            return Choice::from(0u8);
+        }
+        let m = match id_scalar(id.as_ref()) {
+            Err(_) => return Choice::from(0u8),
+            Ok(m) => m,
+        };
+
+        let t =
+            accumulator.0 * self.s_a + self.e * self.s_b - G1Projective::GENERATOR * self.challenge;
+        let challenge =
+            non_membership_challenge(m, accumulator, self.c, self.e, t, proof, nonce.as_ref());
+
+        let rhs = G2Projective::GENERATOR * m + pk.0;
+        challenge.ct_eq(&self.challenge)
+            & multi_miller_loop(&[
+                (&self.c.to_affine(), &G2Prepared::from(rhs.to_affine())),
+                (
+                    &(-self.e).to_affine(),
+                    &G2Prepared::from(G2Affine::generator()),
+                ),
+            ])
+            .final_exponentiation()
+            .is_identity()
+    }
+
+    /// Convert this proof into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..48].copy_from_slice(&self.c.to_affine().to_compressed());
+        out[48..96].copy_from_slice(&self.e.to_affine().to_compressed());
+        out[96..128].copy_from_slice(&self.challenge.to_le_bytes());
+        out[128..160].copy_from_slice(&self.s_a.to_le_bytes());
+        out[160..].copy_from_slice(&self.s_b.to_le_bytes());
+        out
+    }
+
+    /// Convert a byte sequence to a proof
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let cc = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[..48]).unwrap())
+            .map(G1Projective::from);
+        let ee = G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[48..96]).unwrap())
+            .map(G1Projective::from);
+        let challenge = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[96..128]).unwrap());
+        let s_a = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[128..160]).unwrap());
+        let s_b = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[160..]).unwrap());
+
+        cc.and_then(|c| {
+            ee.and_then(|e| {
+                challenge.and_then(|challenge| {
+                    s_a.and_then(|s_a| {
+                        s_b.and_then(|s_b| {
+                            CtOption::new(
+                                Self {
+                                    c,
+                                    e,
+                                    challenge,
+                                    s_a,
+                                    s_b,
+                                },
+                                Choice::from(1u8),
+                            )
+                        })
+                    })
+                })
+            })
+        })
+    }
+}
+
+fn non_membership_challenge(
+    m: Scalar,
+    accumulator: Accumulator,
+    c: G1Projective,
+    e: G1Projective,
+    t: G1Projective,
+    proof: &Proof,
+    nonce: &[u8],
+) -> Scalar {
+    hash_to_scalar(&[
+        NON_MEMBERSHIP_DST,
+        &m.to_le_bytes(),
+        &accumulator.to_bytes(),
+        &c.to_affine().to_compressed(),
+        &e.to_affine().to_compressed(),
+        &t.to_affine().to_compressed(),
+        &proof.to_bytes(),
+        nonce,
+    ])
+}
+
+/// m = H_s(id) as used for tokens
+fn id_scalar(id: &[u8]) -> Result<Scalar, Error> {
+    let m = hash_to_scalar(&[id]);
+    if m.is_zero().unwrap_u8() == 1 {
+        return Err(Error::ZeroHash);
+    }
+    Ok(m)
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod registry {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Tracks revoked ids and issues witnesses and updates.
+    ///
+    /// The accumulator starts with a random element that is never
+    /// published so witnesses are never trivial.
+    ///
+    /// ```
+    /// use oberon::*;
+    /// use rand::thread_rng;
+    ///
+    /// let sk = SecretKey::new(thread_rng());
+    /// let pk = PublicKey::from(&sk);
+    /// let mut registry = RevocationRegistry::new(thread_rng());
+    /// let acc_pk = registry.public_key();
+    ///
+    /// let id = b"alice";
+    /// let token = sk.sign(id).unwrap();
+    /// let mut accumulator = registry.accumulator();
+    /// let mut witness = registry.witness(id).unwrap();
+    ///
+    /// // The holder applies published updates to stay current
+    /// let update = registry.revoke(b"bobby").unwrap();
+    /// witness.update(id, accumulator, &[update]).unwrap();
+    /// accumulator = update.accumulator();
+    ///
+    /// let nonce = b"nonce";
+    /// let proof = Proof::new(&token, &[], id, nonce, thread_rng()).unwrap();
+    /// let nm = NonMembershipProof::new(&witness, id, accumulator, &proof, nonce, thread_rng()).unwrap();
+    ///
+    /// assert_eq!(proof.open(pk, id, nonce).unwrap_u8(), 1u8);
+    /// assert_eq!(nm.open(acc_pk, accumulator, id, &proof, nonce).unwrap_u8(), 1u8);
+    /// ```
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct RevocationRegistry {
+        sk: AccumulatorSecretKey,
+        accumulator: Accumulator,
+        elements: Vec<Scalar>,
+        history: Vec<AccumulatorUpdate>,
+    }
+
+    impl RevocationRegistry {
+        /// Create a new registry with a random key
+        pub fn new(mut rng: impl RngCore + CryptoRng) -> Self {
+            let sk = AccumulatorSecretKey::new(&mut rng);
+            Self::with_key(sk, rng)
+        }
+
+        /// Create a new registry with an existing key
+        pub fn with_key(sk: AccumulatorSecretKey, mut rng: impl RngCore + CryptoRng) -> Self {
+            let seed = non_zero_scalar(&mut rng);
+            let accumulator = Accumulator(G1Projective::GENERATOR * (seed + sk.0));
+            Self {
+                sk,
+                accumulator,
+                elements: alloc::vec![seed],
+                history: Vec::new(),
+            }
+        }
+
+        /// The public key for verifying non-membership
+        pub fn public_key(&self) -> AccumulatorPublicKey {
+            AccumulatorPublicKey::from(&self.sk)
+        }
+
+        /// The current accumulator value
+        pub fn accumulator(&self) -> Accumulator {
+            self.accumulator
+        }
+
+        /// All updates published by this registry in order
+        pub fn history(&self) -> &[AccumulatorUpdate] {
+            &self.history
+        }
+
+        /// Is `id` revoked
+        pub fn is_revoked<B: AsRef<[u8]>>(&self, id: B) -> bool {
+            match id_scalar(id.as_ref()) {
+                Err(_) => false,
+                Ok(m) => self.elements[1..].contains(&m),
+            }
+        }
+
+        /// Revoke `id` returning the update to publish
+        pub fn revoke<B: AsRef<[u8]>>(&mut self, id: B) -> Result<AccumulatorUpdate, Error> {
+            let m = id_scalar(id.as_ref())?;
+            if self.elements.contains(&m) {
+                return Err(Error::Revoked);
+            }
+            self.accumulator = Accumulator(self.accumulator.0 * (m + self.sk.0));
+            self.elements.push(m);
+            Ok(self.record(m, true))
+        }
+
+        /// Reinstate a revoked `id` returning the update to publish
+        pub fn restore<B: AsRef<[u8]>>(&mut self, id: B) -> Result<AccumulatorUpdate, Error> {
+            let m = id_scalar(id.as_ref())?;
+            let index = self.elements[1..]
+                .iter()
+                .position(|e| *e == m)
+                .ok_or(Error::InvalidParameters)?;
+            let inv = (m + self.sk.0).invert().unwrap();
+            self.accumulator = Accumulator(self.accumulator.0 * inv);
+            self.elements.remove(index + 1);
+            Ok(self.record(m, false))
+        }
+
+        /// Create a witness that `id` is not revoked for the current accumulator
+        pub fn witness<B: AsRef<[u8]>>(&self, id: B) -> Result<NonMembershipWitness, Error> {
+            let m = id_scalar(id.as_ref())?;
+            let d = self
+                .elements
+                .iter()
+                .fold(Scalar::ONE, |acc, e| acc * (*e - m));
+            if d.is_zero().unwrap_u8() == 1 {
+                return Err(Error::Revoked);
+            }
+            let inv =
+                Option::<Scalar>::from((m + self.sk.0).invert()).ok_or(Error::InvalidParameters)?;
+            let c = (self.accumulator.0 - G1Projective::GENERATOR * d) * inv;
+            Ok(NonMembershipWitness { c, d })
+        }
+
+        fn record(&mut self, element: Scalar, revoked: bool) -> AccumulatorUpdate {
+            let update = AccumulatorUpdate {
+                element,
+                revoked,
+                accumulator: self.accumulator,
+            };
+            self.history.push(update);
+            update
+        }
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use registry::*;
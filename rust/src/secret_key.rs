@@ -66,6 +66,8 @@ impl ConstantTimeEq for SecretKey {
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(SecretKey);
 
+try_from_bytes_impl!(SecretKey);
+
 impl SecretKey {
     /// The number of bytes in a secret key
     pub const BYTES: usize = 96;
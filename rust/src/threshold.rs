@@ -0,0 +1,406 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::inner_types::*;
+use crate::{PublicKey, SecretKey, Token};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::ZeroizeOnDrop;
+
+/// A Shamir share of a secret key held by one issuer
+/// Display is not implemented to prevent accidental leak of the share
+///
+/// Any `threshold` shares created by `SecretKeyShare::split` can
+/// produce partial tokens that combine into a token valid under
+/// the original `PublicKey`.
+///
+/// ```
+/// use oberon::*;
+///
+/// let sk = SecretKey::new(rand::thread_rng());
+/// let pk = PublicKey::from(&sk);
+/// let shares = SecretKeyShare::split(&sk, 2, 3, rand::thread_rng()).unwrap();
+///
+/// let id = b"test identity";
+/// let partials = [shares[0].sign(id).unwrap(), shares[2].sign(id).unwrap()];
+/// let token = PartialToken::combine(&partials).unwrap();
+/// assert_eq!(token.verify(pk, id).unwrap_u8(), 1u8);
+/// ```
+#[derive(Clone, Debug, Eq, Deserialize, Serialize, ZeroizeOnDrop)]
+#[zeroize(drop)]
+pub struct SecretKeyShare {
+    pub(crate) identifier: u16,
+    pub(crate) w: Scalar,
+    pub(crate) x: Scalar,
+    pub(crate) y: Scalar,
+}
+
+impl Default for SecretKeyShare {
+    fn default() -> Self {
+        Self {
+            identifier: 0,
+            w: Scalar::ZERO,
+            x: Scalar::ZERO,
+            y: Scalar::ZERO,
+        }
+    }
+}
+
+impl PartialEq for SecretKeyShare {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl ConstantTimeEq for SecretKeyShare {
+    fn ct_eq(&self, rhs: &Self) -> Choice {
+        self.identifier.ct_eq(&rhs.identifier)
+            & self.x.ct_eq(&rhs.x)
+            & self.y.ct_eq(&rhs.y)
+            & self.w.ct_eq(&rhs.w)
+    }
+}
+
+try_from_bytes_impl!(SecretKeyShare);
+
+impl SecretKeyShare {
+    /// The number of bytes in a secret key share
+    pub const BYTES: usize = 98;
+
+    /// Split a secret key into `limit` shares where any `threshold`
+    /// of them are required to sign tokens
+    pub fn split(
+        sk: &SecretKey,
+        threshold: usize,
+        limit: usize,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Option<Vec<Self>> {
+        if threshold < 2 || limit < threshold || limit > u16::MAX as usize {
+            return None;
+        }
+        let w = random_polynomial(sk.w, threshold, &mut rng);
+        let x = random_polynomial(sk.x, threshold, &mut rng);
+        let y = random_polynomial(sk.y, threshold, &mut rng);
+
+        let shares = (1..=limit as u16)
+            .map(|identifier| {
+                let s = Scalar::from(identifier as u64);
+                Self {
+                    identifier,
+                    w: evaluate_polynomial(&w, s),
+                    x: evaluate_polynomial(&x, s),
+                    y: evaluate_polynomial(&y, s),
+                }
+            })
+            .collect();
+        Some(shares)
+    }
+
+    /// Reconstruct the secret key from `threshold` shares
+    pub fn combine(shares: &[SecretKeyShare]) -> Option<SecretKey> {
+        if shares.is_empty() {
+            return None;
+        }
+        let mut sk = SecretKey::default();
+        for share in shares {
+            let lambda =
+                lagrange_coefficient(share.identifier, shares.iter().map(|s| s.identifier))?;
+            sk.w += share.w * lambda;
+            sk.x += share.x * lambda;
+            sk.y += share.y * lambda;
+        }
+        Some(sk)
+    }
+
+    /// The identifier of this share
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Sign an `id` to a partial token
+    pub fn sign<B: AsRef<[u8]>>(&self, id: B) -> Option<PartialToken> {
+        PartialToken::new(self, id)
+    }
+
+    /// Convert this secret key share into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..2].copy_from_slice(&self.identifier.to_le_bytes());
+        out[2..34].copy_from_slice(&self.w.to_le_bytes()[..]);
+        out[34..66].copy_from_slice(&self.x.to_le_bytes()[..]);
+        out[66..].copy_from_slice(&self.y.to_le_bytes()[..]);
+        out
+    }
+
+    /// Convert a byte sequence to a secret key share
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let identifier = u16::from_le_bytes([data[0], data[1]]);
+        let ww = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[2..34]).unwrap());
+        let xx = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[34..66]).unwrap());
+        let yy = Scalar::from_le_bytes(&<[u8; 32]>::try_from(&data[66..]).unwrap());
+
+        ww.and_then(|w| {
+            xx.and_then(|x| {
+                yy.and_then(|y| {
+                    CtOption::new(
+                        Self {
+                            identifier,
+                            w,
+                            x,
+                            y,
+                        },
+                        !identifier.ct_eq(&0u16),
+                    )
+                })
+            })
+        })
+    }
+}
+
+/// The public key corresponding to a secret key share.
+/// Used to check partial tokens from a single issuer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PublicKeyShare {
+    pub(crate) identifier: u16,
+    pub(crate) w: G2Projective,
+    pub(crate) x: G2Projective,
+    pub(crate) y: G2Projective,
+}
+
+impl Default for PublicKeyShare {
+    fn default() -> Self {
+        Self {
+            identifier: 0,
+            w: G2Projective::IDENTITY,
+            x: G2Projective::IDENTITY,
+            y: G2Projective::IDENTITY,
+        }
+    }
+}
+
+impl From<&SecretKeyShare> for PublicKeyShare {
+    fn from(sk: &SecretKeyShare) -> Self {
+        Self {
+            identifier: sk.identifier,
+            w: G2Projective::GENERATOR * sk.w,
+            x: G2Projective::GENERATOR * sk.x,
+            y: G2Projective::GENERATOR * sk.y,
+        }
+    }
+}
+
+try_from_bytes_impl!(PublicKeyShare);
+
+impl PublicKeyShare {
+    /// The number of bytes in a public key share
+    pub const BYTES: usize = 290;
+
+    /// Reconstruct the public key from `threshold` public key shares
+    pub fn combine(shares: &[PublicKeyShare]) -> Option<PublicKey> {
+        if shares.is_empty() {
+            return None;
+        }
+        let mut pk = PublicKey::default();
+        for share in shares {
+            let lambda =
+                lagrange_coefficient(share.identifier, shares.iter().map(|s| s.identifier))?;
+            pk.w += share.w * lambda;
+            pk.x += share.x * lambda;
+            pk.y += share.y * lambda;
+        }
+        Some(pk)
+    }
+
+    /// The identifier of this share
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Is this public key share invalid
+    pub fn is_invalid(&self) -> Choice {
+        self.identifier.ct_eq(&0u16) | self.public_key().is_invalid()
+    }
+
+    /// Convert this public key share into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..2].copy_from_slice(&self.identifier.to_le_bytes());
+        out[2..].copy_from_slice(&self.public_key().to_bytes());
+        out
+    }
+
+    /// Convert a byte sequence to a public key share
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let identifier = u16::from_le_bytes([data[0], data[1]]);
+        let ww = G2Affine::from_compressed(&<[u8; 96]>::try_from(&data[2..98]).unwrap())
+            .map(G2Projective::from);
+        let xx = G2Affine::from_compressed(&<[u8; 96]>::try_from(&data[98..194]).unwrap())
+            .map(G2Projective::from);
+        let yy = G2Affine::from_compressed(&<[u8; 96]>::try_from(&data[194..]).unwrap())
+            .map(G2Projective::from);
+
+        ww.and_then(|w| {
+            xx.and_then(|x| {
+                yy.and_then(|y| {
+                    CtOption::new(
+                        Self {
+                            identifier,
+                            w,
+                            x,
+                            y,
+                        },
+                        !identifier.ct_eq(&0u16),
+                    )
+                })
+            })
+        })
+    }
+
+    /// Verify that a partial token is valid
+    pub fn verify_partial_token<B: AsRef<[u8]>>(&self, id: B, token: &PartialToken) -> Choice {
+        token.verify(self, id)
+    }
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey {
+            w: self.w,
+            x: self.x,
+            y: self.y,
+        }
+    }
+}
+
+/// A token signed by a single secret key share
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PartialToken {
+    pub(crate) identifier: u16,
+    pub(crate) token: Token,
+}
+
+try_from_bytes_impl!(PartialToken);
+
+impl PartialToken {
+    /// The number of bytes in a partial token
+    pub const BYTES: usize = 50;
+
+    /// Create a new partial token
+    pub fn new<B: AsRef<[u8]>>(sk: &SecretKeyShare, id: B) -> Option<Self> {
+        let share_key = SecretKey {
+            w: sk.w,
+            x: sk.x,
+            y: sk.y,
+        };
+        Token::new(&share_key, id).map(|token| Self {
+            identifier: sk.identifier,
+            token,
+        })
+    }
+
+    /// Check whether the partial token is valid to the public key share
+    /// that corresponds to the secret key share that created it
+    pub fn verify<B: AsRef<[u8]>>(&self, pk: &PublicKeyShare, id: B) -> Choice {
+        if (pk.is_invalid() | !self.identifier.ct_eq(&pk.identifier)).unwrap_u8() == 1 {
+            return Choice::from(0u8);
+        }
+        self.token.verify(pk.public_key(), id)
+    }
+
+    /// Combine `threshold` partial tokens into a token
+    pub fn combine(partials: &[PartialToken]) -> Option<Token> {
+        let mut sigma = G1Projective::IDENTITY;
+        for partial in partials {
+            let lambda =
+                lagrange_coefficient(partial.identifier, partials.iter().map(|p| p.identifier))?;
+            sigma += partial.token.0 * lambda;
+        }
+        if sigma.is_identity().unwrap_u8() == 1 {
+            return None;
+        }
+        Some(Token(sigma))
+    }
+
+    /// The identifier of the share that created this partial token
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Convert this partial token into a byte sequence
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let mut out = [0u8; Self::BYTES];
+        out[..2].copy_from_slice(&self.identifier.to_le_bytes());
+        out[2..].copy_from_slice(&self.token.to_bytes());
+        out
+    }
+
+    /// Convert a byte sequence to a partial token
+    pub fn from_bytes(data: &[u8; Self::BYTES]) -> CtOption<Self> {
+        let identifier = u16::from_le_bytes([data[0], data[1]]);
+        G1Affine::from_compressed(&<[u8; 48]>::try_from(&data[2..]).unwrap()).and_then(|p| {
+            CtOption::new(
+                Self {
+                    identifier,
+                    token: Token(G1Projective::from(p)),
+                },
+                !identifier.ct_eq(&0u16),
+            )
+        })
+    }
+}
+
+/// Create a random polynomial of degree `threshold - 1` with `secret` as the constant term
+pub(crate) fn random_polynomial(
+    secret: Scalar,
+    threshold: usize,
+    mut rng: impl RngCore + CryptoRng,
+) -> Vec<Scalar> {
+    let mut coefficients = Vec::with_capacity(threshold);
+    coefficients.push(secret);
+    for _ in 1..threshold {
+        coefficients.push(Scalar::random(&mut rng));
+    }
+    coefficients
+}
+
+/// Evaluate the polynomial at `x` using Horner's method
+pub(crate) fn evaluate_polynomial(coefficients: &[Scalar], x: Scalar) -> Scalar {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, c| acc * x + c)
+}
+
+/// Compute the lagrange coefficient at zero for `identifier`
+/// given the set of participating `identifiers`.
+/// Fails if any identifier is zero or repeated.
+pub(crate) fn lagrange_coefficient(
+    identifier: u16,
+    identifiers: impl Iterator<Item = u16>,
+) -> Option<Scalar> {
+    if identifier == 0 {
+        return None;
+    }
+    let xi = Scalar::from(identifier as u64);
+    let mut num = Scalar::ONE;
+    let mut den = Scalar::ONE;
+    let mut seen = false;
+    for j in identifiers {
+        if j == 0 {
+            return None;
+        }
+        if j == identifier {
+            if seen {
+                return None;
+            }
+            seen = true;
+            continue;
+        }
+        let xj = Scalar::from(j as u64);
+        num *= xj;
+        den *= xj - xi;
+    }
+    Option::<Scalar>::from(den.invert()).map(|d| num * d)
+}
@@ -3,7 +3,7 @@
     SPDX-License-Identifier: Apache-2.0
 */
 use crate::inner_types::*;
-use crate::{util::*, Blinding, PublicKey, SecretKey};
+use crate::{Blinding, Error, IdDigest, PublicKey, SecretKey};
 #[cfg(feature = "wasm")]
 use core::convert::TryFrom;
 use core::ops::{Add, Sub};
@@ -49,6 +49,8 @@ impl ConstantTimeEq for Token {
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(Token);
 
+try_from_bytes_impl!(Token);
+
 impl<'a, 'b> Add<&'b Blinding> for &'a Token {
     type Output = Token;
 
@@ -127,54 +129,58 @@ impl Token {
 
     /// Create a new token
     pub fn new<B: AsRef<[u8]>>(sk: &SecretKey, id: B) -> Option<Self> {
-        let id = id.as_ref();
-        let m = hash_to_scalar(&[id]);
-        if m.is_zero().unwrap_u8() == 1 {
-            return None;
-        }
-        let m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
-        if m_tick.is_zero().unwrap_u8() == 1 {
-            return None;
-        }
-        let u = hash_to_curve(&m_tick.to_le_bytes()[..]);
-        if u.is_identity().unwrap_u8() == 1 {
-            return None;
-        }
+        Self::try_new(sk, id).ok()
+    }
+
+    /// Create a new token returning the reason on failure
+    pub fn try_new<B: AsRef<[u8]>>(sk: &SecretKey, id: B) -> Result<Self, Error> {
+        Self::try_new_with_digest(sk, &IdDigest::try_new(id)?)
+    }
+
+    /// Create a new token from a precomputed id digest
+    pub(crate) fn try_new_with_digest(sk: &SecretKey, digest: &IdDigest) -> Result<Self, Error> {
+        let IdDigest { m, m_tick, a: u } = *digest;
 
         let sigma = u * (sk.x + sk.w * m_tick + sk.y * m);
         if sigma.is_identity().unwrap_u8() == 1 {
-            return None;
+            return Err(Error::IdentityPoint);
         }
-        Some(Self(sigma))
+        Ok(Self(sigma))
     }
 
     /// Check whether the token is valid to the public key
     pub fn verify<B: AsRef<[u8]>>(&self, pk: PublicKey, id: B) -> Choice {
-        let id = id.as_ref();
-        let m = hash_to_scalar(&[id]);
-        if m.is_zero().unwrap_u8() == 1 {
-            return Choice::from(0u8);
+        match IdDigest::new(id) {
+            None => Choice::from(0u8),
+            Some(digest) => self.verify_with_digest(pk, &digest),
         }
-        let m_tick = hash_to_scalar(&[&m.to_le_bytes()[..]]);
-        if m_tick.is_zero().unwrap_u8() == 1 {
-            return Choice::from(0u8);
+    }
+
+    /// Check whether the token is valid to the public key
+    /// returning the reason on failure. Unlike `verify`, this
+    /// is not constant time with respect to why it failed
+    pub fn try_verify<B: AsRef<[u8]>>(&self, pk: PublicKey, id: B) -> Result<(), Error> {
+        if pk.is_invalid().unwrap_u8() == 1 {
+            return Err(Error::InvalidPublicKey);
+        }
+        if self.0.is_identity().unwrap_u8() == 1 {
+            return Err(Error::IdentityPoint);
         }
-        let u = hash_to_curve(&m_tick.to_le_bytes()[..]);
-        if u.is_identity().unwrap_u8() == 1 {
-            return Choice::from(0u8);
+        let digest = IdDigest::try_new(id)?;
+        if self.verify_with_digest(pk, &digest).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
         }
+    }
 
-        #[cfg(feature = "std")]
-        let rhs = G2Projective::sum_of_products(
-            &[pk.w, pk.x, pk.y],
-            &[m_tick, Scalar::ONE, m]);
-        #[cfg(all(feature = "rust", not(feature = "std")))]
-        let rhs = G2Projective::sum_of_products_in_place(
-            &[pk.w, pk.x, pk.y],
-            &mut [m_tick, Scalar::ONE, m]);
+    /// Check whether the token is valid to the public key
+    /// using a precomputed id digest
+    pub fn verify_with_digest(&self, pk: PublicKey, digest: &IdDigest) -> Choice {
+        let rhs = pk.sum_of_products(digest.m_tick, digest.m);
 
         multi_miller_loop(&[
-            (&u.to_affine(), &G2Prepared::from(rhs.to_affine())),
+            (&digest.a.to_affine(), &G2Prepared::from(rhs.to_affine())),
             (
                 &self.0.to_affine(),
                 &G2Prepared::from(-G2Affine::generator()),
@@ -184,6 +190,42 @@ impl Token {
         .is_identity()
     }
 
+    /// Replace the blindings on this token. The `old` blindings must
+    /// remove to a token that is valid for `pk` and `id` before the
+    /// `new` blindings are applied, so a wrong old factor is an error
+    /// instead of a token that can never prove
+    ///
+    /// ```
+    /// use oberon::*;
+    /// use rand::thread_rng;
+    ///
+    /// let sk = SecretKey::new(thread_rng());
+    /// let pk = PublicKey::from(&sk);
+    /// let id = b"test identity";
+    /// let blinded_token = sk.sign(id).unwrap() - Blinding::new(b"1234");
+    ///
+    /// let old = [Blinding::new(b"1234")];
+    /// let new = [Blinding::new(b"5678")];
+    /// let blinded_token = blinded_token.rebind(&old, &new, pk, id).unwrap();
+    ///
+    /// let proof = Proof::new(&blinded_token, &new, id, b"nonce", thread_rng()).unwrap();
+    /// assert_eq!(proof.open(pk, id, b"nonce").unwrap_u8(), 1u8);
+    /// assert!(blinded_token.rebind(&old, &new, pk, id).is_err());
+    /// ```
+    pub fn rebind<B: AsRef<[u8]>>(
+        &self,
+        old: &[Blinding],
+        new: &[Blinding],
+        pk: PublicKey,
+        id: B,
+    ) -> Result<Self, Error> {
+        let token = Self(self.0 + old.iter().map(|b| b.0).sum::<G1Projective>());
+        token.try_verify(pk, id)?;
+        Ok(Self(
+            token.0 - new.iter().map(|b| b.0).sum::<G1Projective>(),
+        ))
+    }
+
     /// Convert this token into a byte sequence
     pub fn to_bytes(&self) -> [u8; Self::BYTES] {
         self.0.to_affine().to_compressed()
@@ -0,0 +1,318 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+use crate::{
+    Challenge, ChallengeKey, Error, HttpAuthorization, HttpComponent, Keyring, PublicKey,
+    ReplayCache, HTTP_AUTH_SCHEME,
+};
+use alloc::{sync::Arc, vec::Vec};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use http::{header, HeaderValue, Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use std::{sync::Mutex, time::SystemTime};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Looks up the issuer public key for the credentials on a request
+pub trait KeyResolver {
+    /// The key to check the proof with at `now` seconds since the unix epoch
+    fn resolve(&self, auth: &HttpAuthorization, now: u64) -> Result<PublicKey, Error>;
+}
+
+impl KeyResolver for PublicKey {
+    fn resolve(&self, _auth: &HttpAuthorization, _now: u64) -> Result<PublicKey, Error> {
+        Ok(*self)
+    }
+}
+
+impl KeyResolver for Keyring {
+    /// Uses the key named by `kid`. Returns `Error::UnknownKey` if the
+    /// credentials have no key id or the key is not in the keyring
+    fn resolve(&self, auth: &HttpAuthorization, now: u64) -> Result<PublicKey, Error> {
+        let key_id = auth.key_id().ok_or(Error::UnknownKey)?;
+        let entry = self.get(&key_id).ok_or(Error::UnknownKey)?;
+        if self.is_valid(&key_id, now) {
+            Ok(entry.public_key())
+        } else {
+            Err(Error::Expired)
+        }
+    }
+}
+
+impl<F> KeyResolver for F
+where
+    F: Fn(&HttpAuthorization, u64) -> Result<PublicKey, Error>,
+{
+    fn resolve(&self, auth: &HttpAuthorization, now: u64) -> Result<PublicKey, Error> {
+        self(auth, now)
+    }
+}
+
+/// Decides whether the nonce on a request is acceptable
+pub trait NoncePolicy {
+    /// Check `nonce` at `now` seconds since the unix epoch
+    fn check(&self, nonce: &[u8], now: u64) -> Result<(), Error>;
+}
+
+impl<F> NoncePolicy for F
+where
+    F: Fn(&[u8], u64) -> Result<(), Error>,
+{
+    fn check(&self, nonce: &[u8], now: u64) -> Result<(), Error> {
+        self(nonce, now)
+    }
+}
+
+/// Accepts nonces that are challenges issued by `key` within
+/// `ttl` seconds and not seen before
+#[derive(Debug)]
+pub struct ChallengePolicy<R> {
+    key: ChallengeKey,
+    ttl: u64,
+    cache: Mutex<R>,
+}
+
+impl<R: ReplayCache> ChallengePolicy<R> {
+    /// Create a new policy that records used challenges in `cache`
+    pub fn new(key: ChallengeKey, ttl: u64, cache: R) -> Self {
+        Self {
+            key,
+            ttl,
+            cache: Mutex::new(cache),
+        }
+    }
+}
+
+impl<R: ReplayCache> NoncePolicy for ChallengePolicy<R> {
+    fn check(&self, nonce: &[u8], now: u64) -> Result<(), Error> {
+        let challenge = Challenge::try_from_bytes(nonce)?;
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        self.key.check_once(&challenge, now, self.ttl, &mut *cache)
+    }
+}
+
+/// The identity proven by a request, inserted into the
+/// request extensions by `OberonAuth`
+#[derive(Clone, Debug)]
+pub struct VerifiedIdentity {
+    id: Vec<u8>,
+    public_key: PublicKey,
+}
+
+impl VerifiedIdentity {
+    /// The id the proof was made for
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// The key the token was issued under
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+}
+
+/// A `Layer` that checks the `Authorization: Oberon ...` header
+/// on every request before passing it to the inner service
+///
+/// Requests without valid credentials get a 401 response with
+/// the reason in the response extensions. The `Content-Digest`
+/// header is bound to the proof but checking it against the body
+/// is left to the handler with `HttpAuthorization::check_content_digest`.
+///
+/// ```
+/// use oberon::*;
+/// use rand::thread_rng;
+///
+/// let sk = SecretKey::new(thread_rng());
+/// let challenge_key = ChallengeKey::new(thread_rng());
+/// let layer = OberonAuthLayer::new(
+///     PublicKey::from(&sk),
+///     ChallengePolicy::new(challenge_key, 60, MemoryReplayCache::new()),
+/// );
+/// ```
+pub struct OberonAuthLayer<K, P> {
+    resolver: Arc<K>,
+    policy: Arc<P>,
+    required: Vec<HttpComponent>,
+    clock: fn() -> u64,
+}
+
+impl<K, P> Clone for OberonAuthLayer<K, P> {
+    fn clone(&self) -> Self {
+        Self {
+            resolver: self.resolver.clone(),
+            policy: self.policy.clone(),
+            required: self.required.clone(),
+            clock: self.clock,
+        }
+    }
+}
+
+impl<K: KeyResolver, P: NoncePolicy> OberonAuthLayer<K, P> {
+    /// Create a new layer that requires proofs to cover
+    /// the request method and target
+    pub fn new(resolver: K, policy: P) -> Self {
+        Self {
+            resolver: Arc::new(resolver),
+            policy: Arc::new(policy),
+            required: [HttpComponent::Method, HttpComponent::TargetUri].to_vec(),
+            clock: unix_now,
+        }
+    }
+
+    /// The request components proofs must cover
+    pub fn require(mut self, components: &[HttpComponent]) -> Self {
+        self.required = components.to_vec();
+        self
+    }
+
+    /// Use `clock` for the current time in seconds since the unix epoch
+    pub fn with_clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl<S, K, P> Layer<S> for OberonAuthLayer<K, P> {
+    type Service = OberonAuth<S, K, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OberonAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// The service created by `OberonAuthLayer`
+pub struct OberonAuth<S, K, P> {
+    inner: S,
+    layer: OberonAuthLayer<K, P>,
+}
+
+impl<S: Clone, K, P> Clone for OberonAuth<S, K, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, K: KeyResolver, P: NoncePolicy> OberonAuth<S, K, P> {
+    /// Check the credentials on `request` returning the proven identity
+    pub fn authenticate<B>(
+        &self,
+        request: Request<B>,
+    ) -> (Request<B>, Result<VerifiedIdentity, Error>) {
+        let (parts, body) = request.into_parts();
+        let result = self.check(&parts);
+        (Request::from_parts(parts, body), result)
+    }
+
+    fn check(&self, parts: &http::request::Parts) -> Result<VerifiedIdentity, Error> {
+        let now = (self.layer.clock)();
+        let auth = HttpAuthorization::from_headers(&parts.headers)?;
+        let public_key = self.layer.resolver.resolve(&auth, now)?;
+        auth.try_verify(parts, public_key, &self.layer.required)?;
+        // only spend the nonce once the proof is known to be good
+        self.layer.policy.check(auth.nonce(), now)?;
+        Ok(VerifiedIdentity {
+            id: auth.id().to_vec(),
+            public_key,
+        })
+    }
+}
+
+impl<S, K, P, B, ResB> Service<Request<B>> for OberonAuth<S, K, P>
+where
+    S: Service<Request<B>, Response = Response<ResB>>,
+    K: KeyResolver,
+    P: NoncePolicy,
+    ResB: Default,
+{
+    type Response = Response<ResB>;
+    type Error = S::Error;
+    type Future = OberonAuthFuture<S::Future, ResB>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        match self.authenticate(request) {
+            (mut request, Ok(identity)) => {
+                request.extensions_mut().insert(identity);
+                OberonAuthFuture {
+                    state: AuthState::Inner {
+                        future: self.inner.call(request),
+                    },
+                }
+            }
+            (_, Err(error)) => OberonAuthFuture {
+                state: AuthState::Rejected {
+                    response: Some(unauthorized(error)),
+                },
+            },
+        }
+    }
+}
+
+pin_project! {
+    /// The response future of `OberonAuth`
+    pub struct OberonAuthFuture<F, B> {
+        #[pin]
+        state: AuthState<F, B>,
+    }
+}
+
+pin_project! {
+    #[project = AuthStateProj]
+    enum AuthState<F, B> {
+        Inner {
+            #[pin]
+            future: F,
+        },
+        Rejected {
+            response: Option<Response<B>>,
+        },
+    }
+}
+
+impl<F, B, E> Future for OberonAuthFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().state.project() {
+            AuthStateProj::Inner { future } => future.poll(cx),
+            AuthStateProj::Rejected { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+fn unauthorized<B: Default>(error: Error) -> Response<B> {
+    let mut response = Response::new(B::default());
+    *response.status_mut() = StatusCode::UNAUTHORIZED;
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static(HTTP_AUTH_SCHEME),
+    );
+    response.extensions_mut().insert(error);
+    response
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -2,8 +2,9 @@
     Copyright Michael Lodder. All Rights Reserved.
     SPDX-License-Identifier: Apache-2.0
 */
-use crate::inner_types::{elliptic_curve::hash2curve::ExpandMsgXof, G1Projective, Scalar};
+use crate::inner_types::{elliptic_curve::hash2curve::ExpandMsgXof, *};
 use digest::{ExtendableOutput, Update, XofReader};
+use rand_core::{CryptoRng, RngCore};
 use sha3::Shake256;
 
 const TO_SCALAR_DST: &[u8] = b"OBERON_BLS12381FQ_XOF:SHAKE-256_";
@@ -38,3 +39,23 @@ pub fn hash_to_scalars(data: &[&[u8]], out: &mut [Scalar]) {
 pub fn hash_to_curve(data: &[u8]) -> G1Projective {
     G1Projective::hash::<ExpandMsgXof<Shake256>>(data, TO_CURVE_DST)
 }
+
+pub fn non_zero_scalar(mut rng: impl RngCore + CryptoRng) -> Scalar {
+    let mut s = Scalar::random(&mut rng);
+    while s.is_zero().unwrap_u8() == 1 {
+        s = Scalar::random(&mut rng);
+    }
+    s
+}
+
+/// Randomize the unblinded token `sigma` for the id hashed to `a`.
+/// Returns h, s' and t
+pub fn randomize(
+    sigma: G1Projective,
+    a: G1Projective,
+    mut rng: impl RngCore + CryptoRng,
+) -> (G1Projective, G1Projective, Scalar) {
+    let r = non_zero_scalar(&mut rng);
+    let t = Scalar::random(&mut rng);
+    (a * r, (sigma + a * t) * r, t)
+}
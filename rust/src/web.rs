@@ -2,14 +2,48 @@
     Copyright Michael Lodder. All Rights Reserved.
     SPDX-License-Identifier: Apache-2.0
 */
-use crate::{Blinding, Proof, PublicKey, SecretKey, Token};
+use crate::{
+    Blinding, Challenge, ChallengeKey, Error, PasswordParams, Proof, PublicKey, SecretKey, Token,
+};
 use rand::prelude::*;
 use wasm_bindgen::prelude::*;
 
-use std::vec::Vec;
+use std::{string::String, string::ToString, vec::Vec};
 
 type BlindingList = Vec<Blinding>;
 
+/// The error thrown when an operation fails
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct OberonError {
+    code: i32,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl OberonError {
+    /// The error code shared with the other bindings
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// The error description
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<Error> for OberonError {
+    fn from(e: Error) -> Self {
+        Self {
+            code: e.code(),
+            message: e.to_string(),
+        }
+    }
+}
+
 /// Create new random secret key
 #[wasm_bindgen]
 pub fn new_secret_key() -> SecretKey {
@@ -31,8 +65,8 @@ pub fn secret_key_from_seed(seed: &[u8]) -> SecretKey {
 
 /// Create a new token for a given ID
 #[wasm_bindgen]
-pub fn new_token(sk: SecretKey, id: &[u8]) -> Option<Token> {
-    Token::new(&sk, id)
+pub fn new_token(sk: SecretKey, id: &[u8]) -> Result<Token, OberonError> {
+    Ok(Token::try_new(&sk, id)?)
 }
 
 /// Verify a token for a given ID
@@ -47,6 +81,21 @@ pub fn create_blinding(data: &[u8]) -> Blinding {
     Blinding::new(data)
 }
 
+/// Create a blinding factor from a password for the token on `id`.
+/// `params` are the encoded `PasswordParams` or the defaults if missing
+#[wasm_bindgen]
+pub fn create_password_blinding(
+    password: &str,
+    id: &[u8],
+    params: Option<Vec<u8>>,
+) -> Result<Blinding, OberonError> {
+    let params = params
+        .map(|p| PasswordParams::try_from_bytes(&p))
+        .transpose()?
+        .unwrap_or_default();
+    Ok(Blinding::from_password(password, id, &params)?)
+}
+
 /// Adds a blinding factor to the token
 #[wasm_bindgen]
 pub fn add_blinding(token: Token, data: &[u8]) -> Token {
@@ -59,14 +108,41 @@ pub fn remove_blinding(token: Token, data: &[u8]) -> Token {
     token + Blinding::new(data)
 }
 
+/// Replaces the blinding factors on the token after checking the
+/// old ones give a valid token. Both lists are JSON encoded blindings
+#[wasm_bindgen]
+pub fn rebind(
+    token: Token,
+    old_blindings: JsValue,
+    new_blindings: JsValue,
+    pk: PublicKey,
+    id: &[u8],
+) -> Result<Token, OberonError> {
+    let parse = |blindings: JsValue| {
+        blindings
+            .as_string()
+            .and_then(|b| serde_json::from_str::<BlindingList>(&b).ok())
+            .ok_or(Error::InvalidEncoding)
+    };
+    let old = parse(old_blindings)?;
+    let new = parse(new_blindings)?;
+    Ok(token.rebind(&old, &new, pk, id)?)
+}
+
 /// Creates a proof using a nonce received from a verifier
 #[wasm_bindgen]
-pub fn create_proof(token: Token, id: &[u8], blindings: JsValue, nonce: &[u8]) -> Option<Proof> {
+pub fn create_proof(
+    token: Token,
+    id: &[u8],
+    blindings: JsValue,
+    nonce: &[u8],
+) -> Result<Proof, OberonError> {
     let rng = thread_rng();
-    match serde_json::from_str::<BlindingList>(&blindings.as_string().unwrap()) {
-        Err(_) => None,
-        Ok(bs) => Proof::new(&token, &bs, id, nonce, rng),
-    }
+    let bs = blindings
+        .as_string()
+        .and_then(|b| serde_json::from_str::<BlindingList>(&b).ok())
+        .ok_or(Error::InvalidEncoding)?;
+    Ok(Proof::try_new(&token, &bs, id, nonce, rng)?)
 }
 
 /// Creates a proof using a nonce received from a verifier
@@ -74,3 +150,21 @@ pub fn create_proof(token: Token, id: &[u8], blindings: JsValue, nonce: &[u8]) -
 pub fn verify_proof(proof: Proof, pk: PublicKey, id: &[u8], nonce: &[u8]) -> bool {
     proof.open(pk, id, nonce).unwrap_u8() == 1
 }
+
+/// Create a new random challenge key for a verifier
+#[wasm_bindgen]
+pub fn new_challenge_key() -> ChallengeKey {
+    ChallengeKey::new(thread_rng())
+}
+
+/// Mint a challenge to send to a prover as the proof nonce
+#[wasm_bindgen]
+pub fn new_challenge(key: ChallengeKey, now: u64) -> Challenge {
+    key.issue(now, thread_rng())
+}
+
+/// Check a challenge was issued by the key within `ttl` seconds before `now`
+#[wasm_bindgen]
+pub fn verify_challenge(challenge: Challenge, key: ChallengeKey, now: u64, ttl: u64) -> bool {
+    key.check(&challenge, now, ttl).is_ok()
+}
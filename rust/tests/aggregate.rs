@@ -0,0 +1,78 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{AggregatePublicKey, Blinding, Error, Proof, PublicKey, SecretKey};
+
+#[test]
+fn aggregate_tokens() {
+    let mut rng = MockRng::new();
+    let sk1 = SecretKey::new(&mut rng);
+    let sk2 = SecretKey::new(&mut rng);
+    let sk3 = SecretKey::new(&mut rng);
+    let pk1 = PublicKey::from(&sk1);
+    let pk2 = PublicKey::from(&sk2);
+    let pk3 = PublicKey::from(&sk3);
+
+    let apk = AggregatePublicKey::new(&[pk1, pk2, pk3]).unwrap();
+    assert_eq!(apk.public_key(), pk1 + pk2 + pk3);
+    assert_eq!(apk, AggregatePublicKey::new(&[pk3, pk1, pk2]).unwrap());
+
+    let token = sk1.sign(ID).unwrap() + sk2.sign(ID).unwrap() + sk3.sign(ID).unwrap();
+    assert_eq!(apk.verify_token(ID, &token).unwrap_u8(), 1u8);
+    assert_eq!(apk.verify_token(b"wrong id", &token).unwrap_u8(), 0u8);
+
+    let blinding = Blinding::new(b"1234");
+    let blinded = &token - &blinding;
+    let proof = Proof::new(&blinded, &[blinding], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open_aggregate(&apk, ID, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(proof.try_open_aggregate(&apk, ID, b"nonce"), Ok(()));
+    assert_eq!(proof.open(apk.into(), ID, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(
+        proof.try_open_aggregate(&apk, ID, b"other nonce"),
+        Err(Error::VerificationFailed)
+    );
+
+    // missing one issuer
+    let partial = sk1.sign(ID).unwrap() + sk2.sign(ID).unwrap();
+    let proof = Proof::new(&partial, &[], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open_aggregate(&apk, ID, b"nonce").unwrap_u8(), 0u8);
+    let apk12 = AggregatePublicKey::new(&[pk1, pk2]).unwrap();
+    assert_eq!(proof.open_aggregate(&apk12, ID, b"nonce").unwrap_u8(), 1u8);
+
+    // tokens for different ids do not combine
+    let mixed = sk1.sign(ID).unwrap() + sk2.sign(b"another id").unwrap();
+    assert_eq!(apk12.verify_token(ID, &mixed).unwrap_u8(), 0u8);
+}
+
+#[test]
+fn aggregate_public_key_checks() {
+    let mut rng = MockRng::new();
+    let pk1 = PublicKey::from(&SecretKey::new(&mut rng));
+    let pk2 = PublicKey::from(&SecretKey::new(&mut rng));
+
+    assert_eq!(
+        AggregatePublicKey::try_new(&[]),
+        Err(Error::InvalidPublicKey)
+    );
+    assert_eq!(
+        AggregatePublicKey::try_new(&[pk1, pk2, pk1]),
+        Err(Error::InvalidPublicKey)
+    );
+    assert_eq!(
+        AggregatePublicKey::try_new(&[pk1, PublicKey::default()]),
+        Err(Error::InvalidPublicKey)
+    );
+    assert_eq!(AggregatePublicKey::new(&[pk1]).unwrap().public_key(), pk1);
+
+    let apk = AggregatePublicKey::new(&[pk1, pk2]).unwrap();
+    let bytes = apk.to_bytes();
+    assert_eq!(AggregatePublicKey::try_from_bytes(&bytes).unwrap(), apk);
+    assert_eq!(
+        AggregatePublicKey::try_from_bytes(&bytes[1..]),
+        Err(Error::InvalidLength)
+    );
+}
@@ -0,0 +1,87 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::MockRng;
+use oberon::{BatchVerifier, Blinding, Proof, PublicKey, SecretKey, Token};
+
+const NONCE: &[u8] = b"batch verification nonce";
+
+fn ids() -> Vec<Vec<u8>> {
+    (0..8u8).map(|i| vec![b'i', b'd', i]).collect()
+}
+
+#[test]
+fn batch_open_works() {
+    let mut rng = MockRng::new();
+    let sk1 = SecretKey::new(&mut rng);
+    let sk2 = SecretKey::new(&mut rng);
+    let pk1 = PublicKey::from(&sk1);
+    let pk2 = PublicKey::from(&sk2);
+    let blinding = Blinding::new(b"1234");
+
+    let mut entries = Vec::new();
+    for (i, id) in ids().into_iter().enumerate() {
+        let (sk, pk) = if i % 2 == 0 { (&sk1, pk1) } else { (&sk2, pk2) };
+        let token = sk.sign(&id).unwrap() - &blinding;
+        let proof = Proof::new(&token, &[blinding], &id, NONCE, &mut rng).unwrap();
+        assert_eq!(proof.open(pk, &id, NONCE).unwrap_u8(), 1u8);
+        entries.push((proof, pk, id, NONCE.to_vec()));
+    }
+    assert!(Proof::batch_open(&entries, &mut rng).is_empty());
+
+    // Bad entries are identified
+    entries[1].1 = pk1;
+    entries[4].3 = b"wrong nonce".to_vec();
+    entries[7].2 = b"wrong id".to_vec();
+    assert_eq!(Proof::batch_open(&entries, &mut rng), vec![1, 4, 7]);
+
+    // Invalid public keys are rejected without pairing
+    entries[0].1 = PublicKey::default();
+    assert_eq!(Proof::batch_open(&entries, &mut rng), vec![0, 1, 4, 7]);
+}
+
+#[test]
+fn batch_verify_tokens() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let mut entries: Vec<(Token, PublicKey, Vec<u8>)> = ids()
+        .into_iter()
+        .map(|id| (sk.sign(&id).unwrap(), pk, id))
+        .collect();
+    assert!(Token::batch_verify(&entries, &mut rng).is_empty());
+
+    entries[3].0 = entries[2].0.clone();
+    assert_eq!(Token::batch_verify(&entries, &mut rng), vec![3]);
+}
+
+#[test]
+fn batch_verifier_mixed() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let id = b"batch identity";
+    let token = sk.sign(id).unwrap();
+    let proof = Proof::new(&token, &[], id, NONCE, &mut rng).unwrap();
+
+    let mut verifier = BatchVerifier::new();
+    assert!(verifier.is_empty());
+    assert_eq!(verifier.verify(&mut rng).unwrap_u8(), 1u8);
+
+    assert_eq!(verifier.add_token(&token, pk, id), 0);
+    assert_eq!(verifier.add_proof(&proof, pk, id, NONCE), 1);
+    assert_eq!(verifier.len(), 2);
+    assert_eq!(verifier.verify(&mut rng).unwrap_u8(), 1u8);
+
+    assert_eq!(verifier.add_proof(&proof, pk, id, b"other nonce"), 2);
+    assert_eq!(verifier.verify(&mut rng).unwrap_u8(), 0u8);
+    assert_eq!(verifier.failures(&mut rng), vec![2]);
+
+    assert_eq!(verifier.add_proof(&Proof::default(), pk, id, NONCE), 3);
+    assert_eq!(verifier.failures(&mut rng), vec![2, 3]);
+}
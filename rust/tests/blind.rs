@@ -0,0 +1,167 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{
+    BlindIssuanceLog, BlindTokenRequest, BlindTokenResponse, Error, Proof, PublicKey, SecretKey,
+};
+use rand_core::RngCore;
+
+#[derive(Default)]
+struct Log(Vec<[u8; 32]>);
+
+impl BlindIssuanceLog for Log {
+    fn insert(&mut self, m_tick: &[u8; 32]) -> bool {
+        if self.0.contains(m_tick) {
+            return false;
+        }
+        self.0.push(*m_tick);
+        true
+    }
+}
+
+#[test]
+fn blind_issuance() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let (request, secrets) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    assert_eq!(request.verify().unwrap_u8(), 1u8);
+    let response = sk.blind_sign(&request, &mut Log::default()).unwrap();
+    let token = response.unblind(&secrets, pk, ID).unwrap();
+
+    assert_eq!(token.verify(pk, ID).unwrap_u8(), 1u8);
+    assert_eq!(token, sk.sign(ID).unwrap());
+
+    let mut nonce = [0u8; 16];
+    rng.fill_bytes(&mut nonce);
+    let proof = Proof::new(&token, &[], ID, nonce, &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, nonce).unwrap_u8(), 1u8);
+
+    // wrong id or key when unblinding
+    assert_eq!(
+        response.unblind(&secrets, pk, b"wrong id"),
+        Err(Error::VerificationFailed)
+    );
+    let pk2 = PublicKey::from(&SecretKey::new(&mut rng));
+    assert_eq!(
+        response.unblind(&secrets, pk2, ID),
+        Err(Error::VerificationFailed)
+    );
+    // secrets from another request
+    let (_, secrets2) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    assert_eq!(
+        response.unblind(&secrets2, pk, ID),
+        Err(Error::VerificationFailed)
+    );
+}
+
+#[test]
+fn blind_request_malformed() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+
+    assert_eq!(
+        sk.blind_sign(&BlindTokenRequest::default(), &mut Log::default())
+            .unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    let (request, _) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    let (other, _) = BlindTokenRequest::new(b"other id", &mut rng).unwrap();
+    let mut bytes = request.to_bytes();
+    // swap in a commitment from another request
+    bytes[80..128].copy_from_slice(&other.to_bytes()[80..128]);
+    let tampered = BlindTokenRequest::from_bytes(&bytes).unwrap();
+    assert_eq!(tampered.verify().unwrap_u8(), 0u8);
+    assert_eq!(
+        sk.blind_sign(&tampered, &mut Log::default()).unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    let mut bytes = request.to_bytes();
+    bytes[200] ^= 1;
+    let tampered = BlindTokenRequest::from_bytes(&bytes).unwrap();
+    assert_eq!(tampered.verify().unwrap_u8(), 0u8);
+}
+
+#[test]
+fn blind_request_generator_base() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let generator = hex::decode("97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb").unwrap();
+
+    // asking the issuer to sign the G1 generator would leak the key in G1
+    let (request, _) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    let mut bytes = request.to_bytes();
+    bytes[32..80].copy_from_slice(&generator);
+    let forged = BlindTokenRequest::from_bytes(&bytes).unwrap();
+    assert_eq!(forged.verify().unwrap_u8(), 0u8);
+    assert_eq!(
+        sk.blind_sign(&forged, &mut Log::default()).unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    // with m' = m = 0
+    bytes[..32].copy_from_slice(&[0u8; 32]);
+    bytes[80..128].copy_from_slice(&generator);
+    let forged = BlindTokenRequest::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        sk.blind_sign(&forged, &mut Log::default()).unwrap_err(),
+        Error::VerificationFailed
+    );
+}
+
+#[test]
+fn blind_serialization() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let (request, secrets) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    let bytes = request.to_bytes();
+    let request2 = BlindTokenRequest::from_bytes(&bytes).unwrap();
+    assert_eq!(request2.to_bytes(), bytes);
+    assert_eq!(
+        BlindTokenRequest::try_from_bytes(&bytes[1..]).unwrap_err(),
+        Error::InvalidLength
+    );
+
+    let response = sk.blind_sign(&request2, &mut Log::default()).unwrap();
+    let bytes = response.to_bytes();
+    let response2 = BlindTokenResponse::try_from_bytes(&bytes).unwrap();
+    assert_eq!(response2.to_bytes(), bytes);
+
+    let json = serde_json::to_string(&response2).unwrap();
+    let response3: BlindTokenResponse = serde_json::from_str(&json).unwrap();
+    assert!(response3.unblind(&secrets, pk, ID).is_ok());
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[test]
+fn blind_issuance_log() {
+    use oberon::MemoryBlindIssuanceLog;
+
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let mut log = MemoryBlindIssuanceLog::new();
+
+    let (request, _) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    assert!(sk.blind_sign(&request, &mut log).is_ok());
+    assert_eq!(log.len(), 1);
+
+    // a fresh request for the same id has the same m'
+    let (request, _) = BlindTokenRequest::new(ID, &mut rng).unwrap();
+    assert_eq!(
+        sk.blind_sign(&request, &mut log).unwrap_err(),
+        Error::Replayed
+    );
+
+    let (request, _) = BlindTokenRequest::new(b"other id", &mut rng).unwrap();
+    assert!(sk.blind_sign(&request, &mut log).is_ok());
+    assert_eq!(log.len(), 2);
+}
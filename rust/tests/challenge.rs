@@ -0,0 +1,158 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Challenge, ChallengeKey, Error, Proof, PublicKey, SecretKey};
+
+const NOW: u64 = 1_700_000_000;
+
+#[test]
+fn challenge_works() {
+    let mut rng = MockRng::new();
+    let key = ChallengeKey::new(&mut rng);
+    let challenge = key.issue(NOW, &mut rng);
+    assert_eq!(challenge.issued_at(), NOW);
+    assert_ne!(key.issue(NOW, &mut rng), challenge);
+
+    assert_eq!(key.check(&challenge, NOW, 60), Ok(()));
+    assert_eq!(key.check(&challenge, NOW + 60, 60), Ok(()));
+    assert_eq!(key.check(&challenge, NOW + 61, 60), Err(Error::Expired));
+    assert_eq!(key.check(&challenge, NOW - 61, 60), Err(Error::Expired));
+    // issued in the future
+    assert_eq!(key.check(&challenge, NOW - 1, 60), Err(Error::Expired));
+
+    let other = ChallengeKey::new(&mut rng);
+    assert_eq!(
+        other.check(&challenge, NOW, 60),
+        Err(Error::VerificationFailed)
+    );
+
+    // changing the issue time breaks the mac
+    let mut bytes = challenge.to_bytes();
+    bytes[7] ^= 1;
+    let tampered = Challenge::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        key.check(&tampered, NOW, 60),
+        Err(Error::VerificationFailed)
+    );
+
+    let bytes = challenge.to_bytes();
+    assert_eq!(Challenge::try_from_bytes(&bytes).unwrap(), challenge);
+    assert_eq!(
+        Challenge::try_from_bytes(&bytes[1..]),
+        Err(Error::InvalidLength)
+    );
+    let key2 = ChallengeKey::try_from_bytes(&key.to_bytes()).unwrap();
+    assert_eq!(key2.check(&challenge, NOW, 60), Ok(()));
+}
+
+#[test]
+fn open_with_challenge() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let key = ChallengeKey::new(&mut rng);
+    let challenge = key.issue(NOW, &mut rng);
+
+    let proof = Proof::new(&token, &[], ID, challenge.to_bytes(), &mut rng).unwrap();
+    assert_eq!(
+        proof.open_with_challenge(pk, ID, &challenge, &key, NOW + 10, 60),
+        Ok(())
+    );
+    assert_eq!(
+        proof.open_with_challenge(pk, ID, &challenge, &key, NOW + 100, 60),
+        Err(Error::Expired)
+    );
+    assert_eq!(
+        proof.open_with_challenge(pk, b"wrong id", &challenge, &key, NOW, 60),
+        Err(Error::VerificationFailed)
+    );
+
+    // a proof for a different challenge
+    let challenge2 = key.issue(NOW, &mut rng);
+    assert_eq!(
+        proof.open_with_challenge(pk, ID, &challenge2, &key, NOW, 60),
+        Err(Error::VerificationFailed)
+    );
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[test]
+fn replay_cache() {
+    use oberon::MemoryReplayCache;
+
+    let mut rng = MockRng::new();
+    let key = ChallengeKey::new(&mut rng);
+    let challenge = key.issue(NOW, &mut rng);
+    let mut cache = MemoryReplayCache::new();
+
+    assert_eq!(key.check_once(&challenge, NOW, 60, &mut cache), Ok(()));
+    assert_eq!(
+        key.check_once(&challenge, NOW + 1, 60, &mut cache),
+        Err(Error::Replayed)
+    );
+    assert_eq!(cache.len(), 1);
+
+    let challenge2 = key.issue(NOW + 30, &mut rng);
+    assert_eq!(
+        key.check_once(&challenge2, NOW + 30, 60, &mut cache),
+        Ok(())
+    );
+    assert_eq!(cache.len(), 2);
+
+    // the first challenge is pruned once it expires
+    let challenge3 = key.issue(NOW + 61, &mut rng);
+    assert_eq!(
+        key.check_once(&challenge3, NOW + 61, 60, &mut cache),
+        Ok(())
+    );
+    assert_eq!(cache.len(), 2);
+    assert_eq!(
+        key.check_once(&challenge, NOW + 61, 60, &mut cache),
+        Err(Error::Expired)
+    );
+
+    // forged challenges are not recorded
+    let forged = ChallengeKey::new(&mut rng).issue(NOW + 61, &mut rng);
+    assert_eq!(
+        key.check_once(&forged, NOW + 61, 60, &mut cache),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(cache.len(), 2);
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[test]
+fn open_once() {
+    use oberon::MemoryReplayCache;
+
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let key = ChallengeKey::new(&mut rng);
+    let challenge = key.issue(NOW, &mut rng);
+    let mut cache = MemoryReplayCache::new();
+
+    // a bad proof does not spend the challenge
+    let proof = Proof::new(&token, &[], ID, challenge.to_bytes(), &mut rng).unwrap();
+    assert_eq!(
+        proof.open_once(pk, b"wrong id", &challenge, &key, NOW, 60, &mut cache),
+        Err(Error::VerificationFailed)
+    );
+    assert!(cache.is_empty());
+
+    assert_eq!(
+        proof.open_once(pk, ID, &challenge, &key, NOW + 10, 60, &mut cache),
+        Ok(())
+    );
+    assert_eq!(cache.len(), 1);
+    assert_eq!(
+        proof.open_once(pk, ID, &challenge, &key, NOW + 20, 60, &mut cache),
+        Err(Error::Replayed)
+    );
+}
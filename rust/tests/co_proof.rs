@@ -0,0 +1,127 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{
+    Blinding, CoProofRequest, CoProofResponse, Error, Proof, PublicKey, SecretKey, ServerTokenShare,
+};
+
+#[test]
+fn co_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let (device_share, server_share) = token.split(ID, &mut rng).unwrap();
+
+    // neither share is the token
+    assert_eq!(device_share.verify(pk, ID).unwrap_u8(), 0u8);
+    let proof = Proof::new(&device_share, &[], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 0u8);
+
+    // the device share can be blinded like a token
+    let blinding = Blinding::new(b"1234");
+    let device_share = device_share - &blinding;
+
+    let (request, secrets) = CoProofRequest::new(&device_share, &[blinding], ID, &mut rng).unwrap();
+    let response = server_share
+        .respond(&request, ID, b"nonce", &mut rng)
+        .unwrap();
+    let proof = response.finish(&secrets, pk, ID, b"nonce").unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(proof.open(pk, ID, b"wrong nonce").unwrap_u8(), 0u8);
+    assert_eq!(proof.open(pk, b"wrong id", b"nonce").unwrap_u8(), 0u8);
+
+    // the device must use the nonce the server answered for
+    assert_eq!(
+        response
+            .finish(&secrets, pk, ID, b"wrong nonce")
+            .unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    // a wrong pin gives a proof the device rejects
+    let (request, secrets) =
+        CoProofRequest::new(&device_share, &[Blinding::new(b"1235")], ID, &mut rng).unwrap();
+    let response = server_share
+        .respond(&request, ID, b"nonce", &mut rng)
+        .unwrap();
+    assert_eq!(
+        response.finish(&secrets, pk, ID, b"nonce").unwrap_err(),
+        Error::VerificationFailed
+    );
+}
+
+#[test]
+fn co_proof_needs_both_shares() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let (device_share, server_share) = token.split(ID, &mut rng).unwrap();
+
+    // a new split does not work with the old server share
+    let (_, other_server_share) = token.split(ID, &mut rng).unwrap();
+    assert_ne!(server_share, other_server_share);
+    let (request, secrets) = CoProofRequest::new(&device_share, &[], ID, &mut rng).unwrap();
+    let response = other_server_share
+        .respond(&request, ID, b"nonce", &mut rng)
+        .unwrap();
+    assert_eq!(
+        response.finish(&secrets, pk, ID, b"nonce").unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    // the server answers for the id it holds the share of
+    let response = server_share
+        .respond(&request, b"wrong id", b"nonce", &mut rng)
+        .unwrap();
+    assert_eq!(
+        response.finish(&secrets, pk, ID, b"nonce").unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    assert_eq!(
+        server_share
+            .respond(&CoProofRequest::default(), ID, b"nonce", &mut rng)
+            .unwrap_err(),
+        Error::IdentityPoint
+    );
+    assert_eq!(
+        ServerTokenShare::default()
+            .respond(&request, ID, b"nonce", &mut rng)
+            .unwrap_err(),
+        Error::IdentityPoint
+    );
+}
+
+#[test]
+fn co_proof_serialization() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let (device_share, server_share) = token.split(ID, &mut rng).unwrap();
+
+    let server_share = ServerTokenShare::try_from_bytes(&server_share.to_bytes()).unwrap();
+    let (request, secrets) = CoProofRequest::new(&device_share, &[], ID, &mut rng).unwrap();
+    let request = CoProofRequest::try_from_bytes(&request.to_bytes()).unwrap();
+    let response = server_share
+        .respond(&request, ID, b"nonce", &mut rng)
+        .unwrap();
+    let response = CoProofResponse::try_from_bytes(&response.to_bytes()).unwrap();
+    let proof = response.finish(&secrets, pk, ID, b"nonce").unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+
+    assert_eq!(
+        CoProofResponse::try_from_bytes(&[0u8; 96]).unwrap_err(),
+        Error::InvalidLength
+    );
+    assert_eq!(
+        ServerTokenShare::try_from_bytes(&[0xffu8; ServerTokenShare::BYTES]).unwrap_err(),
+        Error::InvalidEncoding
+    );
+}
@@ -0,0 +1,170 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{
+    Complaint, DkgOutput, DkgParticipant, DkgRound, Justification, PartialToken, Round1Broadcast,
+    Round1Share,
+};
+
+const THRESHOLD: usize = 3;
+const LIMIT: usize = 5;
+
+fn participants(rng: &mut MockRng) -> Vec<DkgParticipant> {
+    (1..=LIMIT as u16)
+        .map(|i| DkgParticipant::new(i, THRESHOLD, LIMIT, &mut *rng).unwrap())
+        .collect()
+}
+
+fn round1(
+    participants: &mut [DkgParticipant],
+    rng: &mut MockRng,
+) -> (Vec<Round1Broadcast>, Vec<Round1Share>) {
+    let mut broadcasts = Vec::new();
+    let mut shares = Vec::new();
+    for p in participants.iter_mut() {
+        let (b, s) = p.round1(&mut *rng).unwrap();
+        broadcasts.push(b);
+        shares.extend(s);
+    }
+    (broadcasts, shares)
+}
+
+fn remaining_rounds(
+    participants: &mut [DkgParticipant],
+    broadcasts: &[Round1Broadcast],
+    shares: &[Round1Share],
+    answer: impl Fn(u16) -> bool,
+) -> Vec<Option<DkgOutput>> {
+    let complaints: Vec<Complaint> = participants
+        .iter_mut()
+        .flat_map(|p| p.round2(broadcasts, shares).unwrap())
+        .collect();
+    let justifications: Vec<Justification> = participants
+        .iter_mut()
+        .flat_map(|p| {
+            let answers = p.round3(&complaints).unwrap();
+            if answer(p.identifier()) {
+                answers
+            } else {
+                Vec::new()
+            }
+        })
+        .collect();
+    participants
+        .iter_mut()
+        .map(|p| p.finalize(&justifications))
+        .collect()
+}
+
+fn check_outputs(outputs: &[&DkgOutput], qualified: &[u16]) {
+    let pk = outputs[0].public_key;
+    for output in outputs {
+        assert_eq!(output.public_key, pk);
+        assert_eq!(output.qualified, qualified);
+        assert_eq!(output.public_key_shares, outputs[0].public_key_shares);
+    }
+
+    let partials: Vec<PartialToken> = outputs
+        .iter()
+        .map(|o| {
+            let partial = o.secret_key_share.sign(ID).unwrap();
+            let pk_share = &o.public_key_shares[o.secret_key_share.identifier() as usize - 1];
+            assert_eq!(partial.verify(pk_share, ID).unwrap_u8(), 1u8);
+            partial
+        })
+        .collect();
+    let token = PartialToken::combine(&partials[..THRESHOLD]).unwrap();
+    assert_eq!(token.verify(pk, ID).unwrap_u8(), 1u8);
+    let token = PartialToken::combine(&partials[partials.len() - THRESHOLD..]).unwrap();
+    assert_eq!(token.verify(pk, ID).unwrap_u8(), 1u8);
+}
+
+#[test]
+fn dkg_works() {
+    let mut rng = MockRng::new();
+    let mut participants = participants(&mut rng);
+    let (broadcasts, shares) = round1(&mut participants, &mut rng);
+    assert_eq!(shares.len(), LIMIT * (LIMIT - 1));
+
+    let outputs = remaining_rounds(&mut participants, &broadcasts, &shares, |_| true);
+    let outputs: Vec<&DkgOutput> = outputs.iter().map(|o| o.as_ref().unwrap()).collect();
+    check_outputs(&outputs, &[1, 2, 3, 4, 5]);
+    assert!(participants.iter().all(|p| p.round() == DkgRound::Complete));
+}
+
+#[test]
+fn dkg_rounds_out_of_order() {
+    let mut rng = MockRng::new();
+    let mut participant = DkgParticipant::new(1, THRESHOLD, LIMIT, &mut rng).unwrap();
+    assert!(participant.round2(&[], &[]).is_none());
+    assert!(participant.round3(&[]).is_none());
+    assert!(participant.finalize(&[]).is_none());
+    assert!(participant.round1(&mut rng).is_some());
+    assert!(participant.round1(&mut rng).is_none());
+
+    assert!(DkgParticipant::new(0, THRESHOLD, LIMIT, &mut rng).is_none());
+    assert!(DkgParticipant::new(6, THRESHOLD, LIMIT, &mut rng).is_none());
+    assert!(DkgParticipant::new(1, 1, LIMIT, &mut rng).is_none());
+}
+
+#[test]
+fn dkg_justified_complaint() {
+    let mut rng = MockRng::new();
+    let mut participants = participants(&mut rng);
+    let (broadcasts, mut shares) = round1(&mut participants, &mut rng);
+
+    // Share from 2 to 4 is lost in transit
+    shares.retain(|s| !(s.sender() == 2 && s.receiver() == 4));
+
+    let outputs = remaining_rounds(&mut participants, &broadcasts, &shares, |_| true);
+    let outputs: Vec<&DkgOutput> = outputs.iter().map(|o| o.as_ref().unwrap()).collect();
+    check_outputs(&outputs, &[1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn dkg_disqualify_cheater() {
+    let mut rng = MockRng::new();
+    let mut participants = participants(&mut rng);
+    let (mut broadcasts, mut shares) = round1(&mut participants, &mut rng);
+
+    // 3 sends a bad share to 1 and refuses to answer the complaint
+    let tampered = shares
+        .iter()
+        .position(|s| s.sender() == 3 && s.receiver() == 1)
+        .unwrap();
+    let replacement = shares
+        .iter()
+        .find(|s| s.sender() == 3 && s.receiver() == 2)
+        .unwrap()
+        .clone();
+    shares[tampered] = replacement;
+
+    // 5 broadcasts the wrong number of commitments
+    let mut short = DkgParticipant::new(5, THRESHOLD - 1, LIMIT, &mut rng).unwrap();
+    broadcasts[4] = short.round1(&mut rng).unwrap().0;
+
+    let outputs = remaining_rounds(&mut participants, &broadcasts, &shares, |i| i != 3);
+    assert!(outputs[2].is_none());
+    let outputs: Vec<&DkgOutput> = [0, 1, 3]
+        .iter()
+        .map(|i| outputs[*i].as_ref().unwrap())
+        .collect();
+    check_outputs(&outputs, &[1, 2, 4]);
+    assert_eq!(participants[0].disqualified(), vec![3, 5]);
+}
+
+#[test]
+fn dkg_too_few_qualified() {
+    let mut rng = MockRng::new();
+    let mut participants = participants(&mut rng);
+    let (mut broadcasts, shares) = round1(&mut participants, &mut rng);
+    broadcasts.truncate(1);
+
+    let outputs = remaining_rounds(&mut participants, &broadcasts, &shares, |_| true);
+    assert!(outputs.iter().all(|o| o.is_none()));
+}
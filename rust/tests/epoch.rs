@@ -0,0 +1,136 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Epoch, EpochProof, Error, Proof, PublicKey, SecretKey, Token};
+use rand_core::RngCore;
+
+const NOT_BEFORE: u64 = 1_700_000_000;
+const NOT_AFTER: u64 = 1_700_086_400;
+
+#[test]
+fn epoch_token_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let epoch = Epoch::new(NOT_BEFORE, NOT_AFTER).unwrap();
+    assert!(Epoch::new(NOT_AFTER, NOT_BEFORE).is_none());
+
+    let token = sk.sign_with_epoch(ID, epoch).unwrap();
+    assert_eq!(token.verify_with_epoch(pk, ID, epoch).unwrap_u8(), 1u8);
+    // not valid as a plain token or for another window
+    assert_eq!(token.verify(pk, ID).unwrap_u8(), 0u8);
+    let other = Epoch::new(NOT_BEFORE, NOT_AFTER + 1).unwrap();
+    assert_eq!(token.verify_with_epoch(pk, ID, other).unwrap_u8(), 0u8);
+    assert_ne!(token, Token::new(&sk, ID).unwrap());
+}
+
+#[test]
+fn epoch_proof_checks_time() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let epoch = Epoch::new(NOT_BEFORE, NOT_AFTER).unwrap();
+    let token = sk.sign_with_epoch(ID, epoch).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded_token = token - &blinding;
+    let mut nonce = [0u8; 16];
+    rng.fill_bytes(&mut nonce);
+
+    let proof = EpochProof::new(&blinded_token, &[blinding], ID, epoch, nonce, &mut rng).unwrap();
+    assert_eq!(proof.epoch(), epoch);
+    assert_eq!(proof.open(pk, ID, nonce, NOT_BEFORE, 0).unwrap_u8(), 1u8);
+    assert_eq!(proof.open(pk, ID, nonce, NOT_AFTER, 0).unwrap_u8(), 1u8);
+    assert_eq!(
+        proof.open(pk, ID, nonce, NOT_BEFORE - 1, 0).unwrap_u8(),
+        0u8
+    );
+    assert_eq!(proof.open(pk, ID, nonce, NOT_AFTER + 1, 0).unwrap_u8(), 0u8);
+    // clock skew
+    assert_eq!(
+        proof.open(pk, ID, nonce, NOT_BEFORE - 30, 30).unwrap_u8(),
+        1u8
+    );
+    assert_eq!(
+        proof.open(pk, ID, nonce, NOT_AFTER + 30, 30).unwrap_u8(),
+        1u8
+    );
+    assert_eq!(
+        proof.open(pk, ID, nonce, NOT_AFTER + 31, 30).unwrap_u8(),
+        0u8
+    );
+
+    assert_eq!(proof.try_open(pk, ID, nonce, NOT_BEFORE, 0), Ok(()));
+    assert_eq!(
+        proof.try_open(pk, ID, nonce, NOT_AFTER + 1, 0),
+        Err(Error::Expired)
+    );
+    assert_eq!(
+        proof.try_open(pk, b"wrong id", nonce, NOT_BEFORE, 0),
+        Err(Error::VerificationFailed)
+    );
+
+    // a widened window does not verify
+    let mut bytes = proof.to_bytes();
+    bytes[Proof::BYTES + 8..].copy_from_slice(&u64::MAX.to_be_bytes());
+    let widened = EpochProof::from_bytes(&bytes).unwrap();
+    assert_eq!(widened.open(pk, ID, nonce, NOT_BEFORE, 0).unwrap_u8(), 0u8);
+
+    // a plain token cannot be used
+    let plain = sk.sign(ID).unwrap();
+    let proof = EpochProof::new(&plain, &[], ID, epoch, nonce, &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, nonce, NOT_BEFORE, 0).unwrap_u8(), 0u8);
+}
+
+#[test]
+fn epoch_proof_bytes() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let epoch = Epoch::new(NOT_BEFORE, NOT_AFTER).unwrap();
+    let token = sk.sign_with_epoch(ID, epoch).unwrap();
+    let proof = EpochProof::new(&token, &[], ID, epoch, b"nonce", &mut rng).unwrap();
+
+    let bytes = proof.to_bytes();
+    let proof2 = EpochProof::try_from_bytes(&bytes).unwrap();
+    assert_eq!(proof2.to_bytes(), bytes);
+
+    let mut bytes = epoch.to_bytes();
+    bytes.swap(0, 8);
+    bytes[0] = 0xff;
+    assert_eq!(Epoch::from_bytes(&bytes).is_none().unwrap_u8(), 1u8);
+}
+
+#[test]
+fn epoch_vectors() {
+    let vectors: serde_json::Value =
+        serde_json::from_str(include_str!("../../test_vectors/epoch.json")).unwrap();
+    for v in vectors.as_array().unwrap() {
+        let sk_bytes = hex::decode(v["sk"].as_str().unwrap()).unwrap();
+        let sk = SecretKey::from_bytes(&sk_bytes.try_into().unwrap()).unwrap();
+        let id = hex::decode(v["id"].as_str().unwrap()).unwrap();
+        let epoch = Epoch::new(
+            v["not_before"].as_u64().unwrap(),
+            v["not_after"].as_u64().unwrap(),
+        )
+        .unwrap();
+
+        let digest = epoch.digest(&id).unwrap().to_bytes();
+        assert_eq!(hex::encode(&digest[..32]), v["m"].as_str().unwrap());
+        assert_eq!(hex::encode(&digest[32..64]), v["m'"].as_str().unwrap());
+        assert_eq!(hex::encode(&digest[64..]), v["u"].as_str().unwrap());
+
+        let message = hex::decode(v["message"].as_str().unwrap()).unwrap();
+        let token = sk.sign_with_epoch(&id, epoch).unwrap();
+        assert_eq!(token, sk.sign(&message).unwrap());
+        assert_eq!(hex::encode(token.to_bytes()), v["token"].as_str().unwrap());
+        assert_eq!(
+            token
+                .verify_with_epoch(PublicKey::from(&sk), &id, epoch)
+                .unwrap_u8(),
+            1u8
+        );
+    }
+}
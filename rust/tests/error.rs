@@ -0,0 +1,127 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, IdDigest, PreparedPublicKey, Proof, PublicKey, SecretKey, Token};
+
+#[test]
+fn try_token_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = Token::try_new(&sk, ID).unwrap();
+    assert_eq!(Some(token.clone()), Token::new(&sk, ID));
+
+    assert_eq!(token.try_verify(pk, ID), Ok(()));
+    assert_eq!(
+        token.try_verify(pk, b"wrong id"),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(
+        token.try_verify(PublicKey::default(), ID),
+        Err(Error::InvalidPublicKey)
+    );
+    assert_eq!(
+        Token::default().try_verify(pk, ID),
+        Err(Error::IdentityPoint)
+    );
+    assert_eq!(
+        Token::try_new(&SecretKey::default(), ID),
+        Err(Error::IdentityPoint)
+    );
+}
+
+#[test]
+fn try_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded_token = token.clone() - &blinding;
+
+    let proof = Proof::try_new(&blinded_token, &[blinding], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.try_open(pk, ID, b"nonce"), Ok(()));
+    assert_eq!(
+        proof.try_open(pk, ID, b"wrong nonce"),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(
+        proof.try_open(PublicKey::default(), ID, b"nonce"),
+        Err(Error::InvalidPublicKey)
+    );
+    assert_eq!(
+        Proof::default().try_open(pk, ID, b"nonce"),
+        Err(Error::IdentityPoint)
+    );
+
+    // Blindings that cancel the token, flipping the sign bit negates the point
+    let mut bytes = token.to_bytes();
+    bytes[0] ^= 0x20;
+    let cancel = Blinding::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        Proof::try_new(&token, &[cancel], ID, b"nonce", &mut rng).unwrap_err(),
+        Error::IdentityPoint
+    );
+    assert!(Proof::new(&token, &[cancel], ID, b"nonce", &mut rng).is_none());
+
+    assert_eq!(
+        PreparedPublicKey::try_new(PublicKey::default()).unwrap_err(),
+        Error::InvalidPublicKey
+    );
+}
+
+#[test]
+fn try_from_bytes_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let proof = Proof::new(&token, &[], ID, b"nonce", &mut rng).unwrap();
+
+    assert_eq!(SecretKey::try_from_bytes(&sk.to_bytes()), Ok(sk.clone()));
+    assert_eq!(PublicKey::try_from_bytes(&pk.to_bytes()), Ok(pk));
+    assert_eq!(Token::try_from_bytes(&token.to_bytes()), Ok(token.clone()));
+    assert_eq!(
+        Proof::try_from_bytes(&proof.to_bytes()).unwrap().to_bytes(),
+        proof.to_bytes()
+    );
+    let digest = IdDigest::new(ID).unwrap();
+    assert_eq!(IdDigest::try_from_bytes(&digest.to_bytes()), Ok(digest));
+
+    assert_eq!(
+        Token::try_from_bytes(&token.to_bytes()[1..]),
+        Err(Error::InvalidLength)
+    );
+    assert_eq!(
+        Blinding::try_from_bytes(&[0xffu8; Blinding::BYTES]).unwrap_err(),
+        Error::InvalidEncoding
+    );
+    assert_eq!(
+        SecretKey::try_from_bytes(&[0xffu8; SecretKey::BYTES]),
+        Err(Error::InvalidEncoding)
+    );
+}
+
+#[test]
+fn error_codes_are_stable() {
+    let errors = [
+        Error::InvalidEncoding,
+        Error::InvalidLength,
+        Error::InvalidPublicKey,
+        Error::IdentityPoint,
+        Error::ZeroHash,
+        Error::VerificationFailed,
+        Error::InvalidParameters,
+        Error::Revoked,
+        Error::Expired,
+        Error::Replayed,
+        Error::UnknownKey,
+    ];
+    for (i, e) in errors.iter().enumerate() {
+        assert_eq!(e.code(), i as i32 + 1);
+    }
+}
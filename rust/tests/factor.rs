@@ -0,0 +1,149 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, FactorInfo, FactorKind, FactorSet, Proof, PublicKey, SecretKey};
+
+#[test]
+fn factor_set_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+
+    let mut factors = FactorSet::new();
+    factors.add_pin("unlock", b"1234").unwrap();
+    factors.add_key_file("device", b"key file").unwrap();
+    factors.add_env_secret("env", b"OBERON_SECRET").unwrap();
+    factors
+        .add(FactorKind::Custom, "hsm", Blinding::new(b"hsm key"))
+        .unwrap();
+    assert_eq!(factors.len(), 4);
+
+    // same as subtracting each blinding
+    let blinded_token = factors.apply(&token);
+    let expected = token.clone()
+        - Blinding::new(b"1234")
+        - Blinding::new(b"key file")
+        - Blinding::new(b"OBERON_SECRET")
+        - Blinding::new(b"hsm key");
+    assert_eq!(blinded_token, expected);
+
+    let proof = Proof::new(&blinded_token, factors.blindings(), ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+
+    // the factors are derived again from the user each time
+    let mut wrong = FactorSet::new();
+    wrong.add_pin("unlock", b"1235").unwrap();
+    wrong.add_key_file("device", b"key file").unwrap();
+    wrong.add_env_secret("env", b"OBERON_SECRET").unwrap();
+    wrong.add_custom("hsm", b"hsm key").unwrap();
+    let proof = Proof::new(&blinded_token, wrong.blindings(), ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 0u8);
+
+    let info = factors.remove("env").unwrap();
+    assert_eq!(info.kind(), FactorKind::EnvSecret);
+    assert_eq!(info.label(), "env");
+    assert!(factors.get("env").is_none());
+    assert_eq!(factors.get("hsm").unwrap().kind(), FactorKind::Custom);
+    assert_eq!(factors.len(), 3);
+    assert!(factors.remove("env").is_none());
+}
+
+#[test]
+fn factor_set_labels() {
+    let mut factors = FactorSet::new();
+    factors.add_pin("unlock", b"1234").unwrap();
+    assert_eq!(
+        factors.add_pin("unlock", b"5678").unwrap_err(),
+        Error::InvalidParameters
+    );
+    assert_eq!(
+        factors.add_pin("", b"1234").unwrap_err(),
+        Error::InvalidParameters
+    );
+    assert_eq!(
+        factors
+            .add_pin("a label that is longer than 32 bytes", b"1234")
+            .unwrap_err(),
+        Error::InvalidParameters
+    );
+    assert_eq!(factors.len(), 1);
+}
+
+#[test]
+fn factor_set_metadata() {
+    let mut factors = FactorSet::new();
+    factors.add_pin("unlock", b"1234").unwrap();
+    factors.add_key_file("device", b"key file").unwrap();
+
+    let metadata = serde_json::to_string(factors.metadata()).unwrap();
+    assert!(!metadata.contains("1234"));
+    let metadata: Vec<FactorInfo> = serde_json::from_str(&metadata).unwrap();
+    assert_eq!(metadata, factors.metadata());
+    assert_eq!(metadata[0].label(), "unlock");
+    assert_eq!(metadata[1].kind(), FactorKind::KeyFile);
+
+    // debug output leaves out the factors
+    let debug = format!("{:?}", factors);
+    assert!(debug.contains("unlock"));
+    assert!(!debug.contains(&format!("{:?}", Blinding::new(b"1234"))));
+}
+
+#[cfg(not(any(feature = "alloc", feature = "std")))]
+#[test]
+fn factor_set_capacity() {
+    let mut factors = FactorSet::new();
+    let labels = ["a", "b", "c", "d", "e", "f", "g", "h"];
+    for label in labels {
+        factors.add_custom(label, label.as_bytes()).unwrap();
+    }
+    assert_eq!(
+        factors.add_custom("i", b"i").unwrap_err(),
+        Error::InvalidParameters
+    );
+    factors.remove("a").unwrap();
+    factors.add_custom("i", b"i").unwrap();
+    assert_eq!(factors.metadata()[0].label(), "b");
+    assert_eq!(factors.len(), FactorSet::MAX_FACTORS);
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[test]
+fn factor_set_grows() {
+    let mut factors = FactorSet::new();
+    for i in 0..FactorSet::MAX_FACTORS * 2 {
+        factors
+            .add_custom(&format!("factor {}", i), &i.to_be_bytes())
+            .unwrap();
+    }
+    assert_eq!(factors.len(), FactorSet::MAX_FACTORS * 2);
+}
+
+#[cfg(feature = "password")]
+#[test]
+fn factor_set_password() {
+    use oberon::PasswordParams;
+
+    let params = PasswordParams::new(64, 1, 1).unwrap();
+    let mut factors = FactorSet::new();
+    factors
+        .add_password("login", "correct horse", ID, &params)
+        .unwrap();
+    let info = factors.get("login").unwrap();
+    assert_eq!(info.kind(), FactorKind::Password);
+    assert_eq!(info.params(), Some(params));
+    assert_eq!(
+        factors.blindings()[0].to_bytes(),
+        Blinding::from_password("correct horse", ID, &params)
+            .unwrap()
+            .to_bytes()
+    );
+
+    let metadata = serde_json::to_string(factors.metadata()).unwrap();
+    let metadata: Vec<FactorInfo> = serde_json::from_str(&metadata).unwrap();
+    assert_eq!(metadata[0].params(), Some(params));
+}
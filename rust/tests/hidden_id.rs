@@ -0,0 +1,92 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::inner_types::{Field, Scalar};
+use oberon::{Blinding, Error, HiddenIdProof, IdCommitment, IdDigest, PublicKey, SecretKey};
+
+#[test]
+fn hidden_id_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded = &token - &blinding;
+    let blinder = Scalar::random(&mut rng);
+
+    let proof =
+        HiddenIdProof::new(&blinded, &[blinding], ID, pk, blinder, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(proof.try_open(pk, b"nonce"), Ok(()));
+    assert_eq!(proof.commitment(), IdCommitment::new(ID, blinder).unwrap());
+    assert_ne!(
+        proof.commitment(),
+        IdCommitment::new(b"another identity", blinder).unwrap()
+    );
+
+    assert_eq!(
+        proof.try_open(pk, b"other nonce"),
+        Err(Error::VerificationFailed)
+    );
+    let pk2 = PublicKey::from(&SecretKey::new(&mut rng));
+    assert_eq!(proof.open(pk2, b"nonce").unwrap_u8(), 0u8);
+    assert_eq!(
+        proof.try_open(PublicKey::default(), b"nonce"),
+        Err(Error::InvalidPublicKey)
+    );
+
+    // the same id with a fresh blinder gives an unlinkable commitment
+    let proof2 = HiddenIdProof::new(
+        &token,
+        &[],
+        ID,
+        pk,
+        Scalar::random(&mut rng),
+        b"nonce",
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(proof2.open(pk, b"nonce").unwrap_u8(), 1u8);
+    assert_ne!(proof.commitment(), proof2.commitment());
+
+    // a token for another id
+    let other = sk.sign(b"another identity").unwrap();
+    let proof = HiddenIdProof::new(&other, &[], ID, pk, blinder, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, b"nonce").unwrap_u8(), 0u8);
+}
+
+#[test]
+fn hidden_id_proof_bytes() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let blinder = Scalar::random(&mut rng);
+    let proof = HiddenIdProof::new(&token, &[], ID, pk, blinder, b"nonce", &mut rng).unwrap();
+
+    let bytes = proof.to_bytes();
+    let proof2 = HiddenIdProof::try_from_bytes(&bytes).unwrap();
+    assert_eq!(proof2.to_bytes(), bytes);
+    assert_eq!(proof2.open(pk, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(
+        HiddenIdProof::try_from_bytes(&bytes[1..]).unwrap_err(),
+        Error::InvalidLength
+    );
+
+    // the commitment opens with the generators
+    let (g, j, h) = IdCommitment::generators();
+    let digest = IdDigest::new(ID).unwrap();
+    let commitment = proof.commitment();
+    assert_eq!(
+        commitment.point(),
+        g * digest.id_scalar() + j * digest.hashed_id_scalar() + h * blinder
+    );
+    assert_eq!(
+        IdCommitment::try_from_bytes(&commitment.to_bytes()).unwrap(),
+        commitment
+    );
+}
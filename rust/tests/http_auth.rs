@@ -0,0 +1,202 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(feature = "http")]
+mod common;
+
+use common::{MockRng, ID};
+use http::{header, request::Parts, Method, Request};
+use oberon::{
+    Blinding, Error, HttpAuthorization, HttpComponent, PublicKey, SecretKey, HTTP_AUTH_SCHEME,
+};
+
+const BODY: &[u8] = b"{\"amount\":100}";
+const ALL: [HttpComponent; 3] = [
+    HttpComponent::Method,
+    HttpComponent::TargetUri,
+    HttpComponent::ContentDigest,
+];
+
+fn client_request() -> Parts {
+    let (parts, _) = Request::post("https://API.example.com/v1/payments?currency=usd")
+        .header("content-digest", HttpAuthorization::content_digest(BODY))
+        .body(())
+        .unwrap()
+        .into_parts();
+    parts
+}
+
+// what a server sees for the same request
+fn server_request(auth: &HttpAuthorization) -> Parts {
+    let (parts, _) = Request::post("/v1/payments?currency=usd")
+        .header(header::HOST, "api.example.com")
+        .header("content-digest", HttpAuthorization::content_digest(BODY))
+        .header(header::AUTHORIZATION, auth.to_header_value())
+        .body(())
+        .unwrap()
+        .into_parts();
+    parts
+}
+
+#[test]
+fn http_auth_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded_token = token - &blinding;
+
+    let parts = client_request();
+    let auth = HttpAuthorization::new(
+        &blinded_token,
+        &[blinding],
+        ID,
+        &parts,
+        &ALL,
+        b"nonce",
+        &mut rng,
+    )
+    .unwrap()
+    .with_key_id(pk.key_id());
+    assert!(auth.to_string().starts_with(HTTP_AUTH_SCHEME));
+
+    let server = server_request(&auth);
+    let parsed = HttpAuthorization::from_headers(&server.headers).unwrap();
+    assert_eq!(parsed.id(), ID);
+    assert_eq!(parsed.key_id(), Some(pk.key_id()));
+    assert_eq!(parsed.nonce(), b"nonce");
+    assert_eq!(parsed.components(), &ALL);
+    assert_eq!(parsed.proof().to_bytes(), auth.proof().to_bytes());
+    assert_eq!(parsed.to_string(), auth.to_string());
+    assert_eq!(parsed.verify(&server, pk, &ALL).unwrap_u8(), 1u8);
+    assert_eq!(parsed.try_verify(&parts, pk, &ALL), Ok(()));
+    assert_eq!(
+        HttpAuthorization::check_content_digest(&server, BODY),
+        Ok(())
+    );
+    assert_eq!(
+        HttpAuthorization::check_content_digest(&server, b"{\"amount\":1000}"),
+        Err(Error::VerificationFailed)
+    );
+}
+
+#[test]
+fn http_auth_binds_components() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let parts = client_request();
+    let auth = HttpAuthorization::new(&token, &[], ID, &parts, &ALL, b"nonce", &mut rng).unwrap();
+
+    let mut other = server_request(&auth);
+    other.method = Method::PUT;
+    assert_eq!(
+        auth.try_verify(&other, pk, &ALL),
+        Err(Error::VerificationFailed)
+    );
+
+    let mut other = server_request(&auth);
+    other.uri = "/v1/payments?currency=eur".parse().unwrap();
+    assert_eq!(
+        auth.try_verify(&other, pk, &ALL),
+        Err(Error::VerificationFailed)
+    );
+
+    let mut other = server_request(&auth);
+    other
+        .headers
+        .insert(header::HOST, "evil.example.com".parse().unwrap());
+    assert_eq!(
+        auth.try_verify(&other, pk, &ALL),
+        Err(Error::VerificationFailed)
+    );
+
+    let mut other = server_request(&auth);
+    other.headers.insert(
+        "content-digest",
+        HttpAuthorization::content_digest(b"{\"amount\":1000}"),
+    );
+    assert_eq!(
+        auth.try_verify(&other, pk, &ALL),
+        Err(Error::VerificationFailed)
+    );
+
+    let mut other = server_request(&auth);
+    other.headers.remove("content-digest");
+    assert_eq!(
+        auth.try_verify(&other, pk, &ALL),
+        Err(Error::InvalidParameters)
+    );
+
+    // the verifier can insist on components being covered
+    let partial =
+        HttpAuthorization::new(&token, &[], ID, &parts, &ALL[..2], b"nonce", &mut rng).unwrap();
+    let server = server_request(&partial);
+    assert_eq!(partial.try_verify(&server, pk, &ALL[..2]), Ok(()));
+    assert_eq!(
+        partial.try_verify(&server, pk, &ALL),
+        Err(Error::InvalidParameters)
+    );
+
+    assert_eq!(
+        HttpComponent::canonicalize(&[HttpComponent::Method, HttpComponent::Method], &parts)
+            .unwrap_err(),
+        Error::InvalidParameters
+    );
+}
+
+#[test]
+fn http_auth_canonical_form() {
+    let parts = client_request();
+    let (message, associated_data) = HttpComponent::canonicalize(&ALL, &parts).unwrap();
+    assert_eq!(
+        message,
+        b"\"@signature-params\": (\"@method\" \"@target-uri\" \"content-digest\")"
+    );
+    assert_eq!(associated_data[0], b"\"@method\": POST");
+    assert_eq!(
+        associated_data[1],
+        b"\"@target-uri\": api.example.com/v1/payments?currency=usd"
+    );
+    assert_eq!(
+        associated_data[2],
+        b"\"content-digest\": sha-256=:TUu+Wcaq0iRCzeGZpqil8DRAX814+1qBwk7ySd4cRfE=:"
+    );
+    for c in ALL {
+        assert_eq!(HttpComponent::from_name(c.name()), Some(c));
+    }
+}
+
+#[test]
+fn http_auth_parse_errors() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let token = sk.sign(ID).unwrap();
+    let parts = client_request();
+    let auth = HttpAuthorization::new(&token, &[], ID, &parts, &ALL, b"nonce", &mut rng).unwrap();
+    let value = auth.to_string();
+
+    // the scheme is case insensitive
+    assert!(HttpAuthorization::parse(&value.replacen("Oberon", "oberon", 1)).is_ok());
+    for bad in [
+        value.replacen("Oberon", "Bearer", 1),
+        value.replacen("Oberon ", "Oberon", 1),
+        value.replacen("nonce=", "salt=", 1),
+        value.replacen("nonce=", "id=", 1),
+        value.replacen("components=\"@method", "components=\"@path", 1),
+        format!("{}, kid=\"AAAA\"", value),
+        value.replacen("id=\"", "id=\"!", 1),
+        value.replacen("proof=\"", "proof=\"AAAA", 1),
+        value[..value.find(", proof").unwrap()].to_string(),
+    ] {
+        assert!(HttpAuthorization::parse(&bad).is_err(), "{}", bad);
+    }
+    let (empty, _) = Request::get("/").body(()).unwrap().into_parts();
+    assert_eq!(
+        HttpAuthorization::from_headers(&empty.headers).unwrap_err(),
+        Error::InvalidParameters
+    );
+}
@@ -0,0 +1,123 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, IdDigest, PreparedPublicKey, Proof, PublicKey, SecretKey};
+
+#[test]
+fn id_digest_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let digest = IdDigest::new(ID).unwrap();
+    let other = IdDigest::new(b"wrong id").unwrap();
+    assert_ne!(digest, other);
+
+    let token = sk.sign(ID).unwrap();
+    assert_eq!(token.verify_with_digest(pk, &digest).unwrap_u8(), 1u8);
+    assert_eq!(token.verify_with_digest(pk, &other).unwrap_u8(), 0u8);
+
+    let blinding = Blinding::new(b"1234");
+    let blinded_token = token - &blinding;
+    let proof = Proof::new(&blinded_token, &[blinding], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(
+        proof.open_with_digest(pk, &digest, b"nonce").unwrap_u8(),
+        1u8
+    );
+    assert_eq!(
+        proof.open_with_digest(pk, &other, b"nonce").unwrap_u8(),
+        0u8
+    );
+    assert_eq!(
+        proof
+            .open_with_digest(pk, &digest, b"wrong nonce")
+            .unwrap_u8(),
+        0u8
+    );
+
+    let prepared = PreparedPublicKey::new(pk).unwrap();
+    assert_eq!(
+        prepared
+            .open_with_digest(&proof, &digest, b"nonce")
+            .unwrap_u8(),
+        1u8
+    );
+    assert_eq!(
+        prepared
+            .verify_token_with_digest(&blinded_token, &digest)
+            .unwrap_u8(),
+        0u8
+    );
+}
+
+#[test]
+fn id_digest_serialization() {
+    let digest = IdDigest::new(ID).unwrap();
+    let bytes = digest.to_bytes();
+    assert_eq!(IdDigest::from_bytes(&bytes).unwrap(), digest);
+
+    // m' must be derived from m
+    let mut bad = bytes;
+    bad[..32].copy_from_slice(&IdDigest::new(b"wrong id").unwrap().to_bytes()[..32]);
+    assert_eq!(IdDigest::from_bytes(&bad).is_none().unwrap_u8(), 1u8);
+    assert_eq!(
+        IdDigest::from_bytes_unchecked(&bad).is_none().unwrap_u8(),
+        1u8
+    );
+
+    // A must be derived from m'
+    let mut bad = bytes;
+    bad[64..].copy_from_slice(&IdDigest::new(b"wrong id").unwrap().to_bytes()[64..]);
+    assert_eq!(IdDigest::from_bytes(&bad).is_none().unwrap_u8(), 1u8);
+
+    // trusted storage skips recomputing A
+    assert_eq!(IdDigest::from_bytes_unchecked(&bytes).unwrap(), digest);
+    assert_eq!(
+        IdDigest::from_bytes_unchecked(&bad).is_some().unwrap_u8(),
+        1u8
+    );
+
+    // serde checks the same way
+    let json = serde_json::to_string(&digest).unwrap();
+    assert_eq!(serde_json::from_str::<IdDigest>(&json).unwrap(), digest);
+    let mut tampered: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let other: serde_json::Value =
+        serde_json::to_value(IdDigest::new(b"wrong id").unwrap()).unwrap();
+    tampered["a"] = other["a"].clone();
+    assert!(serde_json::from_value::<IdDigest>(tampered).is_err());
+
+    assert_eq!(
+        IdDigest::from_bytes(&IdDigest::default().to_bytes())
+            .is_none()
+            .unwrap_u8(),
+        1u8
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn id_digest_cache() {
+    use oberon::IdDigestCache;
+
+    let mut cache = IdDigestCache::new(2);
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(b"alice"), IdDigest::new(b"alice"));
+    cache.get(b"bobby");
+    // alice is now the most recently used
+    cache.get(b"alice");
+    cache.get(b"carol");
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains(b"alice"));
+    assert!(!cache.contains(b"bobby"));
+    assert!(cache.contains(b"carol"));
+
+    cache.clear();
+    assert!(cache.is_empty());
+
+    let mut disabled = IdDigestCache::new(0);
+    assert_eq!(disabled.get(ID), IdDigest::new(ID));
+    assert!(disabled.is_empty());
+}
@@ -0,0 +1,83 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, IssuerHidingProof, PublicKey, SecretKey};
+
+#[test]
+fn issuer_hiding_proof_works() {
+    let mut rng = MockRng::new();
+    let sks: Vec<SecretKey> = (0..4).map(|_| SecretKey::new(&mut rng)).collect();
+    let keys: Vec<PublicKey> = sks.iter().map(PublicKey::from).collect();
+
+    for sk in &sks {
+        let token = sk.sign(ID).unwrap();
+        let proof = IssuerHidingProof::new(&token, &[], ID, &keys, b"nonce", &mut rng).unwrap();
+        assert_eq!(proof.keys(), keys.len());
+        assert_eq!(proof.open(&keys, ID, b"nonce").unwrap_u8(), 1u8);
+        assert_eq!(proof.try_open(&keys, ID, b"nonce"), Ok(()));
+        assert_eq!(proof.open(&keys, ID, b"other nonce").unwrap_u8(), 0u8);
+        assert_eq!(proof.open(&keys, b"wrong id", b"nonce").unwrap_u8(), 0u8);
+    }
+
+    // a single key works like a regular proof
+    let token = sks[0].sign(ID).unwrap();
+    let proof = IssuerHidingProof::new(&token, &[], ID, &keys[..1], b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(&keys[..1], ID, b"nonce").unwrap_u8(), 1u8);
+
+    let blinding = Blinding::new(b"1234");
+    let blinded = &token - &blinding;
+    let proof =
+        IssuerHidingProof::new(&blinded, &[blinding], ID, &keys, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(&keys, ID, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(
+        IssuerHidingProof::try_new(&blinded, &[], ID, &keys, b"nonce", &mut rng).unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let proof2: IssuerHidingProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(proof2.open(&keys, ID, b"nonce").unwrap_u8(), 1u8);
+}
+
+#[test]
+fn issuer_hiding_proof_rejects_other_keys() {
+    let mut rng = MockRng::new();
+    let sks: Vec<SecretKey> = (0..3).map(|_| SecretKey::new(&mut rng)).collect();
+    let keys: Vec<PublicKey> = sks.iter().map(PublicKey::from).collect();
+    let token = sks[2].sign(ID).unwrap();
+    let proof = IssuerHidingProof::new(&token, &[], ID, &keys, b"nonce", &mut rng).unwrap();
+
+    // the issuer is not in the verifier's list
+    let outsider = PublicKey::from(&SecretKey::new(&mut rng));
+    let others = [keys[0], keys[1], outsider];
+    assert_eq!(
+        proof.try_open(&others, ID, b"nonce"),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(
+        IssuerHidingProof::try_new(&token, &[], ID, &others, b"nonce", &mut rng).unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    // the list must match the one the proof was made for
+    assert_eq!(
+        proof.try_open(&keys[..2], ID, b"nonce"),
+        Err(Error::InvalidParameters)
+    );
+    let reordered = [keys[2], keys[0], keys[1]];
+    assert_eq!(proof.open(&reordered, ID, b"nonce").unwrap_u8(), 0u8);
+
+    assert_eq!(
+        proof.try_open(&[], ID, b"nonce"),
+        Err(Error::InvalidParameters)
+    );
+    assert_eq!(
+        proof.try_open(&[keys[0], keys[1], PublicKey::default()], ID, b"nonce"),
+        Err(Error::InvalidPublicKey)
+    );
+}
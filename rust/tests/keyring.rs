@@ -0,0 +1,131 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Error, KeyId, Proof, ProofEnvelope, PublicKey, SecretKey};
+
+#[test]
+fn key_id_is_stable() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let pk2 = PublicKey::from(&SecretKey::new(&mut rng));
+
+    assert_eq!(pk.key_id(), KeyId::from(&pk));
+    assert_eq!(
+        pk.key_id(),
+        PublicKey::try_from_bytes(&pk.to_bytes()).unwrap().key_id()
+    );
+    assert_ne!(pk.key_id(), pk2.key_id());
+    assert_eq!(
+        KeyId::try_from_bytes(&pk.key_id().to_bytes()).unwrap(),
+        pk.key_id()
+    );
+}
+
+#[test]
+fn envelope_bytes() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let proof = Proof::new(&token, &[], ID, b"nonce", &mut rng).unwrap();
+    let envelope = ProofEnvelope::new(proof, &pk);
+    assert_eq!(ProofEnvelope::BYTES, 129);
+    assert_eq!(envelope.version(), ProofEnvelope::VERSION);
+    assert_eq!(envelope.key_id(), pk.key_id());
+
+    let bytes = envelope.to_bytes();
+    let envelope2 = ProofEnvelope::try_from_bytes(&bytes).unwrap();
+    assert_eq!(envelope2.to_bytes(), bytes);
+    assert_eq!(envelope2.proof().open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+
+    let mut bytes = envelope.to_bytes();
+    bytes[0] = 2;
+    assert_eq!(
+        ProofEnvelope::try_from_bytes(&bytes).unwrap_err(),
+        Error::InvalidEncoding
+    );
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[test]
+fn keyring_rotation() {
+    use oberon::Keyring;
+
+    const T0: u64 = 1_600_000_000;
+    const T1: u64 = 1_700_000_000;
+    const GRACE: u64 = 3600;
+
+    let mut rng = MockRng::new();
+    let old_sk = SecretKey::new(&mut rng);
+    let new_sk = SecretKey::new(&mut rng);
+    let old_pk = PublicKey::from(&old_sk);
+    let new_pk = PublicKey::from(&new_sk);
+
+    let mut keyring = Keyring::new(GRACE);
+    let old_id = keyring.add(old_pk, T0);
+    assert_eq!(keyring.current(T1).unwrap().public_key(), old_pk);
+    let new_id = keyring.add(new_pk, T1);
+    assert_eq!(keyring.len(), 2);
+    assert_eq!(keyring.current(T1).unwrap().public_key(), new_pk);
+    assert_eq!(keyring.current(T1 - 1).unwrap().public_key(), old_pk);
+    keyring.retire(&old_id, T1).unwrap();
+    assert_eq!(keyring.get(&old_id).unwrap().retired_at(), Some(T1));
+
+    let nonce = b"nonce";
+    let old_token = old_sk.sign(ID).unwrap();
+    let proof = Proof::new(&old_token, &[], ID, nonce, &mut rng).unwrap();
+    let old_envelope = ProofEnvelope::new(proof, &old_pk);
+    let new_token = new_sk.sign(ID).unwrap();
+    let proof = Proof::new(&new_token, &[], ID, nonce, &mut rng).unwrap();
+    let new_envelope = ProofEnvelope::new(proof, &new_pk);
+
+    assert_eq!(keyring.try_open(&new_envelope, ID, nonce, T1), Ok(()));
+    assert_eq!(
+        keyring.try_open(&new_envelope, ID, nonce, T1 - 1),
+        Err(Error::Expired)
+    );
+    // retired key within and after the grace period
+    assert_eq!(
+        keyring.try_open(&old_envelope, ID, nonce, T1 + GRACE),
+        Ok(())
+    );
+    assert_eq!(
+        keyring.try_open(&old_envelope, ID, nonce, T1 + GRACE + 1),
+        Err(Error::Expired)
+    );
+    assert!(keyring.is_valid(&old_id, T1));
+    assert!(!keyring.is_valid(&old_id, T1 + GRACE + 1));
+
+    // the envelope names the wrong key
+    let mut bytes = new_envelope.to_bytes();
+    bytes[1..33].copy_from_slice(&old_id.to_bytes());
+    let wrong = ProofEnvelope::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        keyring.try_open(&wrong, ID, nonce, T1),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(keyring.open(&wrong, ID, nonce, T1).unwrap_u8(), 0u8);
+
+    let removed = keyring.remove(&new_id).unwrap();
+    assert_eq!(removed.public_key(), new_pk);
+    assert_eq!(
+        keyring.try_open(&new_envelope, ID, nonce, T1),
+        Err(Error::UnknownKey)
+    );
+    assert_eq!(keyring.retire(&new_id, T1), Err(Error::UnknownKey));
+
+    let json = serde_json::to_string(&keyring).unwrap();
+    let keyring2: Keyring = serde_json::from_str(&json).unwrap();
+    assert_eq!(keyring2.entries(), keyring.entries());
+    assert_eq!(keyring2.grace_period(), GRACE);
+
+    // a stored key id must match its public key
+    let mut value = serde_json::to_value(&keyring).unwrap();
+    value["entries"][0]["key_id"] = serde_json::to_value(new_id).unwrap();
+    assert!(serde_json::from_value::<Keyring>(value).is_err());
+}
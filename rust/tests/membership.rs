@@ -0,0 +1,76 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, MembershipProof, PublicKey, SecretKey};
+
+const GROUP: [&[u8]; 4] = [b"alice", ID, b"carol", b"dave"];
+
+#[test]
+fn membership_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    for id in GROUP {
+        let token = sk.sign(id).unwrap();
+        let proof = MembershipProof::new(&token, &[], id, &GROUP, pk, b"nonce", &mut rng).unwrap();
+        assert_eq!(proof.members(), GROUP.len());
+        assert_eq!(proof.open(pk, &GROUP, b"nonce").unwrap_u8(), 1u8);
+        assert_eq!(proof.try_open(pk, &GROUP, b"nonce"), Ok(()));
+        assert_eq!(proof.open(pk, &GROUP, b"other nonce").unwrap_u8(), 0u8);
+    }
+
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded = &token - blinding;
+    let proof =
+        MembershipProof::new(&blinded, &[blinding], ID, &GROUP, pk, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, &GROUP, b"nonce").unwrap_u8(), 1u8);
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let proof2: MembershipProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(proof2.open(pk, &GROUP, b"nonce").unwrap_u8(), 1u8);
+}
+
+#[test]
+fn membership_proof_rejects() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let proof = MembershipProof::new(&token, &[], ID, &GROUP, pk, b"nonce", &mut rng).unwrap();
+
+    // the id was removed from the group
+    let others: [&[u8]; 4] = [b"alice", b"bob", b"carol", b"dave"];
+    assert_eq!(
+        proof.try_open(pk, &others, b"nonce"),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(
+        proof.try_open(pk, &GROUP[..3], b"nonce"),
+        Err(Error::InvalidParameters)
+    );
+    let pk2 = PublicKey::from(&SecretKey::new(&mut rng));
+    assert_eq!(proof.open(pk2, &GROUP, b"nonce").unwrap_u8(), 0u8);
+    let empty: [&[u8]; 0] = [];
+    assert_eq!(
+        proof.try_open(pk, &empty, b"nonce"),
+        Err(Error::InvalidParameters)
+    );
+
+    // the holder's id must be in the group
+    assert_eq!(
+        MembershipProof::try_new(&token, &[], ID, &others, pk, b"nonce", &mut rng).unwrap_err(),
+        Error::InvalidParameters
+    );
+
+    // a token for another id in the group does not help
+    let outsider = sk.sign(b"mallory").unwrap();
+    let proof = MembershipProof::new(&outsider, &[], ID, &GROUP, pk, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, &GROUP, b"nonce").unwrap_u8(), 0u8);
+}
@@ -0,0 +1,134 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, Proof, PublicKey, SecretKey};
+use rand_core::RngCore;
+
+const MESSAGE: &[u8] = b"{\"amount\":100}";
+const AD: [&[u8]; 3] = [b"POST", b"/v1/payments", b"api.example.com"];
+
+#[test]
+fn message_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded_token = token - &blinding;
+    let mut nonce = [0u8; 16];
+    rng.fill_bytes(&mut nonce);
+
+    let proof = Proof::sign_message(
+        &blinded_token,
+        &[blinding],
+        ID,
+        MESSAGE,
+        &AD,
+        &nonce,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(
+        proof
+            .verify_message(pk, ID, MESSAGE, &AD, nonce)
+            .unwrap_u8(),
+        1u8
+    );
+    assert!(proof
+        .try_verify_message(pk, ID, MESSAGE, &AD, nonce)
+        .is_ok());
+    // the plain nonce is not enough to open it
+    assert_eq!(proof.open(pk, ID, nonce).unwrap_u8(), 0u8);
+    let binding = Proof::message_binding(MESSAGE, &AD, nonce);
+    assert_eq!(proof.open(pk, ID, binding).unwrap_u8(), 1u8);
+
+    assert_eq!(
+        proof
+            .verify_message(pk, ID, b"{\"amount\":1000}", &AD, nonce)
+            .unwrap_u8(),
+        0u8
+    );
+    assert_eq!(
+        proof
+            .verify_message(pk, ID, MESSAGE, &AD[..2], nonce)
+            .unwrap_u8(),
+        0u8
+    );
+    let other: [&[u8]; 3] = [b"GET", b"/v1/payments", b"api.example.com"];
+    assert_eq!(
+        proof
+            .verify_message(pk, ID, MESSAGE, &other, nonce)
+            .unwrap_u8(),
+        0u8
+    );
+    assert_eq!(
+        proof
+            .verify_message(pk, ID, MESSAGE, &AD, b"wrong nonce")
+            .unwrap_u8(),
+        0u8
+    );
+    assert_eq!(
+        proof
+            .try_verify_message(pk, b"wrong id", MESSAGE, &AD, nonce)
+            .unwrap_err(),
+        Error::VerificationFailed
+    );
+}
+
+#[test]
+fn message_binding_is_unambiguous() {
+    let nonce = b"nonce";
+    // moving bytes between fields changes the binding
+    assert_ne!(
+        Proof::message_binding(b"ab", &[b"c"], nonce),
+        Proof::message_binding(b"a", &[b"bc"], nonce)
+    );
+    assert_ne!(
+        Proof::message_binding(b"", &[b"ab"], nonce),
+        Proof::message_binding(b"", &[b"a", b"b"], nonce)
+    );
+    assert_ne!(
+        Proof::message_binding::<_, &[u8], _>(b"", &[], nonce),
+        Proof::message_binding(b"", &[b""], nonce)
+    );
+    assert_ne!(
+        Proof::message_binding::<_, &[u8], _>(b"nonce", &[], b""),
+        Proof::message_binding::<_, &[u8], _>(b"", &[], b"nonce")
+    );
+}
+
+#[test]
+fn message_vectors() {
+    let vectors: serde_json::Value =
+        serde_json::from_str(include_str!("../../test_vectors/message.json")).unwrap();
+    for v in vectors.as_array().unwrap() {
+        let sk_bytes = hex::decode(v["sk"].as_str().unwrap()).unwrap();
+        let sk = SecretKey::from_bytes(&sk_bytes.try_into().unwrap()).unwrap();
+        let pk = PublicKey::from(&sk);
+        let id = hex::decode(v["id"].as_str().unwrap()).unwrap();
+        let message = hex::decode(v["message"].as_str().unwrap()).unwrap();
+        let nonce = hex::decode(v["nonce"].as_str().unwrap()).unwrap();
+        let ad: Vec<Vec<u8>> = v["associated_data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| hex::decode(a.as_str().unwrap()).unwrap())
+            .collect();
+
+        let binding = Proof::message_binding(&message, &ad, &nonce);
+        assert_eq!(hex::encode(binding), v["binding"].as_str().unwrap());
+
+        let proof_bytes = hex::decode(v["proof"].as_str().unwrap()).unwrap();
+        let proof = Proof::from_bytes(&proof_bytes.try_into().unwrap()).unwrap();
+        assert_eq!(
+            proof
+                .verify_message(pk, &id, &message, &ad, &nonce)
+                .unwrap_u8(),
+            1u8
+        );
+    }
+}
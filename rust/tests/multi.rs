@@ -0,0 +1,103 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{
+    DisclosureProof, Error, MultiPublicKey, MultiSecretKey, MultiToken, Proof, PublicKey,
+    SecretKey, Token,
+};
+
+const ATTRIBUTES: [&[u8]; 4] = [ID, b"tenant-42", b"role:admin", b"expires:2030-01-01"];
+
+#[test]
+fn multi_token_works() {
+    let mut rng = MockRng::new();
+    let sk = MultiSecretKey::new(ATTRIBUTES.len(), &mut rng);
+    let pk = MultiPublicKey::from(&sk);
+    assert_eq!(pk.attributes(), 4);
+
+    let token = sk.sign(&ATTRIBUTES).unwrap();
+    assert_eq!(token.verify(&pk, &ATTRIBUTES).unwrap_u8(), 1u8);
+
+    let mut changed = ATTRIBUTES;
+    changed[2] = b"role:user";
+    assert_eq!(token.verify(&pk, &changed).unwrap_u8(), 0u8);
+    assert_eq!(token.verify(&pk, &ATTRIBUTES[..3]).unwrap_u8(), 0u8);
+    assert_eq!(sk.try_sign(&ATTRIBUTES[..3]), Err(Error::InvalidParameters));
+
+    let bytes = token.to_bytes();
+    assert_eq!(MultiToken::try_from_bytes(&bytes).unwrap(), token);
+}
+
+#[test]
+fn single_attribute_matches_token() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let msk = MultiSecretKey::from(&sk);
+    let mpk = MultiPublicKey::from(pk);
+    assert_eq!(MultiPublicKey::from(&msk), mpk);
+
+    let token = sk.sign(ID).unwrap();
+    let mtoken = msk.sign(&[ID]).unwrap();
+    assert_eq!(MultiToken::from(token.clone()), mtoken);
+    assert_eq!(mtoken.to_bytes().len(), Token::BYTES);
+    assert_eq!(mtoken.verify(&mpk, &[ID]).unwrap_u8(), 1u8);
+    assert_eq!(Proof::BYTES, 96);
+}
+
+#[test]
+fn selective_disclosure() {
+    let mut rng = MockRng::new();
+    let sk = MultiSecretKey::new(ATTRIBUTES.len(), &mut rng);
+    let pk = MultiPublicKey::from(&sk);
+    let token = sk.sign(&ATTRIBUTES).unwrap();
+    let nonce = b"disclosure nonce";
+
+    let proof = DisclosureProof::new(&token, &pk, &ATTRIBUTES, &[1, 2], nonce, &mut rng).unwrap();
+    let disclosed = [(1, ATTRIBUTES[1]), (2, ATTRIBUTES[2])];
+    assert_eq!(proof.open(&pk, &disclosed, nonce).unwrap_u8(), 1u8);
+    // order of the disclosed values does not matter
+    let reversed = [(2, ATTRIBUTES[2]), (1, ATTRIBUTES[1])];
+    assert_eq!(proof.open(&pk, &reversed, nonce).unwrap_u8(), 1u8);
+
+    let wrong = [(1, ATTRIBUTES[1]), (2, &b"role:user"[..])];
+    assert_eq!(proof.open(&pk, &wrong, nonce).unwrap_u8(), 0u8);
+    assert_eq!(proof.open(&pk, &disclosed[..1], nonce).unwrap_u8(), 0u8);
+    assert_eq!(proof.open(&pk, &disclosed, b"other nonce").unwrap_u8(), 0u8);
+    let other_pk = MultiPublicKey::from(&MultiSecretKey::new(ATTRIBUTES.len(), &mut rng));
+    assert_eq!(proof.open(&other_pk, &disclosed, nonce).unwrap_u8(), 0u8);
+
+    // nothing and everything disclosed
+    let proof = DisclosureProof::new(&token, &pk, &ATTRIBUTES, &[], nonce, &mut rng).unwrap();
+    let none: [(usize, &[u8]); 0] = [];
+    assert_eq!(proof.open(&pk, &none, nonce).unwrap_u8(), 1u8);
+    let proof =
+        DisclosureProof::new(&token, &pk, &ATTRIBUTES, &[0, 1, 2, 3], nonce, &mut rng).unwrap();
+    let all: Vec<(usize, &[u8])> = ATTRIBUTES.iter().copied().enumerate().collect();
+    assert_eq!(proof.open(&pk, &all, nonce).unwrap_u8(), 1u8);
+
+    // proofs are unlinkable
+    let proof2 =
+        DisclosureProof::new(&token, &pk, &ATTRIBUTES, &[0, 1, 2, 3], nonce, &mut rng).unwrap();
+    assert_ne!(
+        serde_json::to_string(&proof).unwrap(),
+        serde_json::to_string(&proof2).unwrap()
+    );
+    let json = serde_json::to_string(&proof2).unwrap();
+    let proof3: DisclosureProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(proof3.open(&pk, &all, nonce).unwrap_u8(), 1u8);
+
+    assert_eq!(
+        DisclosureProof::new(&token, &pk, &ATTRIBUTES, &[4], nonce, &mut rng).unwrap_err(),
+        Error::InvalidParameters
+    );
+    // forged token
+    let forged = MultiToken::from(SecretKey::new(&mut rng).sign(ID).unwrap());
+    let proof = DisclosureProof::new(&forged, &pk, &ATTRIBUTES, &[1], nonce, &mut rng).unwrap();
+    assert_eq!(proof.open(&pk, &disclosed[..1], nonce).unwrap_u8(), 0u8);
+}
@@ -0,0 +1,140 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, OprfKey, OprfRequest, OprfResponse, Proof, PublicKey, SecretKey};
+
+fn derive(key: &OprfKey, id: &[u8], pin: &[u8], rng: &mut MockRng) -> Blinding {
+    let (request, secrets) = OprfRequest::blind(id, pin, rng).unwrap();
+    let response = key.evaluate(&request).unwrap();
+    response.finalize(&secrets, id, pin).unwrap()
+}
+
+#[test]
+fn oprf_blinding_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let key = OprfKey::new(&mut rng);
+    let token = sk.sign(ID).unwrap();
+
+    let blinding = derive(&key, ID, b"1234", &mut rng);
+    let blinded_token = token - &blinding;
+
+    // the user derives the blinding again for each proof
+    let blinding = derive(&key, ID, b"1234", &mut rng);
+    let proof = Proof::new(&blinded_token, &[blinding], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+
+    let wrong = derive(&key, ID, b"1235", &mut rng);
+    let proof = Proof::new(&blinded_token, &[wrong], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 0u8);
+
+    // the pin alone is not enough without the server
+    let proof = Proof::new(
+        &blinded_token,
+        &[Blinding::new(b"1234")],
+        ID,
+        b"nonce",
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 0u8);
+}
+
+#[test]
+fn oprf_output_is_deterministic() {
+    let mut rng = MockRng::new();
+    let key = OprfKey::hash(b"oprf server seed");
+    let blinding = derive(&key, ID, b"1234", &mut rng);
+
+    // requests are unlinkable but give the same blinding
+    let (r1, s1) = OprfRequest::blind(ID, b"1234", &mut rng).unwrap();
+    let (r2, _) = OprfRequest::blind(ID, b"1234", &mut rng).unwrap();
+    assert_ne!(r1.to_bytes(), r2.to_bytes());
+    let b1 = key
+        .evaluate(&r1)
+        .unwrap()
+        .finalize(&s1, ID, b"1234")
+        .unwrap();
+    assert_eq!(b1.to_bytes(), blinding.to_bytes());
+
+    let other_key = OprfKey::hash(b"another server seed");
+    let other_id = b"another identity";
+    for b in [
+        derive(&other_key, ID, b"1234", &mut rng),
+        derive(&key, other_id, b"1234", &mut rng),
+        derive(&key, ID, b"12345", &mut rng),
+        derive(&key, b"", b"1234", &mut rng),
+    ] {
+        assert_ne!(b.to_bytes(), blinding.to_bytes());
+    }
+    // id and pin are length prefixed
+    assert_ne!(
+        derive(&key, b"ab", b"c", &mut rng).to_bytes(),
+        derive(&key, b"a", b"bc", &mut rng).to_bytes()
+    );
+
+    // finalizing with the wrong pin gives a different blinding
+    let b2 = key
+        .evaluate(&r1)
+        .unwrap()
+        .finalize(&s1, ID, b"0000")
+        .unwrap();
+    assert_ne!(b2.to_bytes(), blinding.to_bytes());
+}
+
+#[test]
+fn oprf_serialization() {
+    let mut rng = MockRng::new();
+    let key = OprfKey::new(&mut rng);
+    let (request, secrets) = OprfRequest::blind(ID, b"1234", &mut rng).unwrap();
+    let response = key.evaluate(&request).unwrap();
+
+    let key2 = OprfKey::try_from_bytes(&key.to_bytes()).unwrap();
+    assert_eq!(key, key2);
+    let request2 = OprfRequest::try_from_bytes(&request.to_bytes()).unwrap();
+    let response2 = key2.evaluate(&request2).unwrap();
+    assert_eq!(response.to_bytes(), response2.to_bytes());
+    let response3 = OprfResponse::try_from_bytes(&response.to_bytes()).unwrap();
+    assert_eq!(
+        response3
+            .finalize(&secrets, ID, b"1234")
+            .unwrap()
+            .to_bytes(),
+        response.finalize(&secrets, ID, b"1234").unwrap().to_bytes()
+    );
+
+    assert_eq!(
+        OprfRequest::try_from_bytes(&[0u8; 47]).unwrap_err(),
+        Error::InvalidLength
+    );
+    assert_eq!(
+        OprfKey::try_from_bytes(&[0xffu8; OprfKey::BYTES]).unwrap_err(),
+        Error::InvalidEncoding
+    );
+}
+
+#[test]
+fn oprf_rejects_identity() {
+    let mut rng = MockRng::new();
+    let key = OprfKey::new(&mut rng);
+    let (request, secrets) = OprfRequest::blind(ID, b"1234", &mut rng).unwrap();
+    assert_eq!(
+        key.evaluate(&OprfRequest::default()).unwrap_err(),
+        Error::IdentityPoint
+    );
+    assert_eq!(
+        OprfKey::default().evaluate(&request).unwrap_err(),
+        Error::IdentityPoint
+    );
+    assert_eq!(
+        OprfResponse::default()
+            .finalize(&secrets, ID, b"1234")
+            .unwrap_err(),
+        Error::IdentityPoint
+    );
+}
@@ -0,0 +1,99 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(feature = "password")]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, PasswordParams, Proof, PublicKey, SecretKey};
+
+// cheap parameters so the tests run quickly
+fn params() -> PasswordParams {
+    PasswordParams::new(64, 1, 1).unwrap()
+}
+
+#[test]
+fn password_blinding_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+
+    let blinding = Blinding::from_password("correct horse", ID, &params()).unwrap();
+    let blinded_token = token - blinding;
+
+    let blinding = Blinding::from_password("correct horse", ID, &params()).unwrap();
+    let proof = Proof::new(&blinded_token, &[blinding], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+
+    let wrong = Blinding::from_password("correct hors", ID, &params()).unwrap();
+    let proof = Proof::new(&blinded_token, &[wrong], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 0u8);
+
+    // the password is stretched not hashed directly
+    assert_ne!(
+        Blinding::new(b"correct horse").to_bytes(),
+        blinding.to_bytes()
+    );
+}
+
+#[test]
+fn password_blinding_normalizes() {
+    // precomposed and decomposed e acute
+    let composed = Blinding::from_password("caf\u{e9}", ID, &params()).unwrap();
+    let decomposed = Blinding::from_password("cafe\u{301}", ID, &params()).unwrap();
+    assert_eq!(composed.to_bytes(), decomposed.to_bytes());
+
+    // compatibility forms like the fi ligature and fullwidth digits
+    let ligature = Blinding::from_password("\u{fb01}\u{ff11}", ID, &params()).unwrap();
+    let plain = Blinding::from_password("fi1", ID, &params()).unwrap();
+    assert_eq!(ligature.to_bytes(), plain.to_bytes());
+
+    assert_eq!(
+        Blinding::from_password("", ID, &params()).unwrap_err(),
+        Error::InvalidParameters
+    );
+}
+
+#[test]
+fn password_blinding_salt_and_params() {
+    let blinding = Blinding::from_password("1234", ID, &params()).unwrap();
+    for b in [
+        Blinding::from_password("1234", b"another identity", &params()).unwrap(),
+        Blinding::from_password("1234", b"", &params()).unwrap(),
+        Blinding::from_password("1234", ID, &PasswordParams::new(128, 1, 1).unwrap()).unwrap(),
+        Blinding::from_password("1234", ID, &PasswordParams::new(64, 2, 1).unwrap()).unwrap(),
+    ] {
+        assert_ne!(b.to_bytes(), blinding.to_bytes());
+    }
+}
+
+#[test]
+fn password_params_serialization() {
+    let params = PasswordParams::default();
+    assert_eq!(params.m_cost(), 19 * 1024);
+    assert_eq!(params.t_cost(), 2);
+    assert_eq!(params.p_cost(), 1);
+    assert_eq!(
+        PasswordParams::try_from_bytes(&params.to_bytes()).unwrap(),
+        params
+    );
+
+    assert_eq!(
+        PasswordParams::new(1, 1, 1).unwrap_err(),
+        Error::InvalidParameters
+    );
+    assert_eq!(
+        PasswordParams::new(64, 0, 1).unwrap_err(),
+        Error::InvalidParameters
+    );
+    assert_eq!(
+        PasswordParams::try_from_bytes(&[0u8; PasswordParams::BYTES]).unwrap_err(),
+        Error::InvalidEncoding
+    );
+    assert_eq!(
+        PasswordParams::try_from_bytes(&[0u8; 8]).unwrap_err(),
+        Error::InvalidLength
+    );
+}
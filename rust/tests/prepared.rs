@@ -0,0 +1,48 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, PreparedPublicKey, Proof, PublicKey, SecretKey};
+use rand_core::RngCore;
+
+#[test]
+fn prepared_matches_open() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let verifier = PreparedPublicKey::new(pk).unwrap();
+    assert_eq!(verifier.public_key(), pk);
+
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded_token = token.clone() - &blinding;
+    let mut nonce = [0u8; 16];
+    rng.fill_bytes(&mut nonce);
+
+    let proof = Proof::new(&blinded_token, &[blinding], ID, nonce, &mut rng).unwrap();
+    assert_eq!(verifier.open(&proof, ID, nonce).unwrap_u8(), 1u8);
+    assert_eq!(verifier.open(&proof, b"wrong id", nonce).unwrap_u8(), 0u8);
+    assert_eq!(verifier.open(&proof, ID, b"wrong nonce").unwrap_u8(), 0u8);
+    assert_eq!(verifier.open(&Proof::default(), ID, nonce).unwrap_u8(), 0u8);
+
+    // missing blinding
+    let proof = Proof::new(&blinded_token, &[], ID, nonce, &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, nonce).unwrap_u8(), 0u8);
+    assert_eq!(verifier.open(&proof, ID, nonce).unwrap_u8(), 0u8);
+
+    assert_eq!(verifier.verify_token(&token, ID).unwrap_u8(), 1u8);
+    assert_eq!(verifier.verify_token(&token, b"wrong id").unwrap_u8(), 0u8);
+    assert_eq!(verifier.verify_token(&blinded_token, ID).unwrap_u8(), 0u8);
+
+    // other key
+    let other = PreparedPublicKey::new(PublicKey::from(&SecretKey::new(&mut rng))).unwrap();
+    assert_eq!(other.verify_token(&token, ID).unwrap_u8(), 0u8);
+}
+
+#[test]
+fn prepared_rejects_invalid_key() {
+    assert!(PreparedPublicKey::new(PublicKey::default()).is_none());
+}
@@ -0,0 +1,90 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{Blinding, Error, Pseudonym, PseudonymProof, PublicKey, SecretKey};
+
+const SCOPE: &[u8] = b"verifier.example";
+
+#[test]
+fn pseudonym_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let blinding = Blinding::new(b"1234");
+    let blinded = &token - &blinding;
+
+    let proof =
+        PseudonymProof::new(&blinded, &[blinding], ID, pk, SCOPE, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, SCOPE, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(proof.try_open(pk, SCOPE, b"nonce"), Ok(()));
+    assert_eq!(proof.pseudonym(), Pseudonym::new(ID, SCOPE).unwrap());
+
+    assert_eq!(
+        proof.try_open(pk, SCOPE, b"other nonce"),
+        Err(Error::VerificationFailed)
+    );
+    assert_eq!(
+        proof.try_open(pk, b"other.example", b"nonce"),
+        Err(Error::VerificationFailed)
+    );
+    let pk2 = PublicKey::from(&SecretKey::new(&mut rng));
+    assert_eq!(proof.open(pk2, SCOPE, b"nonce").unwrap_u8(), 0u8);
+    assert_eq!(
+        proof.try_open(PublicKey::default(), SCOPE, b"nonce"),
+        Err(Error::InvalidPublicKey)
+    );
+
+    // a token for another id
+    let other = sk.sign(b"another identity").unwrap();
+    let proof = PseudonymProof::new(&other, &[], ID, pk, SCOPE, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, SCOPE, b"nonce").unwrap_u8(), 0u8);
+}
+
+#[test]
+fn pseudonyms_are_scoped() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+
+    let p1 = PseudonymProof::new(&token, &[], ID, pk, SCOPE, b"nonce1", &mut rng).unwrap();
+    let p2 = PseudonymProof::new(&token, &[], ID, pk, SCOPE, b"nonce2", &mut rng).unwrap();
+    let p3 =
+        PseudonymProof::new(&token, &[], ID, pk, b"other.example", b"nonce1", &mut rng).unwrap();
+    assert_eq!(p1.pseudonym(), p2.pseudonym());
+    assert_ne!(p1.pseudonym(), p3.pseudonym());
+    assert_ne!(p1.to_bytes()[..96], p2.to_bytes()[..96]);
+    assert_ne!(
+        Pseudonym::new(ID, SCOPE).unwrap(),
+        Pseudonym::new(b"another identity", SCOPE).unwrap()
+    );
+}
+
+#[test]
+fn pseudonym_proof_bytes() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let token = sk.sign(ID).unwrap();
+    let proof = PseudonymProof::new(&token, &[], ID, pk, SCOPE, b"nonce", &mut rng).unwrap();
+
+    let bytes = proof.to_bytes();
+    let proof2 = PseudonymProof::try_from_bytes(&bytes).unwrap();
+    assert_eq!(proof2.to_bytes(), bytes);
+    assert_eq!(proof2.open(pk, SCOPE, b"nonce").unwrap_u8(), 1u8);
+    assert_eq!(
+        PseudonymProof::try_from_bytes(&bytes[1..]).unwrap_err(),
+        Error::InvalidLength
+    );
+
+    let pseudonym = proof.pseudonym();
+    assert_eq!(
+        Pseudonym::try_from_bytes(&pseudonym.to_bytes()).unwrap(),
+        pseudonym
+    );
+}
@@ -0,0 +1,170 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{
+    Accumulator, AccumulatorUpdate, Error, NonMembershipProof, NonMembershipWitness, Proof,
+    PublicKey, RevocationRegistry, SecretKey,
+};
+
+#[test]
+fn revocation_witness_updates() {
+    let mut rng = MockRng::new();
+    let mut registry = RevocationRegistry::new(&mut rng);
+    let pk = registry.public_key();
+
+    let start = registry.accumulator();
+    let mut witness = registry.witness(ID).unwrap();
+    assert_eq!(witness.verify(ID, start, pk).unwrap_u8(), 1u8);
+
+    registry.revoke(b"bobby").unwrap();
+    registry.revoke(b"carol").unwrap();
+    registry.restore(b"bobby").unwrap();
+    registry.revoke(b"david").unwrap();
+    assert!(registry.is_revoked(b"carol"));
+    assert!(!registry.is_revoked(b"bobby"));
+    assert_eq!(registry.revoke(b"carol").unwrap_err(), Error::Revoked);
+    assert_eq!(
+        registry.restore(b"bobby").unwrap_err(),
+        Error::InvalidParameters
+    );
+
+    // Stale witness fails until updated
+    let current = registry.accumulator();
+    assert_eq!(witness.verify(ID, current, pk).unwrap_u8(), 0u8);
+    witness.update(ID, start, registry.history()).unwrap();
+    assert_eq!(witness.verify(ID, current, pk).unwrap_u8(), 1u8);
+    assert_eq!(witness, registry.witness(ID).unwrap());
+
+    // Holder is revoked
+    let update = registry.revoke(ID).unwrap();
+    let before = witness.clone();
+    assert_eq!(
+        witness.update(ID, current, &[update]).unwrap_err(),
+        Error::Revoked
+    );
+    assert_eq!(witness, before);
+    assert_eq!(
+        witness.verify(ID, registry.accumulator(), pk).unwrap_u8(),
+        0u8
+    );
+    assert_eq!(registry.witness(ID).unwrap_err(), Error::Revoked);
+}
+
+#[test]
+fn revocation_proof_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let mut registry = RevocationRegistry::new(&mut rng);
+    let acc_pk = registry.public_key();
+    registry.revoke(b"bobby").unwrap();
+
+    let token = sk.sign(ID).unwrap();
+    let accumulator = registry.accumulator();
+    let witness = registry.witness(ID).unwrap();
+    let nonce = b"revocation_proof_works";
+    let proof = Proof::new(&token, &[], ID, nonce, &mut rng).unwrap();
+    let nm = NonMembershipProof::new(&witness, ID, accumulator, &proof, nonce, &mut rng).unwrap();
+
+    assert_eq!(proof.open(pk, ID, nonce).unwrap_u8(), 1u8);
+    assert_eq!(
+        nm.open(acc_pk, accumulator, ID, &proof, nonce).unwrap_u8(),
+        1u8
+    );
+    assert_eq!(
+        nm.open(acc_pk, accumulator, b"bobby", &proof, nonce)
+            .unwrap_u8(),
+        0u8
+    );
+    assert_eq!(
+        nm.open(acc_pk, accumulator, ID, &proof, b"wrong nonce")
+            .unwrap_u8(),
+        0u8
+    );
+    // bound to the proof
+    let other = Proof::new(&token, &[], ID, nonce, &mut rng).unwrap();
+    assert_eq!(
+        nm.open(acc_pk, accumulator, ID, &other, nonce).unwrap_u8(),
+        0u8
+    );
+    // stale after a revocation
+    registry.revoke(b"carol").unwrap();
+    assert_eq!(
+        nm.open(acc_pk, registry.accumulator(), ID, &proof, nonce)
+            .unwrap_u8(),
+        0u8
+    );
+
+    assert!(registry.witness(b"carol").is_err());
+    assert_eq!(
+        NonMembershipProof::new(
+            &NonMembershipWitness::default(),
+            ID,
+            accumulator,
+            &proof,
+            nonce,
+            &mut rng
+        )
+        .unwrap_err(),
+        Error::Revoked
+    );
+}
+
+#[test]
+fn revocation_serialization() {
+    let mut rng = MockRng::new();
+    let mut registry = RevocationRegistry::new(&mut rng);
+    let update = registry.revoke(b"bobby").unwrap();
+    let witness = registry.witness(ID).unwrap();
+
+    assert_eq!(
+        AccumulatorUpdate::try_from_bytes(&update.to_bytes()),
+        Ok(update)
+    );
+    assert!(update.is_revocation());
+    assert_eq!(
+        NonMembershipWitness::try_from_bytes(&witness.to_bytes()),
+        Ok(witness.clone())
+    );
+    assert_eq!(
+        Accumulator::try_from_bytes(&registry.accumulator().to_bytes()),
+        Ok(registry.accumulator())
+    );
+
+    let mut bytes = update.to_bytes();
+    bytes[32] = 2;
+    assert_eq!(
+        AccumulatorUpdate::try_from_bytes(&bytes),
+        Err(Error::InvalidEncoding)
+    );
+
+    let sk = SecretKey::new(&mut rng);
+    let token = sk.sign(ID).unwrap();
+    let proof = Proof::new(&token, &[], ID, b"nonce", &mut rng).unwrap();
+    let nm = NonMembershipProof::new(
+        &witness,
+        ID,
+        registry.accumulator(),
+        &proof,
+        b"nonce",
+        &mut rng,
+    )
+    .unwrap();
+    let nm2 = NonMembershipProof::try_from_bytes(&nm.to_bytes()).unwrap();
+    assert_eq!(
+        nm2.open(
+            registry.public_key(),
+            registry.accumulator(),
+            ID,
+            &proof,
+            b"nonce"
+        )
+        .unwrap_u8(),
+        1u8
+    );
+}
@@ -0,0 +1,108 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(any(feature = "alloc", feature = "std"))]
+mod common;
+
+use common::{MockRng, ID};
+use oberon::{PartialToken, PublicKey, PublicKeyShare, SecretKey, SecretKeyShare, Token};
+
+#[test]
+fn threshold_token_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let shares = SecretKeyShare::split(&sk, 3, 5, &mut rng).unwrap();
+    assert_eq!(shares.len(), 5);
+
+    let partials: Vec<PartialToken> = shares.iter().map(|s| s.sign(ID).unwrap()).collect();
+    for (share, partial) in shares.iter().zip(partials.iter()) {
+        let pk_share = PublicKeyShare::from(share);
+        assert_eq!(partial.verify(&pk_share, ID).unwrap_u8(), 1u8);
+        assert_eq!(
+            partial.verify(&pk_share, b"wrong identity").unwrap_u8(),
+            0u8
+        );
+    }
+
+    let expected = sk.sign(ID).unwrap();
+    for subset in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [2, 3, 4]] {
+        let subset: Vec<PartialToken> = subset.iter().map(|i| partials[*i].clone()).collect();
+        let token = PartialToken::combine(&subset).unwrap();
+        assert_eq!(token, expected);
+        assert_eq!(token.verify(pk, ID).unwrap_u8(), 1u8);
+    }
+
+    // Too few partial tokens
+    let token = PartialToken::combine(&partials[..2]).unwrap();
+    assert_eq!(token.verify(pk, ID).unwrap_u8(), 0u8);
+
+    // Duplicate partial tokens
+    assert!(PartialToken::combine(&[
+        partials[0].clone(),
+        partials[0].clone(),
+        partials[1].clone()
+    ])
+    .is_none());
+}
+
+#[test]
+fn threshold_identify_bad_partial() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let shares = SecretKeyShare::split(&sk, 2, 3, &mut rng).unwrap();
+    let pk_shares: Vec<PublicKeyShare> = shares.iter().map(PublicKeyShare::from).collect();
+
+    // share 2 signs the wrong id
+    let partials = [
+        shares[0].sign(ID).unwrap(),
+        shares[1].sign(b"wrong").unwrap(),
+    ];
+    assert_eq!(partials[0].verify(&pk_shares[0], ID).unwrap_u8(), 1u8);
+    assert_eq!(partials[1].verify(&pk_shares[1], ID).unwrap_u8(), 0u8);
+    // partial tokens don't verify under another share's key
+    assert_eq!(partials[0].verify(&pk_shares[1], ID).unwrap_u8(), 0u8);
+}
+
+#[test]
+fn threshold_reconstruct_keys() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let shares = SecretKeyShare::split(&sk, 2, 4, &mut rng).unwrap();
+    let pk_shares: Vec<PublicKeyShare> = shares.iter().map(PublicKeyShare::from).collect();
+
+    assert_eq!(SecretKeyShare::combine(&shares[1..3]).unwrap(), sk);
+    assert_eq!(PublicKeyShare::combine(&pk_shares[2..]).unwrap(), pk);
+    assert!(SecretKeyShare::combine(&[]).is_none());
+    assert!(PublicKeyShare::combine(&[]).is_none());
+
+    assert!(SecretKeyShare::split(&sk, 1, 4, &mut rng).is_none());
+    assert!(SecretKeyShare::split(&sk, 5, 4, &mut rng).is_none());
+}
+
+#[test]
+fn threshold_serialization() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let shares = SecretKeyShare::split(&sk, 2, 3, &mut rng).unwrap();
+    let share = &shares[1];
+    let pk_share = PublicKeyShare::from(share);
+    let partial = share.sign(ID).unwrap();
+
+    let share2 = SecretKeyShare::from_bytes(&share.to_bytes()).unwrap();
+    assert_eq!(&share2, share);
+    let pk_share2 = PublicKeyShare::from_bytes(&pk_share.to_bytes()).unwrap();
+    assert_eq!(pk_share2, pk_share);
+    let partial2 = PartialToken::from_bytes(&partial.to_bytes()).unwrap();
+    assert_eq!(partial2, partial);
+    assert_eq!(partial2.identifier(), 2);
+
+    let mut bytes = partial.to_bytes();
+    bytes[..2].copy_from_slice(&[0u8; 2]);
+    assert_eq!(PartialToken::from_bytes(&bytes).is_none().unwrap_u8(), 1u8);
+
+    let combined = PartialToken::combine(&[partial2, shares[0].sign(ID).unwrap()]).unwrap();
+    assert_eq!(combined, Token::new(&sk, ID).unwrap());
+}
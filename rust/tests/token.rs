@@ -5,7 +5,7 @@
 mod common;
 
 use common::{MockRng, ID};
-use oberon::{PublicKey, SecretKey, Token};
+use oberon::{Blinding, Error, Proof, PublicKey, SecretKey, Token};
 
 #[test]
 fn valid_token() {
@@ -34,3 +34,54 @@ fn token_from_bytes() {
 
     println!("{}", hex::encode(&serde_bare::to_vec(&exp_token).unwrap()));
 }
+
+#[test]
+fn rebind_token() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let pin = Blinding::new(b"1234");
+    let device = Blinding::new(b"device key");
+    let token = sk.sign(ID).unwrap() - pin - device;
+
+    // change the pin keeping the device factor
+    let new_pin = Blinding::new(b"5678");
+    let rebound = token
+        .rebind(&[pin, device], &[new_pin, device], pk, ID)
+        .unwrap();
+    let proof = Proof::new(&rebound, &[new_pin, device], ID, b"nonce", &mut rng).unwrap();
+    assert_eq!(proof.open(pk, ID, b"nonce").unwrap_u8(), 1u8);
+
+    // a typo in the old pin or a missing factor is an error
+    assert_eq!(
+        token
+            .rebind(
+                &[Blinding::new(b"1235"), device],
+                &[new_pin, device],
+                pk,
+                ID
+            )
+            .unwrap_err(),
+        Error::VerificationFailed
+    );
+    assert_eq!(
+        token.rebind(&[pin], &[new_pin], pk, ID).unwrap_err(),
+        Error::VerificationFailed
+    );
+    assert_eq!(
+        token
+            .rebind(&[pin, device], &[new_pin], pk, b"wrong identity")
+            .unwrap_err(),
+        Error::VerificationFailed
+    );
+
+    // factors can be added and removed
+    let unblinded = token.rebind(&[pin, device], &[], pk, ID).unwrap();
+    assert_eq!(unblinded.verify(pk, ID).unwrap_u8(), 1u8);
+    let reblinded = unblinded.rebind(&[], &[pin], pk, ID).unwrap();
+    assert_eq!(reblinded, unblinded - pin);
+    assert_eq!(
+        Token::default().rebind(&[], &[pin], pk, ID).unwrap_err(),
+        Error::IdentityPoint
+    );
+}
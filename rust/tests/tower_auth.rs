@@ -0,0 +1,187 @@
+/*
+    Copyright Michael Lodder. All Rights Reserved.
+    SPDX-License-Identifier: Apache-2.0
+*/
+#![cfg(feature = "tower")]
+mod common;
+
+use common::{MockRng, ID};
+use core::{
+    convert::Infallible,
+    future::{ready, Future, Ready},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+use http::{header, request::Parts, Request, Response, StatusCode};
+use oberon::{
+    ChallengeKey, ChallengePolicy, Error, HttpAuthorization, HttpComponent, Keyring,
+    MemoryReplayCache, OberonAuthLayer, PublicKey, SecretKey, Token, VerifiedIdentity,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const NOW: u64 = 1_700_000_000;
+
+// echoes the verified id back as the body
+#[derive(Clone)]
+struct Echo;
+
+impl Service<Request<()>> for Echo {
+    type Response = Response<Vec<u8>>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<()>) -> Self::Future {
+        let identity = request.extensions().get::<VerifiedIdentity>().unwrap();
+        ready(Ok(Response::new(identity.id().to_vec())))
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn request(token: &Token, nonce: &[u8], rng: &mut MockRng) -> Request<()> {
+    let (mut parts, _) = Request::post("/v1/payments")
+        .header(header::HOST, "api.example.com")
+        .body(())
+        .unwrap()
+        .into_parts();
+    let components = [HttpComponent::Method, HttpComponent::TargetUri];
+    let auth = HttpAuthorization::new(token, &[], ID, &parts, &components, nonce, rng)
+        .unwrap()
+        .with_key_id(PublicKey::from(&SecretKey::hash(b"issuer")).key_id());
+    parts
+        .headers
+        .insert(header::AUTHORIZATION, auth.to_header_value());
+    Request::from_parts(parts, ())
+}
+
+fn error(response: &Response<Vec<u8>>) -> Error {
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Oberon");
+    *response.extensions().get::<Error>().unwrap()
+}
+
+#[test]
+fn tower_auth_works() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::hash(b"issuer");
+    let token = sk.sign(ID).unwrap();
+    let challenge_key = ChallengeKey::new(&mut rng);
+    let layer = OberonAuthLayer::new(
+        PublicKey::from(&sk),
+        ChallengePolicy::new(challenge_key.clone(), 60, MemoryReplayCache::new()),
+    )
+    .with_clock(|| NOW + 5);
+    let mut service = layer.layer(Echo);
+
+    let challenge = challenge_key.issue(NOW, &mut rng);
+    let response =
+        block_on(service.call(request(&token, &challenge.to_bytes(), &mut rng))).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), ID);
+
+    // the challenge can only be used once even by a clone of the service
+    let mut other = service.clone();
+    let response = block_on(other.call(request(&token, &challenge.to_bytes(), &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::Replayed);
+
+    let stale = challenge_key.issue(NOW - 120, &mut rng);
+    let response = block_on(service.call(request(&token, &stale.to_bytes(), &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::Expired);
+
+    let response = block_on(service.call(request(&token, b"not a challenge", &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::InvalidLength);
+
+    let response = block_on(service.call(Request::new(()))).unwrap();
+    assert_eq!(error(&response), Error::InvalidParameters);
+
+    // a bad proof does not use up the challenge
+    let challenge = challenge_key.issue(NOW, &mut rng);
+    let forged = SecretKey::new(&mut rng).sign(ID).unwrap();
+    let response =
+        block_on(service.call(request(&forged, &challenge.to_bytes(), &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::VerificationFailed);
+    let response =
+        block_on(service.call(request(&token, &challenge.to_bytes(), &mut rng))).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn tower_auth_resolvers() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::hash(b"issuer");
+    let token = sk.sign(ID).unwrap();
+    let any_nonce = |_: &[u8], _: u64| Ok(());
+
+    let mut keyring = Keyring::new(60);
+    keyring.add(PublicKey::from(&sk), NOW - 10);
+    let mut service = OberonAuthLayer::new(keyring.clone(), any_nonce)
+        .with_clock(|| NOW)
+        .layer(Echo);
+    let response = block_on(service.call(request(&token, b"nonce", &mut rng))).unwrap();
+    assert_eq!(response.body(), ID);
+
+    keyring
+        .retire(&PublicKey::from(&sk).key_id(), NOW - 120)
+        .unwrap();
+    let mut service = OberonAuthLayer::new(keyring, any_nonce)
+        .with_clock(|| NOW)
+        .layer(Echo);
+    let response = block_on(service.call(request(&token, b"nonce", &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::Expired);
+
+    let mut service = OberonAuthLayer::new(Keyring::new(60), any_nonce).layer(Echo);
+    let response = block_on(service.call(request(&token, b"nonce", &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::UnknownKey);
+
+    let resolver = |auth: &HttpAuthorization, _: u64| {
+        if auth.id() == ID {
+            Ok(PublicKey::from(&SecretKey::hash(b"issuer")))
+        } else {
+            Err(Error::UnknownKey)
+        }
+    };
+    let mut service = OberonAuthLayer::new(resolver, any_nonce).layer(Echo);
+    let response = block_on(service.call(request(&token, b"nonce", &mut rng))).unwrap();
+    assert_eq!(response.body(), ID);
+
+    // the proof does not cover the content digest
+    let mut service = OberonAuthLayer::new(PublicKey::from(&sk), any_nonce)
+        .require(&[HttpComponent::ContentDigest])
+        .layer(Echo);
+    let response = block_on(service.call(request(&token, b"nonce", &mut rng))).unwrap();
+    assert_eq!(error(&response), Error::InvalidParameters);
+}
+
+#[test]
+fn tower_auth_checks_request() {
+    let mut rng = MockRng::new();
+    let sk = SecretKey::hash(b"issuer");
+    let token = sk.sign(ID).unwrap();
+    let service = OberonAuthLayer::new(PublicKey::from(&sk), |_: &[u8], _: u64| Ok(())).layer(Echo);
+
+    let (mut parts, body): (Parts, ()) = request(&token, b"nonce", &mut rng).into_parts();
+    parts.uri = "/v1/refunds".parse().unwrap();
+    let (_, result) = service.authenticate(Request::from_parts(parts, body));
+    assert_eq!(result.unwrap_err(), Error::VerificationFailed);
+
+    let (_, result) = service.authenticate(request(&token, b"nonce", &mut rng));
+    let identity = result.unwrap();
+    assert_eq!(identity.id(), ID);
+    assert_eq!(
+        identity.public_key().to_bytes(),
+        PublicKey::from(&sk).to_bytes()
+    );
+}